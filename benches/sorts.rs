@@ -0,0 +1,80 @@
+//! Hybrid sorts against the standard library: `cargo bench --bench sorts`
+
+#![feature(test)]
+
+extern crate test;
+
+use rad::algorithms::introsort::introsort;
+use rad::algorithms::pdqsort::pdqsort;
+use rad::rand::Wyhash64RNG;
+use test::Bencher;
+
+const N: u64 = 10_000;
+
+fn random_input() -> Vec<u64> {
+    let mut rng = Wyhash64RNG::from_seed(123);
+
+    (0..N).map(|_| rng.gen_in_range(0..u64::MAX)).collect()
+}
+
+fn few_unique_input() -> Vec<u64> {
+    let mut rng = Wyhash64RNG::from_seed(123);
+
+    (0..N).map(|_| rng.gen_in_range(0..8)).collect()
+}
+
+fn ascending_input() -> Vec<u64> {
+    (0..N).collect()
+}
+
+fn descending_input() -> Vec<u64> {
+    (0..N).rev().collect()
+}
+
+fn bench(b: &mut Bencher, input: fn() -> Vec<u64>, sort: fn(&mut [u64])) {
+    let v = input();
+
+    b.iter(|| {
+        let mut a = v.clone();
+        sort(&mut a);
+        a
+    });
+}
+
+macro_rules! benches {
+    ($($name:ident: $input:ident;)*) => {
+        mod introsort_benches {
+            use super::*;
+
+            $(#[bench]
+            fn $name(b: &mut Bencher) {
+                bench(b, $input, introsort);
+            })*
+        }
+
+        mod pdqsort_benches {
+            use super::*;
+
+            $(#[bench]
+            fn $name(b: &mut Bencher) {
+                bench(b, $input, pdqsort);
+            })*
+        }
+
+        mod std_sort_unstable {
+            use super::*;
+
+            $(#[bench]
+            fn $name(b: &mut Bencher) {
+                bench(b, $input, <[u64]>::sort_unstable);
+            })*
+        }
+    };
+}
+
+benches! {
+    random: random_input;
+    few_unique: few_unique_input;
+    ascending: ascending_input;
+    descending: descending_input;
+}
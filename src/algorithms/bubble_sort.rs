@@ -1,11 +1,25 @@
+use std::cmp::Ordering;
+
 pub fn bubble_sort<T: PartialOrd>(a: &mut [T]) {
+    bubble_sort_impl(a, &mut T::lt);
+}
+
+pub fn bubble_sort_by<T, F: FnMut(&T, &T) -> Ordering>(a: &mut [T], mut cmp: F) {
+    bubble_sort_impl(a, &mut |x, y| cmp(x, y) == Ordering::Less);
+}
+
+pub fn bubble_sort_by_key<T, K: Ord, F: FnMut(&T) -> K>(a: &mut [T], mut f: F) {
+    bubble_sort_impl(a, &mut |x, y| f(x) < f(y));
+}
+
+fn bubble_sort_impl<T, F: FnMut(&T, &T) -> bool>(a: &mut [T], is_less: &mut F) {
     if a.len() <= 1 {
         return;
     }
 
     for i in 0..a.len() - 1 {
         for j in ((i + 1)..a.len()).rev() {
-            if a[j] < a[j - 1] {
+            if is_less(&a[j], &a[j - 1]) {
                 a.swap(j, j - 1);
             }
         }
@@ -16,3 +30,13 @@ pub fn bubble_sort<T: PartialOrd>(a: &mut [T]) {
 fn test() {
     crate::tests::test_sort(bubble_sort);
 }
+
+#[test]
+fn by() {
+    crate::tests::test_stable_sort_records(|a| bubble_sort_by(a, |x, y| x.key.cmp(&y.key)));
+}
+
+#[test]
+fn by_key() {
+    crate::tests::test_stable_sort_records(|a| bubble_sort_by_key(a, |r| r.key));
+}
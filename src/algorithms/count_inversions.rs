@@ -1,20 +1,152 @@
+use crate::data_structures::fenwick_tree::FenwickTree;
+
 pub fn count_inversions<T: Copy + PartialOrd>(a: &mut [T]) -> usize {
-    count_inv_rec(a, 0, a.len() - 1)
+    if a.len() <= 1 {
+        return 0;
+    }
+
+    count_inv_rec(a, 0, a.len() - 1, &mut T::le, &mut |_, _| {})
+}
+
+/// Same as `count_inversions`, leaving `a` untouched and not requiring `T: Copy`. Sorts the
+/// indices of the elements instead.
+pub fn count_inversions_nondestructive<T: PartialOrd>(a: &[T]) -> usize {
+    if a.len() <= 1 {
+        return 0;
+    }
+
+    let mut idx: Vec<usize> = (0..a.len()).collect();
+    let last = idx.len() - 1;
+
+    count_inv_rec(
+        &mut idx,
+        0,
+        last,
+        &mut |&i, &j| a[i] <= a[j],
+        &mut |_, _| {},
+    )
 }
 
-fn count_inv_rec<T: Copy + PartialOrd>(a: &mut [T], l: usize, h: usize) -> usize {
+/// The number of inversions together with at most `limit` of the pairs `(i, j)` such that
+/// `i < j` and `a[i] > a[j]`, sorted. Takes O(n log n + limit) time; if there are more pairs than
+/// `limit`, which of them are listed is unspecified.
+pub fn inversion_pairs<T: PartialOrd>(a: &[T], limit: usize) -> (usize, Vec<(usize, usize)>) {
+    let mut pairs = Vec::new();
+
+    if a.len() <= 1 {
+        return (0, pairs);
+    }
+
+    let mut idx: Vec<usize> = (0..a.len()).collect();
+    let last = idx.len() - 1;
+
+    // every element still in the left half forms an inversion with the one taken from the right
+    let count = count_inv_rec(
+        &mut idx,
+        0,
+        last,
+        &mut |&i, &j| a[i] <= a[j],
+        &mut |left, j| {
+            for &i in left.iter().take(limit - pairs.len()) {
+                pairs.push((i, j));
+            }
+        },
+    );
+
+    pairs.sort_unstable();
+
+    (count, pairs)
+}
+
+/// Number of pairs of elements that the permutations `p` and `q` of `0..n` put in different
+/// orders, i.e. the number of adjacent swaps needed to turn one into the other.
+pub fn kendall_tau_distance(p: &[usize], q: &[usize]) -> usize {
+    assert_eq!(
+        p.len(),
+        q.len(),
+        "permutations should be of the same length"
+    );
+
+    assert_permutation(p);
+
+    let pos = inverse_permutation(q);
+    let mut seq: Vec<usize> = p.iter().map(|&x| pos[x]).collect();
+
+    count_inversions(&mut seq)
+}
+
+fn assert_permutation(p: &[usize]) {
+    let mut seen = vec![false; p.len()];
+
+    for &x in p {
+        assert!(x < p.len() && !seen[x], "should be a permutation of 0..n");
+        seen[x] = true;
+    }
+}
+
+fn inverse_permutation(p: &[usize]) -> Vec<usize> {
+    assert_permutation(p);
+
+    let mut pos = vec![0; p.len()];
+
+    for (i, &x) in p.iter().enumerate() {
+        pos[x] = i;
+    }
+
+    pos
+}
+
+/// Count inversions with a Fenwick tree over the ranks of the values: scanning from the right,
+/// each element adds the number of smaller elements already seen.
+pub fn count_inversions_fenwick<T: Ord>(a: &[T]) -> usize {
+    let mut values: Vec<&T> = a.iter().collect();
+
+    values.sort_unstable();
+    values.dedup();
+
+    let mut seen = FenwickTree::new(values.len());
+    let mut inv = 0;
+
+    for x in a.iter().rev() {
+        let rank = values.binary_search(&x).expect("value should be present");
+
+        inv += seen.prefix_sum(rank);
+        seen.add(rank, 1);
+    }
+
+    inv
+}
+
+fn count_inv_rec<T, F, G>(a: &mut [T], l: usize, h: usize, le: &mut F, on_inv: &mut G) -> usize
+where
+    T: Copy,
+    F: FnMut(&T, &T) -> bool,
+    G: FnMut(&[T], T),
+{
     if l == h {
         return 0;
     }
 
     let m = l + (h - l) / 2;
-    let left = count_inv_rec(a, l, m);
-    let right = count_inv_rec(a, m + 1, h);
+    let left = count_inv_rec(a, l, m, le, on_inv);
+    let right = count_inv_rec(a, m + 1, h, le, on_inv);
 
-    left + right + count_inv_in_subrange(a, l, m, h)
+    left + right + count_inv_in_subrange(a, l, m, h, le, on_inv)
 }
 
-fn count_inv_in_subrange<T: Copy + PartialOrd>(a: &mut [T], l: usize, m: usize, h: usize) -> usize {
+fn count_inv_in_subrange<T, F, G>(
+    a: &mut [T],
+    l: usize,
+    m: usize,
+    h: usize,
+    le: &mut F,
+    on_inv: &mut G,
+) -> usize
+where
+    T: Copy,
+    F: FnMut(&T, &T) -> bool,
+    G: FnMut(&[T], T),
+{
     let left = a[l..=m].to_vec();
     let right = a[m + 1..=h].to_vec();
 
@@ -25,11 +157,12 @@ fn count_inv_in_subrange<T: Copy + PartialOrd>(a: &mut [T], l: usize, m: usize,
     let mut k = l;
 
     while i < left.len() && j < right.len() {
-        if left[i] <= right[j] {
+        if le(&left[i], &right[j]) {
             a[k] = left[i];
             i += 1;
         } else {
             a[k] = right[j];
+            on_inv(&left[i..], right[j]);
             j += 1;
             inv += left.len() - i;
         }
@@ -51,9 +184,118 @@ fn count_inv_in_subrange<T: Copy + PartialOrd>(a: &mut [T], l: usize, m: usize,
     inv
 }
 
-#[test]
-fn test() {
-    let mut v = vec![2, 3, 8, 6, 1];
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rand::Wyhash64RNG;
+
+    fn naive_pairs<T: PartialOrd>(a: &[T]) -> Vec<(usize, usize)> {
+        let mut pairs = vec![];
+
+        for i in 0..a.len() {
+            for j in i + 1..a.len() {
+                if a[i] > a[j] {
+                    pairs.push((i, j));
+                }
+            }
+        }
+
+        pairs
+    }
+
+    fn random_arrays() -> Vec<Vec<i64>> {
+        let mut rng = Wyhash64RNG::from_seed(123);
+
+        [0, 1, 2, 10, 100, 300]
+            .into_iter()
+            .map(|n| (0..n).map(|_| rng.gen_in_range_i64(-20..20)).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test() {
+        let mut v = vec![2, 3, 8, 6, 1];
+
+        assert_eq!(count_inversions(&mut v), 5);
+    }
+
+    #[test]
+    fn nondestructive() {
+        for a in random_arrays() {
+            let expected = naive_pairs(&a).len();
+            let copy = a.clone();
+
+            assert_eq!(expected, count_inversions_nondestructive(&a));
+            assert_eq!(copy, a);
+            assert_eq!(expected, count_inversions_fenwick(&a));
+            assert_eq!(expected, count_inversions(&mut a.clone()));
+        }
+
+        let words = ["d", "a", "c", "b"].map(String::from);
+        assert_eq!(4, count_inversions_nondestructive(&words));
+        assert_eq!(4, count_inversions_fenwick(&words));
+    }
+
+    #[test]
+    fn pairs() {
+        for a in random_arrays() {
+            let expected = naive_pairs(&a);
+            let (count, all) = inversion_pairs(&a, usize::MAX);
+
+            assert_eq!(expected.len(), count);
+            assert_eq!(expected, all);
+
+            let (count, some) = inversion_pairs(&a, 7);
+
+            assert_eq!(expected.len(), count);
+            assert_eq!(expected.len().min(7), some.len());
+            assert!(some.iter().all(|p| expected.binary_search(p).is_ok()));
+        }
+
+        assert_eq!((2, vec![(0, 2), (1, 2)]), inversion_pairs(&[2, 3, 1], 10));
+    }
+
+    #[test]
+    fn kendall_tau() {
+        assert_eq!(0, kendall_tau_distance(&[], &[]));
+        assert_eq!(0, kendall_tau_distance(&[2, 0, 1], &[2, 0, 1]));
+        assert_eq!(3, kendall_tau_distance(&[0, 1, 2], &[2, 1, 0]));
+        assert_eq!(5, kendall_tau_distance(&[0, 3, 1, 2, 4], &[1, 2, 0, 4, 3]));
+
+        let mut rng = Wyhash64RNG::from_seed(123);
+        let mut p: Vec<usize> = (0..50).collect();
+        let mut q = p.clone();
 
-    assert_eq!(count_inversions(&mut v), 5);
+        crate::tests::shuffle(&mut p);
+
+        for i in (1..q.len()).rev() {
+            q.swap(i, rng.gen_in_range(0..i as u64 + 1) as usize);
+        }
+
+        // pairs ordered differently by p and q
+        let pos_p: Vec<usize> = (0..50)
+            .map(|x| p.iter().position(|&y| y == x).unwrap())
+            .collect();
+        let pos_q: Vec<usize> = (0..50)
+            .map(|x| q.iter().position(|&y| y == x).unwrap())
+            .collect();
+        let mut expected = 0;
+
+        for x in 0..50 {
+            for y in x + 1..50 {
+                if (pos_p[x] < pos_p[y]) != (pos_q[x] < pos_q[y]) {
+                    expected += 1;
+                }
+            }
+        }
+
+        assert_eq!(expected, kendall_tau_distance(&p, &q));
+        assert_eq!(expected, kendall_tau_distance(&q, &p));
+    }
+
+    #[test]
+    #[should_panic(expected = "permutation")]
+    fn not_a_permutation() {
+        kendall_tau_distance(&[0, 1, 1], &[0, 1, 2]);
+    }
 }
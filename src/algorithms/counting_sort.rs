@@ -1,3 +1,5 @@
+use crate::algorithms::merge_sort::apply_permutation;
+
 pub fn counting_sort(xs: &mut [usize]) {
     if xs.len() <= 1 {
         return;
@@ -52,6 +54,50 @@ pub fn counting_sort_2(xs: &mut [usize]) {
     }
 }
 
+/// Stable counting sort of arbitrary elements by a small integer key. Uses O(n + k) extra space
+/// where k is the range of the keys.
+pub fn counting_sort_by_key<T, F: FnMut(&T) -> usize>(xs: &mut [T], key: F) {
+    let keys: Vec<usize> = xs.iter().map(key).collect();
+
+    let Some((min, max)) = find_min_max(&keys) else {
+        return;
+    };
+
+    let idx: Vec<usize> = (0..xs.len()).collect();
+    let mut out = vec![0; xs.len()];
+
+    counting_pass(&idx, &mut out, max - min + 1, |i| keys[i] - min);
+    apply_permutation(xs, &mut out);
+}
+
+/// Stably distribute the indices in `idx` into `out` by `digit(i)`, which must be less than
+/// `buckets`. Returns the end of every bucket in `out`.
+pub(crate) fn counting_pass(
+    idx: &[usize],
+    out: &mut [usize],
+    buckets: usize,
+    mut digit: impl FnMut(usize) -> usize,
+) -> Vec<usize> {
+    let mut c = vec![0; buckets + 1];
+
+    for &i in idx {
+        c[digit(i) + 1] += 1;
+    }
+
+    for d in 1..=buckets {
+        c[d] += c[d - 1];
+    }
+
+    for &i in idx {
+        let d = digit(i);
+        out[c[d]] = i;
+        c[d] += 1;
+    }
+
+    c.pop();
+    c
+}
+
 fn find_min_max<T: PartialOrd + Copy>(xs: &[T]) -> Option<(T, T)> {
     let (mut min, mut max, offset) = match xs.len() {
         0 => return None,
@@ -134,6 +180,22 @@ mod tests {
         single_test(counting_sort_2);
     }
 
+    #[test]
+    fn by_key() {
+        tests::test_stable_sort_records(|a| {
+            counting_sort_by_key(a, |r| usize::try_from(r.key).unwrap());
+        });
+    }
+
+    #[test]
+    fn by_key_offset() {
+        let mut words = ["ccc", "a", "bb", "dd", "e", "fff"];
+
+        counting_sort_by_key(&mut words, |w| w.len() + 1000);
+
+        assert_eq!(["a", "e", "bb", "dd", "ccc", "fff"], words);
+    }
+
     #[test]
     fn min_max() {
         let len = 1000;
@@ -1,51 +1,67 @@
-use std::mem::swap;
+use std::cmp::Ordering;
+use std::mem::{self, ManuallyDrop};
+use std::ptr;
 
-#[allow(clippy::needless_range_loop)]
-pub fn cycle_sort<T: PartialOrd + Copy>(arr: &mut [T]) {
-    if arr.len() <= 1 {
-        return;
+pub fn cycle_sort<T: PartialOrd>(arr: &mut [T]) {
+    cycle_sort_impl(arr, &mut T::lt);
+}
+
+pub fn cycle_sort_by<T, F: FnMut(&T, &T) -> Ordering>(arr: &mut [T], mut cmp: F) {
+    cycle_sort_impl(arr, &mut |a, b| cmp(a, b) == Ordering::Less);
+}
+
+pub fn cycle_sort_by_key<T, K: Ord, F: FnMut(&T) -> K>(arr: &mut [T], mut f: F) {
+    cycle_sort_impl(arr, &mut |a, b| f(a) < f(b));
+}
+
+// The element being placed is held outside the slice, leaving a hole at `lo`, so that every
+// element is written exactly once, straight into its final position. Should `is_less` panic, the
+// hole is filled with the element in hand, leaving `arr` a permutation of its input.
+struct Hole<'a, T> {
+    arr: &'a mut [T],
+    lo: usize,
+    x: ManuallyDrop<T>,
+}
+
+impl<T> Drop for Hole<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: `arr[lo]` was moved out, so it is overwritten without being dropped, and `x`
+        // isn't used again
+        unsafe { ptr::write(&mut self.arr[self.lo], ManuallyDrop::take(&mut self.x)) }
     }
+}
 
-    let n = arr.len();
+// Where `x` belongs among `arr[lo..]`, ignoring `arr[lo]` itself
+fn position<T, F: FnMut(&T, &T) -> bool>(arr: &[T], lo: usize, x: &T, is_less: &mut F) -> usize {
+    lo + arr[lo + 1..].iter().filter(|&y| is_less(y, x)).count()
+}
 
-    for lo in 0..n - 1 {
-        let mut x = arr[lo];
-        let mut idx = lo;
+fn cycle_sort_impl<T, F: FnMut(&T, &T) -> bool>(arr: &mut [T], is_less: &mut F) {
+    let n = arr.len();
 
-        for i in lo + 1..n {
-            if arr[i] < x {
-                idx += 1;
-            }
-        }
+    for lo in 0..n.saturating_sub(1) {
+        let mut idx = position(arr, lo, &arr[lo], is_less);
 
         if idx == lo {
             continue;
         }
 
-        while x == arr[idx] {
-            idx += 1;
-        }
-
-        if idx != lo {
-            swap(&mut arr[idx], &mut x);
-        }
+        // SAFETY: the hole writes an element back to `arr[lo]` however the cycle ends
+        let x = ManuallyDrop::new(unsafe { ptr::read(&arr[lo]) });
+        let mut hole = Hole {
+            arr: &mut *arr,
+            lo,
+            x,
+        };
 
         while idx != lo {
-            idx = lo;
-
-            for i in lo + 1..n {
-                if arr[i] < x {
-                    idx += 1;
-                }
-            }
-
-            while x == arr[idx] {
+            while !is_less(&hole.x, &hole.arr[idx]) && !is_less(&hole.arr[idx], &hole.x) {
                 idx += 1;
             }
 
-            if x != arr[idx] {
-                swap(&mut arr[idx], &mut x);
-            }
+            mem::swap(&mut hole.arr[idx], &mut *hole.x);
+
+            idx = position(hole.arr, lo, &hole.x, is_less);
         }
     }
 }
@@ -54,3 +70,13 @@ pub fn cycle_sort<T: PartialOrd + Copy>(arr: &mut [T]) {
 fn test() {
     crate::tests::test_sort(cycle_sort);
 }
+
+#[test]
+fn by() {
+    crate::tests::test_sort_records(|a| cycle_sort_by(a, |x, y| x.key.cmp(&y.key)));
+}
+
+#[test]
+fn by_key() {
+    crate::tests::test_sort_records(|a| cycle_sort_by_key(a, |r| r.key));
+}
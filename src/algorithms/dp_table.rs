@@ -0,0 +1,246 @@
+//! Building blocks for dynamic programming: memoized recursion for top-down solutions, and tables
+//! holding a value and a back-pointer per subproblem for bottom-up ones, from which an optimal
+//! solution is rebuilt by following the back-pointers.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Index;
+
+use crate::data_structures::array_2d::Array2D;
+
+/// A recursive function that remembers its results. The function gets a handle to call itself
+/// through, so that the recursive calls are memoized too. The recursion is not turned into a
+/// loop, so deep recursions can still overflow the stack.
+pub struct Memo<K, V, F> {
+    cache: HashMap<K, V>,
+    f: F,
+}
+
+impl<K, V, F> Memo<K, V, F>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+    F: Fn(&mut dyn FnMut(K) -> V, K) -> V,
+{
+    pub fn new(f: F) -> Self {
+        Self {
+            cache: HashMap::new(),
+            f,
+        }
+    }
+
+    pub fn get(&mut self, key: K) -> V {
+        lookup(&mut self.cache, &self.f, key)
+    }
+
+    pub fn cached(&self, key: &K) -> Option<&V> {
+        self.cache.get(key)
+    }
+
+    /// Number of subproblems solved so far.
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+}
+
+fn lookup<K, V, F>(cache: &mut HashMap<K, V>, f: &F, key: K) -> V
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+    F: Fn(&mut dyn FnMut(K) -> V, K) -> V,
+{
+    if let Some(v) = cache.get(&key) {
+        return v.clone();
+    }
+
+    let v = f(&mut |k| lookup(cache, f, k), key.clone());
+
+    cache.insert(key, v.clone());
+
+    v
+}
+
+/// A back-pointer, knowing which cell it was taken from.
+pub trait Step<C> {
+    fn from(&self, cell: C) -> C;
+}
+
+/// Follows back-pointers from `end` until `back` has none, returning the steps in the order they
+/// were taken, each with the cell it leads to.
+pub fn backtrack<C: Copy, S>(end: C, mut back: impl FnMut(C) -> Option<(S, C)>) -> Vec<(C, S)> {
+    let mut path = vec![];
+    let mut cell = end;
+
+    while let Some((step, from)) = back(cell) {
+        path.push((cell, step));
+        cell = from;
+    }
+
+    path.reverse();
+
+    path
+}
+
+/// A value per subproblem `(i, j)`, and the step that achieved it, if any.
+#[derive(Clone, Debug)]
+pub struct Table<V, S> {
+    values: Array2D<V>,
+    steps: Array2D<Option<S>>,
+}
+
+impl<V: Copy, S: Copy> Table<V, S> {
+    pub fn new(rows: usize, cols: usize, value: V) -> Self {
+        Self {
+            values: Array2D::new(value, cols, rows),
+            steps: Array2D::new(None, cols, rows),
+        }
+    }
+}
+
+impl<V, S> Table<V, S> {
+    pub fn rows(&self) -> usize {
+        self.values.height()
+    }
+
+    pub fn cols(&self) -> usize {
+        self.values.width()
+    }
+
+    pub fn step(&self, (i, j): (usize, usize)) -> Option<&S> {
+        self.steps[i][j].as_ref()
+    }
+
+    pub fn set(&mut self, (i, j): (usize, usize), value: V, step: Option<S>) {
+        self.values[i][j] = value;
+        self.steps[i][j] = step;
+    }
+
+    /// Compute every cell in row-major order, so that `f` may read any cell above or to the
+    /// left of the one it computes.
+    pub fn fill(&mut self, mut f: impl FnMut(&Self, (usize, usize)) -> (V, Option<S>)) {
+        for i in 0..self.rows() {
+            for j in 0..self.cols() {
+                let (value, step) = f(self, (i, j));
+
+                self.set((i, j), value, step);
+            }
+        }
+    }
+
+    pub fn values(&self) -> &Array2D<V> {
+        &self.values
+    }
+
+    /// The steps leading to `end`, from a cell without a step.
+    pub fn path(&self, end: (usize, usize)) -> Vec<((usize, usize), S)>
+    where
+        S: Step<(usize, usize)> + Clone,
+    {
+        backtrack(end, |cell| {
+            self.step(cell).map(|step| (step.clone(), step.from(cell)))
+        })
+    }
+}
+
+impl<V, S> Index<(usize, usize)> for Table<V, S> {
+    type Output = V;
+
+    fn index(&self, (i, j): (usize, usize)) -> &V {
+        &self.values[i][j]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum Move {
+        Down,
+        Right,
+    }
+
+    impl Step<(usize, usize)> for Move {
+        fn from(&self, (i, j): (usize, usize)) -> (usize, usize) {
+            match self {
+                Self::Down => (i - 1, j),
+                Self::Right => (i, j - 1),
+            }
+        }
+    }
+
+    #[test]
+    fn memo() {
+        let mut fib = Memo::new(
+            |fib: &mut dyn FnMut(u64) -> u64, n: u64| {
+                if n < 2 {
+                    n
+                } else {
+                    fib(n - 1) + fib(n - 2)
+                }
+            },
+        );
+
+        assert_eq!(12586269025, fib.get(50));
+        assert_eq!(51, fib.len());
+        assert_eq!(Some(&55), fib.cached(&10));
+        assert_eq!(None, fib.cached(&51));
+
+        // binomial coefficients, with a tuple key
+        let mut choose = Memo::new(|c: &mut dyn FnMut((u64, u64)) -> u64, (n, k): (u64, u64)| {
+            if k == 0 || k == n {
+                1
+            } else {
+                c((n - 1, k - 1)) + c((n - 1, k))
+            }
+        });
+
+        assert!(choose.is_empty());
+        assert_eq!(252, choose.get((10, 5)));
+    }
+
+    #[test]
+    fn cheapest_path() {
+        // the cheapest path through a grid moving down and right
+        let grid = [[1, 3, 1], [1, 5, 1], [4, 2, 1]];
+        let mut table = Table::new(3, 3, 0);
+
+        table.fill(|t, (i, j)| match (i, j) {
+            (0, 0) => (grid[0][0], None),
+            (0, _) => (t[(0, j - 1)] + grid[0][j], Some(Move::Right)),
+            (_, 0) => (t[(i - 1, 0)] + grid[i][0], Some(Move::Down)),
+            _ if t[(i - 1, j)] <= t[(i, j - 1)] => (t[(i - 1, j)] + grid[i][j], Some(Move::Down)),
+            _ => (t[(i, j - 1)] + grid[i][j], Some(Move::Right)),
+        });
+
+        assert_eq!(7, table[(2, 2)]);
+        assert_eq!((3, 3), (table.rows(), table.cols()));
+        assert_eq!(None, table.step((0, 0)));
+        assert_eq!(
+            vec![
+                ((0, 1), Move::Right),
+                ((0, 2), Move::Right),
+                ((1, 2), Move::Down),
+                ((2, 2), Move::Down),
+            ],
+            table.path((2, 2))
+        );
+        assert!(table.path((0, 0)).is_empty());
+    }
+
+    #[test]
+    fn backtrack_chain() {
+        // following predecessors through a plain vector
+        let pred = [None, Some(0), Some(0), Some(1), Some(3)];
+        let path = backtrack(4, |i| pred[i].map(|p| ((), p)));
+
+        assert_eq!(
+            vec![1, 3, 4],
+            path.iter().map(|&(c, _)| c).collect::<Vec<_>>()
+        );
+    }
+}
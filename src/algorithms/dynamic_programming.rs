@@ -1,6 +1,11 @@
+use std::borrow::Cow;
 use std::fmt;
 
+use crate::algorithms::dp_table::{Memo, Step, Table};
+use crate::algorithms::line_breaking::{Layout, Word};
 use crate::data_structures::array_2d::Array2D;
+use crate::data_structures::binary_tree::BinaryTree;
+use crate::data_structures::matrix::{Matrix, Scalar};
 
 pub fn rod_cutting(prices: &[usize], n: usize) -> (Vec<usize>, Vec<usize>) {
     let mut revenues = vec![0; n + 1];
@@ -27,14 +32,26 @@ pub fn rod_cutting(prices: &[usize], n: usize) -> (Vec<usize>, Vec<usize>) {
 pub fn rod_cutting_extended(prices: &[usize], mut n: usize) -> (usize, Vec<usize>) {
     let (revenues, cuts) = rod_cutting(prices, n);
     let revenue = revenues[n];
-    let mut reconstr = vec![];
+    let mut pieces = vec![];
 
     while n > 0 {
-        reconstr.push(cuts[n]);
+        pieces.push(cuts[n]);
         n -= cuts[n];
     }
 
-    (revenue, reconstr)
+    (revenue, pieces)
+}
+
+/// The best revenue of `rod_cutting`, computed top-down by memoized recursion (CLRS 15.1).
+pub fn rod_cutting_memoized(prices: &[usize], n: usize) -> usize {
+    let mut revenue = Memo::new(|revenue: &mut dyn FnMut(usize) -> usize, j: usize| {
+        (1..=j)
+            .map(|i| prices[i] + revenue(j - i))
+            .max()
+            .unwrap_or(0)
+    });
+
+    revenue.get(n)
 }
 
 pub fn fib(n: usize) -> usize {
@@ -72,117 +89,277 @@ pub fn fib2(n: usize) -> usize {
     m1
 }
 
-pub fn matrix_parenthesization(dimensions: &[u64]) -> String {
-    let p = dimensions;
-    let n = p.len() - 1;
-    let mut m = Array2D::new(None, n + 1, n + 1);
-    let mut s = Array2D::new(0, n + 1, n);
+/// Order in which to multiply a chain of matrices: `Leaf(i)` is the `i`-th matrix of the chain,
+/// counting from zero, and `Product(l, r)` multiplies the results of two sub-plans.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChainPlan {
+    Leaf(usize),
+    Product(Box<ChainPlan>, Box<ChainPlan>),
+}
 
-    for i in 1..=n {
-        m[i][i] = Some(0);
+impl ChainPlan {
+    /// Multiply `matrices` in the order given by the plan. Only a plan of a single matrix copies
+    /// it.
+    pub fn execute<T: Scalar>(&self, matrices: &[Matrix<T>]) -> Matrix<T> {
+        self.product(matrices).into_owned()
+    }
+
+    // Leaves are borrowed, so that only the intermediate products are allocated
+    fn product<'a, T: Scalar>(&self, matrices: &'a [Matrix<T>]) -> Cow<'a, Matrix<T>> {
+        match self {
+            Self::Leaf(i) => Cow::Borrowed(&matrices[*i]),
+            Self::Product(l, r) => Cow::Owned(&*l.product(matrices) * &*r.product(matrices)),
+        }
     }
+}
+
+/// Renders the plan with CLRS's names, numbering the matrices from one: `((A1A2)A3)`.
+impl fmt::Display for ChainPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Leaf(i) => write!(f, "A{}", i + 1),
+            Self::Product(l, r) => write!(f, "({l}{r})"),
+        }
+    }
+}
+
+/// The minimum number of scalar multiplications needed to multiply a chain of matrices, where
+/// matrix `i` is `dimensions[i]` x `dimensions[i + 1]`, and the plan achieving it.
+pub fn matrix_chain_order(dimensions: &[u64]) -> (u64, ChainPlan) {
+    assert!(
+        dimensions.len() >= 2,
+        "chain should have at least one matrix"
+    );
+
+    let p = dimensions;
+    let n = p.len() - 1;
+    let mut m = Array2D::new(0, n, n);
+    let mut s = Array2D::new(0, n, n);
 
     for l in 2..=n {
-        for i in 1..=(n - l + 1) {
+        for i in 0..=(n - l) {
             let j = i + l - 1;
 
-            for k in i..j {
-                let Some(prefix) = m[i][k] else {
-                    continue;
-                };
-                let Some(postfix) = m[k + 1][j] else {
-                    continue;
-                };
-                let this_cost = p[i - 1] * p[k] * p[j];
-                let full_cost = prefix + postfix + this_cost;
+            m[i][j] = u64::MAX;
 
-                let curr = &mut m[i][j];
+            for k in i..j {
+                let cost = m[i][k] + m[k + 1][j] + p[i] * p[k + 1] * p[j + 1];
 
-                if curr.is_none() || curr.is_some_and(|cost| full_cost < cost) {
-                    *curr = Some(full_cost);
+                if cost < m[i][j] {
+                    m[i][j] = cost;
                     s[i][j] = k;
                 }
             }
         }
     }
 
-    let mut output = String::new();
+    (m[0][n - 1], chain_plan(&s, 0, n - 1))
+}
 
-    print_parenthesization(&mut output, &s, 1, n).expect("failed to print parenthesization");
+fn chain_plan(s: &Array2D<usize>, i: usize, j: usize) -> ChainPlan {
+    if i == j {
+        return ChainPlan::Leaf(i);
+    }
+
+    let l = chain_plan(s, i, s[i][j]);
+    let r = chain_plan(s, s[i][j] + 1, j);
 
-    output
+    ChainPlan::Product(Box::new(l), Box::new(r))
 }
 
-fn print_parenthesization<W: fmt::Write>(
-    f: &mut W,
-    s: &Array2D<usize>,
-    i: usize,
-    j: usize,
-) -> fmt::Result {
-    if i == j {
-        return write!(f, "A{i}");
+pub fn matrix_parenthesization(dimensions: &[u64]) -> String {
+    matrix_chain_order(dimensions).1.to_string()
+}
+
+/// Multiply a chain of matrices in the cheapest order.
+pub fn matrix_chain_multiply<T: Scalar>(matrices: &[Matrix<T>]) -> Matrix<T> {
+    assert!(
+        !matrices.is_empty(),
+        "chain should have at least one matrix"
+    );
+
+    let mut dimensions = vec![matrices[0].rows() as u64];
+
+    for (i, a) in matrices.iter().enumerate() {
+        if let Some(b) = matrices.get(i + 1) {
+            assert_eq!(a.cols(), b.rows(), "matrices {i} and {} don't chain", i + 1);
+        }
+
+        dimensions.push(a.cols() as u64);
     }
 
-    write!(f, "(")?;
+    matrix_chain_order(&dimensions).1.execute(matrices)
+}
 
-    print_parenthesization(f, s, i, s[i][j])?;
-    print_parenthesization(f, s, s[i][j] + 1, j)?;
+/// Shape of a binary search tree over keys `k0 < k1 < ...`: `Key(i, l, r)` has key `i` at its
+/// root, and `Dummy(i)` stands for the unsuccessful searches ending between keys `i - 1` and `i`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BstPlan {
+    Dummy(usize),
+    Key(usize, Box<BstPlan>, Box<BstPlan>),
+}
+
+impl BstPlan {
+    /// Indices of the keys in preorder, the order in which inserting them into an unbalanced tree
+    /// builds this shape.
+    pub fn preorder(&self) -> Vec<usize> {
+        let mut out = vec![];
+
+        self.preorder_into(&mut out);
+
+        out
+    }
 
-    write!(f, ")")
+    fn preorder_into(&self, out: &mut Vec<usize>) {
+        if let Self::Key(i, l, r) = self {
+            out.push(*i);
+            l.preorder_into(out);
+            r.preorder_into(out);
+        }
+    }
+
+    /// Load `entries`, one per key of the plan and in strictly increasing key order, into a tree
+    /// of this shape.
+    pub fn to_tree<K: Ord + Copy, V>(
+        &self,
+        entries: impl IntoIterator<Item = (K, V)>,
+    ) -> BinaryTree<K, V> {
+        let entries: Vec<(K, V)> = entries.into_iter().collect();
+        let order = self.preorder();
+
+        assert_eq!(
+            order.len(),
+            entries.len(),
+            "should have an entry per key of the plan"
+        );
+        assert!(
+            entries.windows(2).all(|w| w[0].0 < w[1].0),
+            "keys should be strictly increasing"
+        );
+
+        let mut entries: Vec<Option<(K, V)>> = entries.into_iter().map(Some).collect();
+        let mut tree = BinaryTree::new();
+
+        for i in order {
+            let (key, value) = entries[i].take().expect("plan should use every key once");
+
+            tree.insert(key, value);
+        }
+
+        tree
+    }
 }
 
-#[derive(Clone, Copy)]
-enum Direction {
-    Unset,
-    Up,
-    Left,
-    UpLeft,
+/// A binary search tree minimizing the expected cost of a search (CLRS 15.5), counted as the
+/// number of nodes visited, where `p[i]` is the probability of searching for key `i` and `q[i]`
+/// that of searching between keys `i - 1` and `i`. Returns the expected cost and the tree.
+pub fn optimal_bst(p: &[f64], q: &[f64]) -> (f64, BstPlan) {
+    optimal_bst_impl(p, q, false)
 }
 
-pub fn longest_common_subsequence(xs: &[u8], ys: &[u8]) -> Vec<u8> {
-    let xl = xs.len();
-    let yl = ys.len();
-
-    let mut c = Array2D::new(0, xl + 1, yl + 1);
-    let mut b = Array2D::new(Direction::Unset, xl + 1, yl + 1);
-
-    for x in 1..=xl {
-        for y in 1..=yl {
-            if xs[x - 1] == ys[y - 1] {
-                c[y][x] = 1 + c[y - 1][x - 1];
-                b[y][x] = Direction::UpLeft;
-            } else if c[y - 1][x] >= c[y][x - 1] {
-                c[y][x] = c[y - 1][x];
-                b[y][x] = Direction::Up;
+/// Same as `optimal_bst`, in O(n²) rather than O(n³) time, by Knuth's observation that the root
+/// of an optimal tree over `ki..=kj` lies between the roots of the trees over `ki..kj` and
+/// `ki+1..=kj`.
+pub fn optimal_bst_knuth(p: &[f64], q: &[f64]) -> (f64, BstPlan) {
+    optimal_bst_impl(p, q, true)
+}
+
+fn optimal_bst_impl(p: &[f64], q: &[f64], knuth: bool) -> (f64, BstPlan) {
+    assert_eq!(
+        p.len() + 1,
+        q.len(),
+        "should have a dummy key around each key"
+    );
+
+    // numbering keys from 1 as in CLRS: e[i][j] and w[i][j] are the expected cost and total
+    // probability of a subtree over keys i..=j, where j = i - 1 is an empty tree
+    let n = p.len();
+    let mut e = Array2D::new(0.0, n + 1, n + 2);
+    let mut w = Array2D::new(0.0, n + 1, n + 2);
+    let mut root = Array2D::new(0, n + 1, n + 2);
+
+    for i in 1..=n + 1 {
+        e[i][i - 1] = q[i - 1];
+        w[i][i - 1] = q[i - 1];
+    }
+
+    for l in 1..=n {
+        for i in 1..=n - l + 1 {
+            let j = i + l - 1;
+            let candidates = if knuth && l > 1 {
+                root[i][j - 1]..=root[i + 1][j]
             } else {
-                c[y][x] = c[y][x - 1];
-                b[y][x] = Direction::Left;
+                i..=j
+            };
+
+            e[i][j] = f64::INFINITY;
+            w[i][j] = w[i][j - 1] + p[j - 1] + q[j];
+
+            for r in candidates {
+                let cost = e[i][r - 1] + e[r + 1][j] + w[i][j];
+
+                if cost < e[i][j] {
+                    e[i][j] = cost;
+                    root[i][j] = r;
+                }
             }
         }
     }
 
-    let mut w = vec![];
-    reconstruct_lcs(&mut w, &b, xs, xl, yl);
-    w.reverse();
-    w
+    (e[1][n], bst_plan(&root, 1, n))
 }
 
-fn reconstruct_lcs(w: &mut Vec<u8>, b: &Array2D<Direction>, xs: &[u8], x: usize, y: usize) {
-    if x == 0 || y == 0 {
-        return;
+fn bst_plan(root: &Array2D<usize>, i: usize, j: usize) -> BstPlan {
+    if j + 1 == i {
+        return BstPlan::Dummy(j);
     }
 
-    match b[y][x] {
-        Direction::Unset => panic!("direction should be set"),
-        Direction::Up => reconstruct_lcs(w, b, xs, x, y - 1),
-        Direction::Left => reconstruct_lcs(w, b, xs, x - 1, y),
-        Direction::UpLeft => {
-            w.push(xs[x - 1]);
-            reconstruct_lcs(w, b, xs, x - 1, y - 1);
+    let r = root[i][j];
+    let left = bst_plan(root, i, r - 1);
+    let right = bst_plan(root, r + 1, j);
+
+    BstPlan::Key(r - 1, Box::new(left), Box::new(right))
+}
+
+#[derive(Clone, Copy)]
+enum LcsStep {
+    Match,
+    SkipX,
+    SkipY,
+}
+
+impl Step<(usize, usize)> for LcsStep {
+    fn from(&self, (x, y): (usize, usize)) -> (usize, usize) {
+        match self {
+            Self::Match => (x - 1, y - 1),
+            Self::SkipX => (x - 1, y),
+            Self::SkipY => (x, y - 1),
         }
     }
 }
 
+pub fn longest_common_subsequence(xs: &[u8], ys: &[u8]) -> Vec<u8> {
+    let mut c = Table::new(xs.len() + 1, ys.len() + 1, 0);
+
+    c.fill(|c, (x, y)| {
+        if x == 0 || y == 0 {
+            (0, None)
+        } else if xs[x - 1] == ys[y - 1] {
+            (1 + c[(x - 1, y - 1)], Some(LcsStep::Match))
+        } else if c[(x, y - 1)] >= c[(x - 1, y)] {
+            (c[(x, y - 1)], Some(LcsStep::SkipY))
+        } else {
+            (c[(x - 1, y)], Some(LcsStep::SkipX))
+        }
+    });
+
+    c.path((xs.len(), ys.len()))
+        .into_iter()
+        .filter(|(_, step)| matches!(step, LcsStep::Match))
+        .map(|((x, _), _)| xs[x - 1])
+        .collect()
+}
+
 pub fn longest_common_subsequence_2(a: &str, b: &str) -> String {
     let a = a.as_bytes();
     let b = b.as_bytes();
@@ -303,94 +480,67 @@ pub fn longest_increasing_subsequence_2(xs: &[usize]) -> Vec<usize> {
     subs
 }
 
-#[derive(Clone, Copy, PartialEq)]
-enum PalDirection {
-    End,
-    Char(u8),
-    Left,
-    Right,
+// Steps between the cells `(l, s)` of the subsequences of length `l` starting at index `s`
+#[derive(Clone, Copy)]
+enum PalStep {
+    Middle,
+    Ends,
+    DropFirst,
+    DropLast,
 }
 
-pub fn longest_palindrome_subsequence(a: &[u8]) -> Vec<u8> {
-    let n = a.len();
-    let mut m = Array2D::new(0, n, n + 1);
-    let mut b = Array2D::new(PalDirection::End, n, n + 1);
-
-    for (s, a) in a.iter().enumerate() {
-        m[1][s] = 1;
-        b[1][s] = PalDirection::Char(*a);
-    }
-
-    for l in 2..=n {
-        let mut s = 0;
-
-        while s + l <= n {
-            if a[s] == a[s + l - 1] {
-                m[l][s] = 2 + m[l - 2][s + 1];
-                b[l][s] = PalDirection::Char(a[s]);
-            } else {
-                let ll = m[l - 1][s];
-                let lr = m[l - 1][s + 1];
-
-                if ll > lr {
-                    m[l][s] = ll;
-                    b[l][s] = PalDirection::Left;
-                } else {
-                    m[l][s] = lr;
-                    b[l][s] = PalDirection::Right;
-                }
-            }
-
-            s += 1;
+impl Step<(usize, usize)> for PalStep {
+    fn from(&self, (l, s): (usize, usize)) -> (usize, usize) {
+        match self {
+            Self::Middle => (0, s),
+            Self::Ends => (l - 2, s + 1),
+            Self::DropFirst => (l - 1, s + 1),
+            Self::DropLast => (l - 1, s),
         }
     }
-
-    reconstruct_lps(&m, &b, n)
 }
 
-fn reconstruct_lps(m: &Array2D<usize>, b: &Array2D<PalDirection>, n: usize) -> Vec<u8> {
-    let len = m[n][0];
-
-    let mut o = Vec::with_capacity(len);
-    let mut s = 0;
-    let mut l = n;
-
-    loop {
-        match b[l][s] {
-            PalDirection::End => break,
-            PalDirection::Char(c) => {
-                o.push(c);
-                s += 1;
-
-                if l < 2 {
-                    break;
-                }
+pub fn longest_palindrome_subsequence(a: &[u8]) -> Vec<u8> {
+    let n = a.len();
 
-                l -= 2;
-            }
-            PalDirection::Left => {
-                l -= 1;
-            }
-            PalDirection::Right => {
-                s += 1;
-                l -= 1;
-            }
-        }
+    if n == 0 {
+        return vec![];
     }
 
-    let mut i = len / 2 - 1;
-
-    while o.len() != len {
-        o.push(o[i]);
-
-        if i == 0 {
-            break;
+    let mut m = Table::new(n + 1, n, 0);
+
+    m.fill(|m, (l, s)| {
+        if l == 0 || s + l > n {
+            (0, None)
+        } else if l == 1 {
+            (1, Some(PalStep::Middle))
+        } else if a[s] == a[s + l - 1] {
+            (2 + m[(l - 2, s + 1)], Some(PalStep::Ends))
+        } else if m[(l - 1, s)] > m[(l - 1, s + 1)] {
+            (m[(l - 1, s)], Some(PalStep::DropLast))
+        } else {
+            (m[(l - 1, s + 1)], Some(PalStep::DropFirst))
         }
-
-        i -= 1;
-    }
-
-    o
+    });
+
+    // The path runs from the innermost step out, so the outer characters come last
+    let path = m.path((n, 0));
+    let half: Vec<u8> = path
+        .iter()
+        .rev()
+        .filter(|(_, step)| matches!(step, PalStep::Ends))
+        .map(|&((_, s), _)| a[s])
+        .collect();
+    let middle = path
+        .first()
+        .filter(|(_, step)| matches!(step, PalStep::Middle))
+        .map(|&((_, s), _)| a[s]);
+
+    half.iter()
+        .copied()
+        .chain(middle)
+        .chain(half.iter().rev().copied())
+        .collect()
 }
 
 pub fn longest_palindrome_subsequence_2(seq: &str) -> String {
@@ -431,201 +581,73 @@ pub fn longest_palindrome_subsequence_2(seq: &str) -> String {
     p[seq.len()][0].clone()
 }
 
-#[allow(clippy::cast_possible_wrap)] // CBA
-pub fn printing_neatly(words: &[&'static str], width: isize) -> Vec<String> {
-    let n = words.len();
-    let mut extras = Array2D::new(0, n + 1, n + 1);
-    let mut linecost = Array2D::new(None, n + 1, n + 1);
-    let mut startcost = vec![None; n + 1];
-    let mut pred = vec![0; n + 1];
-
-    for i in 1..=n {
-        extras[i][i] = width - words[i - 1].len() as isize;
-
-        for j in i + 1..=n {
-            extras[i][j] = extras[i][j - 1] - words[j - 1].len() as isize - 1;
-        }
-    }
-
-    for i in 1..=n {
-        for j in i..=n {
-            linecost[i][j] = if extras[i][j] < 0 {
-                None
-            } else if j == n && extras[i][j] >= 0 {
-                Some(0)
-            } else {
-                Some(extras[i][j].pow(3))
-            };
-        }
-    }
-
-    startcost[0] = Some(0);
-
-    for j in 1..=n {
-        startcost[j] = None;
-
-        for i in 1..=j {
-            let Some(prev) = startcost[i - 1] else {
-                continue;
-            };
-            let Some(line) = linecost[i][j] else { continue };
-
-            let cost = prev + line;
+/// Lines of at most `width` characters minimizing the sum of the cubes of the trailing spaces on
+/// every line but the last. See `algorithms::line_breaking` for other penalties and alignments.
+pub fn printing_neatly(words: &[&str], width: isize) -> Vec<String> {
+    let width = usize::try_from(width).expect("width should not be negative");
+    let words: Vec<Word> = words.iter().map(|w| Word::new(w)).collect();
 
-            if startcost[j].is_none() || startcost[j].is_some_and(|s| cost < s) {
-                startcost[j] = Some(cost);
-                pred[j] = i;
-            }
-        }
-    }
-
-    let len = number_printed_lines(&pred, n);
-    let mut lines = vec![&words[0..0]; len];
-
-    reconstruct_neat_print(&mut lines, words, &pred, n);
-
-    join_lines(&lines)
-}
-
-fn number_printed_lines(pred: &[usize], j: usize) -> usize {
-    let i = pred[j];
-
-    if i == 1 {
-        1
-    } else {
-        number_printed_lines(pred, i - 1) + 1
-    }
-}
-
-fn reconstruct_neat_print<'w>(
-    lines: &mut [&'w [&str]],
-    words: &'w [&'static str],
-    pred: &[usize],
-    j: usize,
-) -> usize {
-    let i = pred[j];
-
-    let k = if i == 1 {
-        1
-    } else {
-        reconstruct_neat_print(lines, words, pred, i - 1) + 1
-    };
-
-    lines[k - 1] = &words[i - 1..j];
-
-    k
-}
-
-fn join_lines(lines: &[&[&str]]) -> Vec<String> {
-    let mut paragraph = vec![String::new(); lines.len()];
-
-    for (i, line) in lines.iter().enumerate() {
-        let (head, tail) = line.split_at(1);
-
-        paragraph[i].push_str(head[0]);
-
-        for word in tail {
-            paragraph[i].push(' ');
-            paragraph[i].push_str(word);
-        }
-    }
-
-    paragraph
+    Layout::new(width).format(&words)
 }
 
 #[derive(Clone, Copy)]
 pub enum EditOperations {
-    Unset,
     Copy(char),
     Replace(char, char),
     Delete(char),
     Insert(char),
-    End,
 }
 
+/// Unit-cost edit script between the characters of `x` and `y`. See `algorithms::edit_distance`
+/// for other costs and sequences.
 pub fn edit_distance(x: &str, y: &str) -> Vec<EditOperations> {
-    let m = x.len();
-    let n = y.len();
-    let mut d = Array2D::new(0, n + 1, m + 1);
-    let mut p = Array2D::new(EditOperations::Unset, n + 1, m + 1);
-
-    for i in 0..=m {
-        for j in 0..=n {
-            if i == 0 {
-                d[i][j] = j;
-                p[i][j] = EditOperations::End;
-                continue;
-            }
+    let x: Vec<char> = x.chars().collect();
+    let y: Vec<char> = y.chars().collect();
+    let mut d = Table::new(x.len() + 1, y.len() + 1, 0);
 
-            if j == 0 {
-                d[i][j] = i;
-                p[i][j] = EditOperations::End;
-                continue;
-            }
-
-            let xp = x.as_bytes()[i - 1] as char;
-            let yp = y.as_bytes()[j - 1] as char;
+    d.fill(|d, (i, j)| {
+        if i == 0 {
+            return (j, None);
+        }
 
-            if xp == yp {
-                d[i][j] = d[i - 1][j - 1];
-                p[i][j] = EditOperations::Copy(xp);
-            } else {
-                let ins = d[i][j - 1];
-                let del = d[i - 1][j];
-                let rep = d[i - 1][j - 1];
-
-                if ins < del && ins < rep {
-                    d[i][j] = 1 + ins;
-                    p[i][j] = EditOperations::Insert(yp);
-                } else if del < ins && del < rep {
-                    d[i][j] = 1 + del;
-                    p[i][j] = EditOperations::Delete(xp);
-                } else {
-                    d[i][j] = 1 + rep;
-                    p[i][j] = EditOperations::Replace(xp, yp);
-                }
-            }
+        if j == 0 {
+            return (i, None);
         }
-    }
 
-    reconstruct_edit_distance(&p, m, n)
-}
+        let xp = x[i - 1];
+        let yp = y[j - 1];
 
-fn reconstruct_edit_distance(
-    p: &Array2D<EditOperations>,
-    mut i: usize,
-    mut j: usize,
-) -> Vec<EditOperations> {
-    let mut ops = vec![];
+        if xp == yp {
+            return (d[(i - 1, j - 1)], Some(EditOperations::Copy(xp)));
+        }
 
-    loop {
-        let op = p[i][j];
+        let ins = d[(i, j - 1)];
+        let del = d[(i - 1, j)];
+        let rep = d[(i - 1, j - 1)];
 
-        match op {
-            EditOperations::Unset => panic!("edit operation should be set"),
-            EditOperations::Copy(_x) => {
-                i -= 1;
-                j -= 1;
-            }
-            EditOperations::Replace(_x, _y) => {
-                i -= 1;
-                j -= 1;
-            }
-            EditOperations::Delete(_x) => {
-                i -= 1;
-            }
-            EditOperations::Insert(_x) => {
-                j -= 1;
-            }
-            EditOperations::End => break,
+        if ins < del && ins < rep {
+            (1 + ins, Some(EditOperations::Insert(yp)))
+        } else if del < ins && del < rep {
+            (1 + del, Some(EditOperations::Delete(xp)))
+        } else {
+            (1 + rep, Some(EditOperations::Replace(xp, yp)))
         }
+    });
 
-        ops.push(op);
-    }
-
-    ops.reverse();
+    d.path((x.len(), y.len()))
+        .into_iter()
+        .map(|(_, op)| op)
+        .collect()
+}
 
-    ops
+impl Step<(usize, usize)> for EditOperations {
+    fn from(&self, (i, j): (usize, usize)) -> (usize, usize) {
+        match self {
+            Self::Copy(_) | Self::Replace(_, _) => (i - 1, j - 1),
+            Self::Delete(_) => (i - 1, j),
+            Self::Insert(_) => (i, j - 1),
+        }
+    }
 }
 
 pub fn string_breaking(len: usize, breaks: &[usize]) -> usize {
@@ -669,49 +691,306 @@ pub struct Item {
     weight: usize,
 }
 
+impl Item {
+    pub fn new(value: u32, weight: usize) -> Self {
+        Self { value, weight }
+    }
+
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+
+    pub fn weight(&self) -> usize {
+        self.weight
+    }
+}
+
 pub fn binary_knapsack(items: &[Item], max_weight: usize) -> Vec<Item> {
+    knapsack_01(items, max_weight)
+        .into_iter()
+        .map(|i| items[i])
+        .collect()
+}
+
+/// 0/1 knapsack over a table indexed by weight: the indices of the most valuable set of items
+/// weighing at most `capacity`, in ascending order.
+pub fn knapsack_01(items: &[Item], capacity: usize) -> Vec<usize> {
+    let items: Vec<(u64, usize)> = items
+        .iter()
+        .map(|item| (u64::from(item.value), item.weight))
+        .collect();
+
+    knapsack_01_impl(&items, capacity)
+}
+
+// The same over `(value, weight)` pairs, whose values may not fit an `Item`
+fn knapsack_01_impl(items: &[(u64, usize)], capacity: usize) -> Vec<usize> {
     let n = items.len();
-    let mut v = Array2D::new(0, max_weight + 1, n + 1);
+    let mut v = Array2D::new(0_u64, capacity + 1, n + 1);
 
-    for i in 1..=n {
-        let item = &items[i - 1];
+    for (i, &(value, weight)) in items.iter().enumerate() {
+        for w in 0..=capacity {
+            v[i + 1][w] = v[i][w];
 
-        for w in 1..=max_weight {
-            let prev = v[i - 1][w];
+            if weight <= w {
+                let this = value + v[i][w - weight];
 
-            if item.weight > max_weight {
-                v[i][w] = prev;
-            } else {
-                let this = item.value + v[i - 1][max_weight - item.weight];
+                if this > v[i + 1][w] {
+                    v[i + 1][w] = this;
+                }
+            }
+        }
+    }
 
-                if this > prev {
-                    v[i][w] = this;
-                } else {
-                    v[i][w] = prev;
+    let mut chosen = vec![];
+    let mut w = capacity;
+
+    for i in (0..n).rev() {
+        if v[i + 1][w] != v[i][w] {
+            chosen.push(i);
+            w -= items[i].1;
+        }
+    }
+
+    chosen.reverse();
+    chosen
+}
+
+/// Knapsack with an unlimited supply of each item. An index appears once per copy taken.
+///
+/// Panics if an item weighs nothing but has a value, as any number of copies could be taken.
+pub fn unbounded_knapsack(items: &[Item], capacity: usize) -> Vec<usize> {
+    if let Some(i) = items
+        .iter()
+        .position(|item| item.weight == 0 && item.value > 0)
+    {
+        panic!("item {i} is free and valuable, so there is no best choice");
+    }
+
+    // `last[w]` is the item completing the best choice for `w`, if it beats the one for `w - 1`
+    let mut best = vec![0_u64; capacity + 1];
+    let mut last = vec![None; capacity + 1];
+
+    for w in 1..=capacity {
+        best[w] = best[w - 1];
+
+        for (i, item) in items.iter().enumerate() {
+            if item.weight > 0 && item.weight <= w {
+                let this = u64::from(item.value) + best[w - item.weight];
+
+                if this > best[w] {
+                    best[w] = this;
+                    last[w] = Some(i);
                 }
             }
         }
     }
 
-    let mut output = vec![];
-    let mut i = n - 1;
-    let mut w = max_weight;
+    let mut chosen = vec![];
+    let mut w = capacity;
 
-    while i != 0 {
-        if v[i][w] != v[i - 1][w] {
-            output.push(items[i]);
-            w -= items[i].weight;
+    while w > 0 {
+        match last[w] {
+            Some(i) => {
+                chosen.push(i);
+                w -= items[i].weight;
+            }
+            None => w -= 1,
         }
+    }
+
+    chosen.sort_unstable();
+    chosen
+}
+
+/// Knapsack where item `i` can be taken up to `counts[i]` times. The copies of an item are grouped
+/// into bundles of 1, 2, 4, ... copies, so that any count up to the limit is a choice of bundles,
+/// and the bundles are solved as a 0/1 knapsack.
+pub fn bounded_knapsack(items: &[Item], counts: &[usize], capacity: usize) -> Vec<usize> {
+    assert_eq!(items.len(), counts.len(), "every item should have a count");
+
+    let mut bundles = vec![];
+    let mut origin = vec![];
+
+    for (i, (item, &count)) in items.iter().zip(counts).enumerate() {
+        let mut left = count;
+        let mut size = 1;
+
+        while left > 0 {
+            let take = size.min(left);
+            let value = u64::from(item.value).saturating_mul(take as u64);
+
+            // bundles too heavy to ever fit are left out
+            if item.weight.checked_mul(take).is_some_and(|w| w <= capacity) {
+                bundles.push((value, item.weight * take));
+                origin.push((i, take));
+            }
 
-        i -= 1;
+            left -= take;
+            size *= 2;
+        }
     }
 
-    output
+    let mut chosen: Vec<usize> = knapsack_01_impl(&bundles, capacity)
+        .into_iter()
+        .flat_map(|b| std::iter::repeat_n(origin[b].0, origin[b].1))
+        .collect();
+
+    chosen.sort_unstable();
+    chosen
+}
+
+/// 0/1 knapsack over a table indexed by total value instead of weight, which suits small values
+/// and huge weights: O(n * sum of values) regardless of `capacity`.
+pub fn knapsack_by_value(items: &[Item], capacity: usize) -> Vec<usize> {
+    let values: Vec<u64> = items.iter().map(|item| u64::from(item.value)).collect();
+
+    knapsack_by_value_impl(&values, items, capacity)
+}
+
+// `w[i][v]` is the least weight of a subset of the first `i` items worth exactly `v`
+fn knapsack_by_value_impl(values: &[u64], items: &[Item], capacity: usize) -> Vec<usize> {
+    let n = items.len();
+    let total = usize::try_from(values.iter().sum::<u64>()).expect("values should fit in memory");
+    let mut w = Array2D::new(None, total + 1, n + 1);
+
+    w[0][0] = Some(0_usize);
+
+    for i in 0..n {
+        let value = values[i] as usize;
+
+        for v in 0..=total {
+            let skip = w[i][v];
+            let take = v
+                .checked_sub(value)
+                .and_then(|rest| w[i][rest])
+                .and_then(|rest: usize| rest.checked_add(items[i].weight));
+
+            w[i + 1][v] = match (skip, take) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+        }
+    }
+
+    let Some(mut v) = (0..=total)
+        .rev()
+        .find(|&v| w[n][v].is_some_and(|weight| weight <= capacity))
+    else {
+        return vec![];
+    };
+
+    let mut chosen = vec![];
+
+    for i in (0..n).rev() {
+        if w[i + 1][v] != w[i][v] {
+            chosen.push(i);
+            v -= values[i] as usize;
+        }
+    }
+
+    chosen.reverse();
+    chosen
+}
+
+/// Fully polynomial-time approximation scheme for 0/1 knapsack: values are scaled down so that
+/// the value-indexed table has O(n^2 / epsilon) columns, and the result is worth at least
+/// `1 - epsilon` times the optimum.
+// values are rounded down on purpose
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn knapsack_fptas(items: &[Item], capacity: usize, epsilon: f64) -> Vec<usize> {
+    assert!(epsilon > 0.0, "epsilon should be positive");
+
+    let fits: Vec<usize> = (0..items.len())
+        .filter(|&i| items[i].weight <= capacity)
+        .collect();
+
+    let Some(max) = fits.iter().map(|&i| items[i].value).max() else {
+        return vec![];
+    };
+
+    if max == 0 {
+        return vec![];
+    }
+
+    let k = epsilon * f64::from(max) / fits.len() as f64;
+    let values: Vec<u64> = fits
+        .iter()
+        .map(|&i| (f64::from(items[i].value) / k).floor() as u64)
+        .collect();
+    let kept: Vec<Item> = fits.iter().map(|&i| items[i]).collect();
+
+    knapsack_by_value_impl(&values, &kept, capacity)
+        .into_iter()
+        .map(|j| fits[j])
+        .collect()
+}
+
+/// 0/1 knapsack with several constraints: row `i` of `weights` holds item `i`'s weight in each
+/// dimension, and the chosen items must stay within `capacities` in all of them. The table has a
+/// column per combination of used capacities, so this is only practical for small capacities.
+pub fn multi_constraint_knapsack(
+    values: &[u32],
+    weights: &Array2D<usize>,
+    capacities: &[usize],
+) -> Vec<usize> {
+    let n = values.len();
+    let d = capacities.len();
+
+    assert_eq!(n, weights.height(), "every item should have weights");
+    assert_eq!(
+        d,
+        weights.width(),
+        "every item should have a weight per constraint"
+    );
+
+    // mixed radix numbering of the used capacities, with strides[k] for dimension k
+    let mut strides = vec![1; d];
+
+    for k in 1..d {
+        strides[k] = strides[k - 1] * (capacities[k - 1] + 1);
+    }
+
+    let states = capacities.iter().map(|c| c + 1).product::<usize>();
+    let offset = |i: usize| -> usize { (0..d).map(|k| weights[i][k] * strides[k]).sum() };
+    let fits = |i: usize, s: usize| -> bool {
+        (0..d).all(|k| (s / strides[k]) % (capacities[k] + 1) >= weights[i][k])
+    };
+
+    let mut v = Array2D::new(0_u64, states, n + 1);
+
+    for i in 0..n {
+        for s in 0..states {
+            v[i + 1][s] = v[i][s];
+
+            if fits(i, s) {
+                let this = u64::from(values[i]) + v[i][s - offset(i)];
+
+                if this > v[i + 1][s] {
+                    v[i + 1][s] = this;
+                }
+            }
+        }
+    }
+
+    let mut chosen = vec![];
+    let mut s = states - 1;
+
+    for i in (0..n).rev() {
+        if v[i + 1][s] != v[i][s] {
+            chosen.push(i);
+            s -= offset(i);
+        }
+    }
+
+    chosen.reverse();
+    chosen
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::rand::Wyhash64RNG;
 
     #[test]
     fn rod_cutting_test() {
@@ -719,6 +998,13 @@ mod tests {
 
         assert_eq!((30, vec![10]), rod_cutting_extended(&prices, 10));
         assert_eq!((18, vec![1, 6]), rod_cutting_extended(&prices, 7));
+
+        for n in 0..=10 {
+            assert_eq!(
+                rod_cutting(&prices, n).0[n],
+                rod_cutting_memoized(&prices, n)
+            );
+        }
     }
 
     #[test]
@@ -745,6 +1031,46 @@ mod tests {
         assert_eq!(p, "((A1(A2A3))((A4A5)A6))");
     }
 
+    #[test]
+    fn matrix_chain_order_test() {
+        let (cost, plan) = matrix_chain_order(&[30, 35, 15, 5, 10, 20, 25]);
+
+        assert_eq!(15125, cost);
+        assert_eq!("((A1(A2A3))((A4A5)A6))", plan.to_string());
+
+        let (cost, plan) = matrix_chain_order(&[10, 100, 5, 50]);
+        let leaf = |i| Box::new(ChainPlan::Leaf(i));
+
+        assert_eq!(7500, cost);
+        assert_eq!(
+            ChainPlan::Product(Box::new(ChainPlan::Product(leaf(0), leaf(1))), leaf(2)),
+            plan
+        );
+        assert_eq!((0, ChainPlan::Leaf(0)), matrix_chain_order(&[3, 4]));
+    }
+
+    #[test]
+    fn matrix_chain_multiply_test() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+        let dimensions = [3, 7, 2, 9, 4, 1];
+        let matrices: Vec<Matrix<i64>> = dimensions
+            .windows(2)
+            .map(|d| {
+                let data: Vec<i64> = (0..d[0] * d[1])
+                    .map(|_| rng.gen_in_range_i64(-5..5))
+                    .collect();
+                Matrix::from_slice(d[0], d[1], &data)
+            })
+            .collect();
+
+        let expected = matrices[1..]
+            .iter()
+            .fold(matrices[0].clone(), |acc, m| &acc * m);
+
+        assert_eq!(expected, matrix_chain_multiply(&matrices));
+        assert_eq!(matrices[2], matrix_chain_multiply(&matrices[2..3]));
+    }
+
     #[test]
     fn longest_common_subsequence_test() {
         let s1 = b"springtime";
@@ -812,6 +1138,10 @@ mod tests {
         let tst = b"xdRAfdjCECA123R_";
         let ans = b"RACECAR";
         assert_eq!(ans, longest_palindrome_subsequence(tst).as_slice());
+
+        assert_eq!(b"abba", longest_palindrome_subsequence(b"abba").as_slice());
+        assert_eq!(b"a", longest_palindrome_subsequence(b"a").as_slice());
+        assert!(longest_palindrome_subsequence(b"").is_empty());
     }
 
     #[test]
@@ -858,12 +1188,107 @@ mod tests {
         assert_eq!(&fmt, ans.as_slice());
     }
 
+    fn bst_cost(plan: &BstPlan, p: &[f64], q: &[f64], depth: usize) -> f64 {
+        match plan {
+            BstPlan::Dummy(i) => (depth + 1) as f64 * q[*i],
+            BstPlan::Key(i, l, r) => {
+                (depth + 1) as f64 * p[*i]
+                    + bst_cost(l, p, q, depth + 1)
+                    + bst_cost(r, p, q, depth + 1)
+            }
+        }
+    }
+
+    #[test]
+    fn optimal_bst_test() {
+        // CLRS figure 15.9
+        let p = [0.15, 0.10, 0.05, 0.10, 0.20];
+        let q = [0.05, 0.10, 0.05, 0.05, 0.05, 0.10];
+        let (cost, plan) = optimal_bst(&p, &q);
+
+        assert!((cost - 2.75).abs() < 1e-9);
+        assert_eq!(vec![1, 0, 4, 3, 2], plan.preorder());
+        assert_eq!(plan, optimal_bst_knuth(&p, &q).1);
+
+        let tree = plan.to_tree(["a", "b", "c", "d", "e"].into_iter().zip(1..));
+
+        assert_eq!(Some(&3), tree.get("c"));
+        assert_eq!(None, tree.get("f"));
+        assert_eq!(
+            vec![Some(1), Some(0), Some(3), Some(2), Some(1)],
+            ["a", "b", "c", "d", "e"].map(|k| tree.depth(k))
+        );
+
+        let (cost, plan) = optimal_bst(&[], &[1.0]);
+
+        assert_eq!((1.0, BstPlan::Dummy(0)), (cost, plan));
+    }
+
+    #[test]
+    #[should_panic(expected = "should have an entry per key of the plan")]
+    fn bst_to_tree_too_few_entries() {
+        let (_, plan) = optimal_bst(&[0.5, 0.5], &[0.0; 3]);
+
+        plan.to_tree([(1, ())]);
+    }
+
+    #[test]
+    #[should_panic(expected = "should have an entry per key of the plan")]
+    fn bst_to_tree_too_many_entries() {
+        let (_, plan) = optimal_bst(&[0.5, 0.5], &[0.0; 3]);
+
+        plan.to_tree([(1, ()), (2, ()), (3, ())]);
+    }
+
+    #[test]
+    #[should_panic(expected = "keys should be strictly increasing")]
+    fn bst_to_tree_unsorted_keys() {
+        let (_, plan) = optimal_bst(&[0.5, 0.5], &[0.0; 3]);
+
+        plan.to_tree([(2, ()), (1, ())]);
+    }
+
+    #[test]
+    fn optimal_bst_random() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+
+        for n in [1, 2, 7, 30] {
+            let weights: Vec<f64> = (0..2 * n + 1)
+                .map(|_| rng.gen_in_range(1..100) as f64)
+                .collect();
+            let total: f64 = weights.iter().sum();
+            let p: Vec<f64> = weights[..n].iter().map(|w| w / total).collect();
+            let q: Vec<f64> = weights[n..].iter().map(|w| w / total).collect();
+
+            let (cost, plan) = optimal_bst(&p, &q);
+            let (knuth_cost, knuth_plan) = optimal_bst_knuth(&p, &q);
+
+            assert!((cost - knuth_cost).abs() < 1e-9);
+            assert!((cost - bst_cost(&plan, &p, &q, 0)).abs() < 1e-9);
+            assert!((cost - bst_cost(&knuth_plan, &p, &q, 0)).abs() < 1e-9);
+
+            let mut keys = knuth_plan.preorder();
+
+            keys.sort_unstable();
+            assert_eq!((0..n).collect::<Vec<_>>(), keys);
+        }
+    }
+
     #[test]
     fn edit_distance_test() {
         let x = "algorithm";
         let y = "altruistic";
 
         assert_eq!(10, edit_distance(x, y).len());
+
+        // characters rather than bytes
+        let ops = edit_distance("año", "ano");
+        let replaced = ops
+            .iter()
+            .filter(|op| matches!(op, EditOperations::Replace('ñ', 'n')))
+            .count();
+
+        assert_eq!((3, 1), (ops.len(), replaced));
     }
 
     #[test]
@@ -874,6 +1299,39 @@ mod tests {
         assert_eq!(40, string_breaking(len, &breaks));
     }
 
+    fn random_items(rng: &mut Wyhash64RNG, n: usize) -> Vec<Item> {
+        (0..n)
+            .map(|_| {
+                Item::new(
+                    rng.gen_in_range(1..40) as u32,
+                    rng.gen_in_range(1..20) as usize,
+                )
+            })
+            .collect()
+    }
+
+    fn total(items: &[Item], chosen: &[usize]) -> (u64, usize) {
+        chosen.iter().fold((0, 0), |(v, w), &i| {
+            (v + u64::from(items[i].value()), w + items[i].weight())
+        })
+    }
+
+    // Best value over all subsets of a multiset given as a list of item indices
+    fn brute_force_knapsack(items: &[Item], pool: &[usize], capacity: usize) -> u64 {
+        (0..1_u32 << pool.len())
+            .map(|mask| {
+                let subset: Vec<usize> = (0..pool.len())
+                    .filter(|b| mask >> b & 1 == 1)
+                    .map(|b| pool[b])
+                    .collect();
+                total(items, &subset)
+            })
+            .filter(|&(_, w)| w <= capacity)
+            .map(|(v, _)| v)
+            .max()
+            .unwrap()
+    }
+
     #[test]
     fn binary_knapsack_test() {
         let items = [
@@ -896,4 +1354,140 @@ mod tests {
 
         assert_eq!(220, total_value);
     }
+
+    #[test]
+    fn knapsack_01_test() {
+        let items = [Item::new(60, 10), Item::new(100, 20), Item::new(120, 30)];
+        let max_weight = 50;
+
+        assert_eq!(vec![1, 2], knapsack_01(&items, max_weight));
+        assert_eq!(vec![1, 2], knapsack_by_value(&items, max_weight));
+        assert!(knapsack_01(&items, 5).is_empty());
+    }
+
+    #[test]
+    fn knapsack_random() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+
+        for n in [0, 1, 5, 12] {
+            let items = random_items(&mut rng, n);
+            let all: Vec<usize> = (0..n).collect();
+
+            for capacity in [0, 7, 30, 100] {
+                let best = brute_force_knapsack(&items, &all, capacity);
+
+                for chosen in [
+                    knapsack_01(&items, capacity),
+                    knapsack_by_value(&items, capacity),
+                ] {
+                    let (v, w) = total(&items, &chosen);
+
+                    assert_eq!(best, v);
+                    assert!(w <= capacity);
+                    assert!(chosen.windows(2).all(|p| p[0] < p[1]));
+                }
+
+                for epsilon in [0.5, 0.1] {
+                    let (v, w) = total(&items, &knapsack_fptas(&items, capacity, epsilon));
+
+                    assert!(w <= capacity);
+                    assert!(v as f64 >= (1.0 - epsilon) * best as f64);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn huge_weights() {
+        let items = [
+            Item::new(3, 4_000_000_000),
+            Item::new(4, 5_000_000_000),
+            Item::new(5, 6_000_000_000),
+        ];
+
+        assert_eq!(vec![0, 1], knapsack_by_value(&items, 9_000_000_000));
+        assert_eq!(vec![0, 1], knapsack_fptas(&items, 9_000_000_000, 0.01));
+    }
+
+    #[test]
+    #[should_panic(expected = "item 1 is free and valuable")]
+    fn unbounded_free_item() {
+        unbounded_knapsack(&[Item::new(1, 1), Item::new(1, 0)], 5);
+    }
+
+    #[test]
+    fn unbounded_and_bounded() {
+        let items = [
+            Item::new(10, 5),
+            Item::new(40, 4),
+            Item::new(30, 6),
+            Item::new(50, 3),
+        ];
+
+        assert_eq!(vec![3, 3, 3], unbounded_knapsack(&items, 10));
+        assert_eq!(vec![1, 3], bounded_knapsack(&items, &[1, 1, 1, 1], 10));
+        assert_eq!(vec![3, 3, 3], bounded_knapsack(&items, &[0, 5, 0, 5], 10));
+
+        // a bundle worth more than any single item can be
+        assert_eq!(
+            vec![0, 0, 0],
+            bounded_knapsack(&[Item::new(3_000_000_000, 1)], &[3], 10)
+        );
+
+        let mut rng = Wyhash64RNG::from_seed(123);
+
+        for _ in 0..10 {
+            let items = random_items(&mut rng, 4);
+            let counts: Vec<usize> = (0..4).map(|_| rng.gen_in_range(0..4) as usize).collect();
+            let pool: Vec<usize> = (0..4)
+                .flat_map(|i| std::iter::repeat_n(i, counts[i]))
+                .collect();
+            let capacity = rng.gen_in_range(0..60) as usize;
+
+            let chosen = bounded_knapsack(&items, &counts, capacity);
+            let (v, w) = total(&items, &chosen);
+
+            assert_eq!(brute_force_knapsack(&items, &pool, capacity), v);
+            assert!(w <= capacity);
+            assert!((0..4).all(|i| chosen.iter().filter(|&&c| c == i).count() <= counts[i]));
+
+            // as many copies as could ever fit make the bounded problem unbounded
+            let unlimited = vec![capacity; 4];
+            let (v, w) = total(&items, &unbounded_knapsack(&items, capacity));
+
+            assert!(w <= capacity);
+            assert_eq!(
+                total(&items, &bounded_knapsack(&items, &unlimited, capacity)).0,
+                v
+            );
+        }
+    }
+
+    #[test]
+    fn multi_constraint() {
+        let values = [10, 13, 7, 8];
+        let weights = Array2D::from_slice(2, 4, &[3, 2, 4, 3, 2, 4, 1, 1]);
+
+        assert_eq!(
+            vec![1, 3],
+            multi_constraint_knapsack(&values, &weights, &[5, 5])
+        );
+        assert_eq!(
+            vec![0, 2, 3],
+            multi_constraint_knapsack(&values, &weights, &[6, 7])
+        );
+
+        // a single constraint is the ordinary knapsack
+        let mut rng = Wyhash64RNG::from_seed(123);
+        let items = random_items(&mut rng, 8);
+        let values: Vec<u32> = items.iter().map(Item::value).collect();
+        let weights: Vec<usize> = items.iter().map(Item::weight).collect();
+        let chosen =
+            multi_constraint_knapsack(&values, &Array2D::from_slice(1, 8, &weights), &[40]);
+
+        assert_eq!(
+            total(&items, &knapsack_01(&items, 40)).0,
+            total(&items, &chosen).0
+        );
+    }
 }
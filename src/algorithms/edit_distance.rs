@@ -0,0 +1,554 @@
+//! Edit distances over any sequence, with configurable operation costs (CLRS problem 15-5), a
+//! distance-only mode in O(min(m, n)) space, unrestricted Damerau-Levenshtein distance, and
+//! Myers' bit-parallel algorithm for patterns of up to 64 elements.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::data_structures::array_2d::Array2D;
+
+/// Cost of each operation turning `x` into `y`. `twiddle` exchanges two adjacent elements and
+/// `kill` deletes the rest of `x` as the very last operation; `None` disables them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EditCosts {
+    pub copy: u64,
+    pub replace: u64,
+    pub delete: u64,
+    pub insert: u64,
+    pub twiddle: Option<u64>,
+    pub kill: Option<u64>,
+}
+
+impl EditCosts {
+    pub const LEVENSHTEIN: Self = Self {
+        copy: 0,
+        replace: 1,
+        delete: 1,
+        insert: 1,
+        twiddle: None,
+        kill: None,
+    };
+
+    /// Levenshtein with transpositions of adjacent elements, where no element is edited more
+    /// than once (the "optimal string alignment" distance).
+    pub const DAMERAU: Self = Self {
+        twiddle: Some(1),
+        ..Self::LEVENSHTEIN
+    };
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EditOp {
+    Copy,
+    Replace,
+    Delete,
+    Insert,
+    Twiddle,
+    /// Delete everything left of `x`.
+    Kill,
+}
+
+fn twiddles<T: PartialEq>(x: &[T], y: &[T], i: usize, j: usize) -> bool {
+    i >= 2 && j >= 2 && x[i - 1] == y[j - 2] && x[i - 2] == y[j - 1]
+}
+
+/// Cheapest sequence of operations turning `x` into `y`, and its cost.
+pub fn edit_script<T: PartialEq>(x: &[T], y: &[T], costs: &EditCosts) -> (u64, Vec<EditOp>) {
+    let (m, n) = (x.len(), y.len());
+    let mut d = Array2D::new(0, n + 1, m + 1);
+
+    for i in 0..=m {
+        for j in 0..=n {
+            d[i][j] = if i == 0 {
+                j as u64 * costs.insert
+            } else if j == 0 {
+                i as u64 * costs.delete
+            } else {
+                step_options(x, y, i, j, costs, |a, b| d[a][b])
+                    .map(|(cost, _)| cost)
+                    .min()
+                    .expect("delete and insert are always options")
+            };
+        }
+    }
+
+    let mut cost = d[m][n];
+    let mut i = m;
+    let mut j = n;
+    let mut ops = vec![];
+
+    if let Some(kill) = costs.kill {
+        if let Some(k) = (0..m)
+            .filter(|&k| d[k][n] + kill < cost)
+            .min_by_key(|&k| d[k][n])
+        {
+            cost = d[k][n] + kill;
+            i = k;
+            ops.push(EditOp::Kill);
+        }
+    }
+
+    while i > 0 || j > 0 {
+        let op = if i == 0 {
+            EditOp::Insert
+        } else if j == 0 {
+            EditOp::Delete
+        } else {
+            step_options(x, y, i, j, costs, |a, b| d[a][b])
+                .find(|&(c, _)| c == d[i][j])
+                .expect("some operation should reach the cell")
+                .1
+        };
+
+        match op {
+            EditOp::Copy | EditOp::Replace => (i, j) = (i - 1, j - 1),
+            EditOp::Twiddle => (i, j) = (i - 2, j - 2),
+            EditOp::Delete => i -= 1,
+            EditOp::Insert => j -= 1,
+            EditOp::Kill => unreachable!(),
+        }
+
+        ops.push(op);
+    }
+
+    ops.reverse();
+
+    (cost, ops)
+}
+
+// Every way of reaching cell (i, j), with i, j >= 1, and what it costs
+fn step_options<'a, T: PartialEq>(
+    x: &'a [T],
+    y: &'a [T],
+    i: usize,
+    j: usize,
+    costs: &'a EditCosts,
+    d: impl Fn(usize, usize) -> u64 + 'a,
+) -> impl Iterator<Item = (u64, EditOp)> + 'a {
+    let same = x[i - 1] == y[j - 1];
+    let pair = if same {
+        (d(i - 1, j - 1) + costs.copy, EditOp::Copy)
+    } else {
+        (d(i - 1, j - 1) + costs.replace, EditOp::Replace)
+    };
+    let twiddle = costs
+        .twiddle
+        .filter(|_| twiddles(x, y, i, j))
+        .map(|t| (d(i - 2, j - 2) + t, EditOp::Twiddle));
+
+    [
+        Some(pair),
+        twiddle,
+        Some((d(i - 1, j) + costs.delete, EditOp::Delete)),
+        Some((d(i, j - 1) + costs.insert, EditOp::Insert)),
+    ]
+    .into_iter()
+    .flatten()
+}
+
+/// Same cost as `edit_script`, keeping only the last three rows of the table, each as long as the
+/// shorter sequence.
+pub fn edit_distance<T: PartialEq>(x: &[T], y: &[T], costs: &EditCosts) -> u64 {
+    if x.len() >= y.len() {
+        let (last_row, last_col) = rolling_rows(x, y, costs.delete, costs.insert, costs);
+
+        // the last column of every row but the last ends with all of `y` produced
+        let killed = match (last_col, costs.kill) {
+            (Some(c), Some(kill)) => c + kill,
+            _ => u64::MAX,
+        };
+
+        last_row[y.len()].min(killed)
+    } else {
+        // rows follow `y` instead, so deleting consumes a column and inserting a row
+        let (last_row, _) = rolling_rows(y, x, costs.insert, costs.delete, costs);
+        let killed = costs.kill.map_or(u64::MAX, |kill| {
+            last_row[..x.len()]
+                .iter()
+                .min()
+                .map_or(u64::MAX, |&c| c + kill)
+        });
+
+        last_row[x.len()].min(killed)
+    }
+}
+
+// Fills the table with a row per element of `r` and a column per element of `c`. Returns the
+// last row and the least value of the last column above it.
+fn rolling_rows<T: PartialEq>(
+    r: &[T],
+    c: &[T],
+    row_cost: u64,
+    col_cost: u64,
+    costs: &EditCosts,
+) -> (Vec<u64>, Option<u64>) {
+    let n = c.len();
+    let mut before: Vec<u64> = vec![0; n + 1];
+    let mut prev: Vec<u64> = (0..=n as u64).map(|j| j * col_cost).collect();
+    let mut cur = vec![0; n + 1];
+    let mut last_col = None;
+
+    for i in 1..=r.len() {
+        last_col = Some(last_col.map_or(prev[n], |l: u64| l.min(prev[n])));
+        cur[0] = i as u64 * row_cost;
+
+        for j in 1..=n {
+            let pair = if r[i - 1] == c[j - 1] {
+                costs.copy
+            } else {
+                costs.replace
+            };
+
+            let mut best = (prev[j - 1] + pair)
+                .min(prev[j] + row_cost)
+                .min(cur[j - 1] + col_cost);
+
+            if let Some(t) = costs.twiddle.filter(|_| twiddles(r, c, i, j)) {
+                best = best.min(before[j - 2] + t);
+            }
+
+            cur[j] = best;
+        }
+
+        std::mem::swap(&mut before, &mut prev);
+        std::mem::swap(&mut prev, &mut cur);
+    }
+
+    (prev, last_col)
+}
+
+/// Levenshtein distance between the characters of two strings, rather than their bytes.
+pub fn str_edit_distance(x: &str, y: &str) -> u64 {
+    let x: Vec<char> = x.chars().collect();
+    let y: Vec<char> = y.chars().collect();
+
+    edit_distance(&x, &y, &EditCosts::LEVENSHTEIN)
+}
+
+/// Damerau-Levenshtein distance with unit costs, where unlike `EditCosts::DAMERAU` the
+/// transposed elements may be edited again, e.g. "ca" -> "ac" -> "abc" costs 2. Follows Lowrance
+/// and Wagner, remembering the last row where each element was seen.
+pub fn damerau_levenshtein<T: Eq + Hash>(x: &[T], y: &[T]) -> usize {
+    let (m, n) = (x.len(), y.len());
+    let infinity = m + n;
+
+    // shifted by one so that row and column 0 can hold the sentinel
+    let mut d = Array2D::new(0, n + 2, m + 2);
+    let mut last_row: HashMap<&T, usize> = HashMap::new();
+
+    d[0][0] = infinity;
+
+    for i in 0..=m {
+        d[i + 1][0] = infinity;
+        d[i + 1][1] = i;
+    }
+
+    for j in 0..=n {
+        d[0][j + 1] = infinity;
+        d[1][j + 1] = j;
+    }
+
+    for i in 1..=m {
+        let mut last_col = 0;
+
+        for j in 1..=n {
+            let k = last_row.get(&y[j - 1]).copied().unwrap_or(0);
+            let l = last_col;
+            let cost = usize::from(x[i - 1] != y[j - 1]);
+
+            if cost == 0 {
+                last_col = j;
+            }
+
+            d[i + 1][j + 1] = (d[i][j] + cost)
+                .min(d[i + 1][j] + 1)
+                .min(d[i][j + 1] + 1)
+                .min(d[k][l] + (i - k - 1) + 1 + (j - l - 1));
+        }
+
+        last_row.insert(&x[i - 1], i);
+    }
+
+    d[m + 1][n + 1]
+}
+
+// Bit `i` of `peq[c]` is set when `pattern[i] == c`
+fn pattern_masks<T: Eq + Hash>(pattern: &[T]) -> HashMap<&T, u64> {
+    assert!(
+        pattern.len() <= 64,
+        "pattern should have at most 64 elements"
+    );
+
+    let mut peq: HashMap<&T, u64> = HashMap::new();
+
+    for (i, c) in pattern.iter().enumerate() {
+        *peq.entry(c).or_default() |= 1 << i;
+    }
+
+    peq
+}
+
+// One column of Myers' algorithm, encoding the vertical differences between adjacent cells as
+// the bit vectors `pv` (+1) and `mv` (-1). Returns the horizontal difference at the last row.
+fn myers_step(eq: u64, pv: &mut u64, mv: &mut u64, last: u64, top_increments: bool) -> i64 {
+    let xv = eq | *mv;
+    let xh = ((eq & *pv).wrapping_add(*pv) ^ *pv) | eq;
+    let mut ph = *mv | !(xh | *pv);
+    let mut mh = *pv & xh;
+
+    let delta = if ph & last != 0 {
+        1
+    } else if mh & last != 0 {
+        -1
+    } else {
+        0
+    };
+
+    ph <<= 1;
+    mh <<= 1;
+
+    // row 0 of the table grows by one per column for a distance, but stays 0 for a search
+    if top_increments {
+        ph |= 1;
+    }
+
+    *pv = mh | !(xv | ph);
+    *mv = ph & xv;
+
+    delta
+}
+
+/// Levenshtein distance between a pattern of at most 64 elements and a text of any length,
+/// computing a whole column of the table per text element with a few word operations (Hyyrö's
+/// variant of Myers' algorithm).
+pub fn myers_distance<T: Eq + Hash>(pattern: &[T], text: &[T]) -> usize {
+    if pattern.is_empty() {
+        return text.len();
+    }
+
+    let peq = pattern_masks(pattern);
+    let last = 1 << (pattern.len() - 1);
+    let mut pv = u64::MAX;
+    let mut mv = 0;
+    let mut score = pattern.len() as i64;
+
+    for c in text {
+        let eq = peq.get(c).copied().unwrap_or(0);
+
+        score += myers_step(eq, &mut pv, &mut mv, last, true);
+    }
+
+    score as usize
+}
+
+/// End positions (exclusive) in `text` of the substrings within edit distance `k` of `pattern`,
+/// together with that distance, using Myers' bit-parallel algorithm.
+pub fn myers_search<T: Eq + Hash>(pattern: &[T], text: &[T], k: usize) -> Vec<(usize, usize)> {
+    if pattern.is_empty() {
+        return (0..=text.len()).map(|end| (end, 0)).collect();
+    }
+
+    let peq = pattern_masks(pattern);
+    let last = 1 << (pattern.len() - 1);
+    let mut pv = u64::MAX;
+    let mut mv = 0;
+    let mut score = pattern.len() as i64;
+    let mut out = vec![];
+
+    if pattern.len() <= k {
+        out.push((0, pattern.len()));
+    }
+
+    for (i, c) in text.iter().enumerate() {
+        let eq = peq.get(c).copied().unwrap_or(0);
+
+        score += myers_step(eq, &mut pv, &mut mv, last, false);
+
+        if score as usize <= k {
+            out.push((i + 1, score as usize));
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rand::Wyhash64RNG;
+
+    fn random_string(rng: &mut Wyhash64RNG, n: usize, alphabet: &[u8]) -> Vec<u8> {
+        (0..n)
+            .map(|_| alphabet[rng.gen_in_range(0..alphabet.len() as u64) as usize])
+            .collect()
+    }
+
+    // Applies the operations, checking their cost along the way
+    fn apply(x: &[u8], y: &[u8], ops: &[EditOp], costs: &EditCosts) -> (Vec<u8>, u64) {
+        let (mut i, mut j) = (0, 0);
+        let mut out = vec![];
+        let mut cost = 0;
+
+        for op in ops {
+            match op {
+                EditOp::Copy => {
+                    assert_eq!(x[i], y[j]);
+                    out.push(x[i]);
+                    (i, j, cost) = (i + 1, j + 1, cost + costs.copy);
+                }
+                EditOp::Replace => {
+                    out.push(y[j]);
+                    (i, j, cost) = (i + 1, j + 1, cost + costs.replace);
+                }
+                EditOp::Delete => (i, cost) = (i + 1, cost + costs.delete),
+                EditOp::Insert => {
+                    out.push(y[j]);
+                    (j, cost) = (j + 1, cost + costs.insert);
+                }
+                EditOp::Twiddle => {
+                    out.extend([x[i + 1], x[i]]);
+                    (i, j, cost) = (i + 2, j + 2, cost + costs.twiddle.unwrap());
+                }
+                EditOp::Kill => (i, cost) = (x.len(), cost + costs.kill.unwrap()),
+            }
+        }
+
+        assert_eq!(x.len(), i);
+
+        (out, cost)
+    }
+
+    #[test]
+    fn clrs_example() {
+        // CLRS problem 15-5: "algorithm" to "altruistic"
+        let costs = EditCosts {
+            copy: 1,
+            replace: 4,
+            delete: 2,
+            insert: 3,
+            twiddle: Some(1),
+            kill: Some(1),
+        };
+        let (cost, ops) = edit_script(b"algorithm", b"altruistic", &costs);
+
+        assert_eq!(
+            (b"altruistic".to_vec(), cost),
+            apply(b"algorithm", b"altruistic", &ops, &costs)
+        );
+        assert_eq!(cost, edit_distance(b"algorithm", b"altruistic", &costs));
+        assert_eq!(Some(&EditOp::Kill), ops.last());
+    }
+
+    #[test]
+    fn levenshtein() {
+        assert_eq!(
+            3,
+            edit_distance(b"kitten", b"sitting", &EditCosts::LEVENSHTEIN)
+        );
+        assert_eq!(
+            3,
+            edit_distance(b"sitting", b"kitten", &EditCosts::LEVENSHTEIN)
+        );
+        assert_eq!(0, edit_distance::<u8>(b"", b"", &EditCosts::LEVENSHTEIN));
+        assert_eq!(4, edit_distance(b"", b"abcd", &EditCosts::LEVENSHTEIN));
+
+        assert_eq!(1, str_edit_distance("naïve", "naive"));
+        assert_eq!(1, str_edit_distance("日本語", "日本"));
+        assert_eq!(2, str_edit_distance("ab", "ba"));
+    }
+
+    #[test]
+    fn transpositions() {
+        assert_eq!(1, edit_distance(b"ab", b"ba", &EditCosts::DAMERAU));
+        assert_eq!(2, edit_distance(b"ab", b"ba", &EditCosts::LEVENSHTEIN));
+
+        // a restricted transposition can't be edited again, an unrestricted one can
+        assert_eq!(3, edit_distance(b"ca", b"abc", &EditCosts::DAMERAU));
+        assert_eq!(2, damerau_levenshtein(b"ca", b"abc"));
+        assert_eq!(0, damerau_levenshtein::<u8>(b"", b""));
+        assert_eq!(3, damerau_levenshtein(b"abc", b""));
+    }
+
+    #[test]
+    fn random_modes_agree() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+        let costs = [
+            EditCosts::LEVENSHTEIN,
+            EditCosts::DAMERAU,
+            EditCosts {
+                copy: 1,
+                replace: 4,
+                delete: 2,
+                insert: 3,
+                twiddle: Some(1),
+                kill: Some(1),
+            },
+            EditCosts {
+                copy: 0,
+                replace: 5,
+                delete: 1,
+                insert: 2,
+                twiddle: Some(3),
+                kill: Some(4),
+            },
+        ];
+
+        for _ in 0..50 {
+            let m = rng.gen_in_range(0..12) as usize;
+            let n = rng.gen_in_range(0..12) as usize;
+            let x = random_string(&mut rng, m, b"abc");
+            let y = random_string(&mut rng, n, b"abc");
+
+            for costs in &costs {
+                let (cost, ops) = edit_script(&x, &y, costs);
+
+                assert_eq!((y.clone(), cost), apply(&x, &y, &ops, costs));
+                assert_eq!(cost, edit_distance(&x, &y, costs), "{x:?} {y:?} {costs:?}");
+            }
+
+            let plain = edit_distance(&x, &y, &EditCosts::LEVENSHTEIN) as usize;
+            let osa = edit_distance(&x, &y, &EditCosts::DAMERAU) as usize;
+            let dl = damerau_levenshtein(&x, &y);
+
+            assert_eq!(plain, myers_distance(&x, &y));
+            assert!(dl <= osa && osa <= plain);
+        }
+    }
+
+    #[test]
+    fn myers() {
+        assert_eq!(3, myers_distance(b"kitten", b"sitting"));
+        assert_eq!(5, myers_distance(b"", b"hello"));
+
+        let mut rng = Wyhash64RNG::from_seed(123);
+        let pattern = random_string(&mut rng, 64, b"ab");
+        let text = random_string(&mut rng, 150, b"ab");
+
+        assert_eq!(
+            edit_distance(&pattern, &text, &EditCosts::LEVENSHTEIN) as usize,
+            myers_distance(&pattern, &text)
+        );
+
+        let found = myers_search(b"survey", b"a surgery survey", 2);
+        let ends: Vec<usize> = found.iter().map(|&(end, _)| end).collect();
+
+        assert!(ends.contains(&16));
+        assert_eq!(Some(&(16, 0)), found.last());
+
+        // every reported end matches some substring within the distance
+        let text = random_string(&mut rng, 60, b"abc");
+        let pattern = b"abcab";
+
+        for end in 0..=text.len() {
+            let best = (0..=end)
+                .map(|start| myers_distance(pattern, &text[start..end]))
+                .min()
+                .unwrap();
+            let reported = myers_search(pattern, &text, 2)
+                .into_iter()
+                .find(|&(e, _)| e == end);
+
+            assert_eq!(if best <= 2 { Some((end, best)) } else { None }, reported);
+        }
+    }
+}
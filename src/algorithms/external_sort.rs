@@ -0,0 +1,379 @@
+//! External merge sort for inputs that do not fit in memory. The input is cut into chunks that fit
+//! the memory budget, each chunk is sorted and spilled to a temporary file as a run, and the runs
+//! are merged with a heap, at most `fan_in` at a time, until one is left.
+//!
+//! Records are compared as whole byte strings, so the output is in lexicographic order.
+
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::algorithms::natural_merge_sort::natural_merge_sort_by;
+use crate::data_structures::fib_heap::FibHeap;
+
+// Unique suffix for temporary file names within the process
+static NEXT_RUN_ID: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordFormat {
+    /// Records of exactly this many bytes; the input length must be a multiple of it
+    Fixed(usize),
+    /// Newline-terminated lines, the last one possibly without a terminator. Every line of the
+    /// output is terminated.
+    Lines,
+}
+
+pub struct ExternalSort {
+    format: RecordFormat,
+    memory_budget: usize,
+    fan_in: usize,
+    temp_dir: PathBuf,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ExternalSortStats {
+    pub records: usize,
+    /// Number of sorted chunks spilled to disk, zero if the whole input fit in memory
+    pub runs: usize,
+    /// Number of merge rounds, the last one writing to the output
+    pub merge_passes: usize,
+}
+
+impl ExternalSort {
+    pub fn new(format: RecordFormat) -> Self {
+        if let RecordFormat::Fixed(size) = format {
+            assert!(size > 0, "record size should be positive");
+        }
+
+        Self {
+            format,
+            memory_budget: 64 << 20,
+            fan_in: 16,
+            temp_dir: std::env::temp_dir(),
+        }
+    }
+
+    /// Approximate number of bytes of records held in memory at once, counting the per-record
+    /// bookkeeping.
+    pub fn memory_budget(mut self, bytes: usize) -> Self {
+        self.memory_budget = bytes;
+        self
+    }
+
+    /// Maximum number of runs merged at once.
+    pub fn fan_in(mut self, k: usize) -> Self {
+        assert!(k >= 2, "fan-in should be at least 2");
+
+        self.fan_in = k;
+        self
+    }
+
+    pub fn temp_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.temp_dir = dir.into();
+        self
+    }
+
+    pub fn sort<R: Read, W: Write>(&self, input: R, output: W) -> io::Result<ExternalSortStats> {
+        let mut input = BufReader::new(input);
+        let mut output = BufWriter::new(output);
+        let mut stats = ExternalSortStats::default();
+        let mut runs = Vec::new();
+        let mut chunk = Vec::new();
+        let mut chunk_size = 0;
+
+        while let Some(record) = self.read_record(&mut input)? {
+            chunk_size += record.len() + size_of::<Vec<u8>>();
+            chunk.push(record);
+            stats.records += 1;
+
+            if chunk_size >= self.memory_budget {
+                runs.push(self.spill(&mut chunk)?);
+                chunk_size = 0;
+            }
+        }
+
+        if runs.is_empty() {
+            sort_chunk(&mut chunk);
+
+            for record in &chunk {
+                self.write_record(&mut output, record)?;
+            }
+
+            return output.flush().map(|()| stats);
+        }
+
+        if !chunk.is_empty() {
+            runs.push(self.spill(&mut chunk)?);
+        }
+
+        stats.runs = runs.len();
+
+        while runs.len() > self.fan_in {
+            let mut merged = Vec::with_capacity(runs.len().div_ceil(self.fan_in));
+
+            for group in runs.chunks(self.fan_in) {
+                let run = TempRun::create(&self.temp_dir)?;
+                let mut w = BufWriter::new(File::create(&run.path)?);
+
+                self.merge(group, &mut w)?;
+                w.flush()?;
+
+                merged.push(run);
+            }
+
+            runs = merged;
+            stats.merge_passes += 1;
+        }
+
+        self.merge(&runs, &mut output)?;
+        output.flush()?;
+
+        stats.merge_passes += 1;
+
+        Ok(stats)
+    }
+
+    fn spill(&self, chunk: &mut Vec<Vec<u8>>) -> io::Result<TempRun> {
+        let run = TempRun::create(&self.temp_dir)?;
+        let mut w = BufWriter::new(File::create(&run.path)?);
+
+        sort_chunk(chunk);
+
+        for record in chunk.drain(..) {
+            self.write_record(&mut w, &record)?;
+        }
+
+        w.flush()?;
+
+        Ok(run)
+    }
+
+    // Heap entries carry the index of their run, to know which reader to refill from
+    fn merge(&self, runs: &[TempRun], output: &mut impl Write) -> io::Result<()> {
+        let mut readers = Vec::with_capacity(runs.len());
+        let mut heap = FibHeap::new();
+
+        for (i, run) in runs.iter().enumerate() {
+            let mut r = BufReader::new(File::open(&run.path)?);
+
+            if let Some(record) = self.read_record(&mut r)? {
+                heap.insert((record, i));
+            }
+
+            readers.push(r);
+        }
+
+        while let Some((record, i)) = heap.extract_min() {
+            self.write_record(output, &record)?;
+
+            if let Some(next) = self.read_record(&mut readers[i])? {
+                heap.insert((next, i));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_record(&self, r: &mut impl BufRead) -> io::Result<Option<Vec<u8>>> {
+        match self.format {
+            RecordFormat::Fixed(size) => {
+                let mut record = vec![0; size];
+                let mut filled = 0;
+
+                while filled < size {
+                    match r.read(&mut record[filled..]) {
+                        Ok(0) => break,
+                        Ok(n) => filled += n,
+                        Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                        Err(e) => return Err(e),
+                    }
+                }
+
+                match filled {
+                    0 => Ok(None),
+                    n if n == size => Ok(Some(record)),
+                    _ => Err(io::Error::new(
+                        ErrorKind::InvalidData,
+                        "input ends with a partial record",
+                    )),
+                }
+            }
+            RecordFormat::Lines => {
+                let mut line = Vec::new();
+
+                if r.read_until(b'\n', &mut line)? == 0 {
+                    return Ok(None);
+                }
+
+                if line.last() == Some(&b'\n') {
+                    line.pop();
+                }
+
+                Ok(Some(line))
+            }
+        }
+    }
+
+    fn write_record(&self, w: &mut impl Write, record: &[u8]) -> io::Result<()> {
+        w.write_all(record)?;
+
+        if self.format == RecordFormat::Lines {
+            w.write_all(b"\n")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn sort_chunk(chunk: &mut [Vec<u8>]) {
+    natural_merge_sort_by(chunk, Ord::cmp);
+}
+
+// Temporary file that is deleted when dropped
+struct TempRun {
+    path: PathBuf,
+}
+
+impl TempRun {
+    fn create(dir: &Path) -> io::Result<Self> {
+        let id = NEXT_RUN_ID.fetch_add(1, Ordering::Relaxed);
+        let name = format!("rad-external-sort-{}-{}", std::process::id(), id);
+        let path = dir.join(name);
+
+        File::create(&path)?;
+
+        Ok(Self { path })
+    }
+}
+
+impl Drop for TempRun {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rand::Wyhash64RNG;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rad-test-{}-{}", name, std::process::id()));
+
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    fn random_lines(n: usize) -> Vec<u8> {
+        let mut rng = Wyhash64RNG::from_seed(123);
+        let mut input = Vec::new();
+
+        for _ in 0..n {
+            let len = rng.gen_in_range(0..12);
+
+            for _ in 0..len {
+                input.push(b'a' + rng.gen_in_range(0..4) as u8);
+            }
+
+            input.push(b'\n');
+        }
+
+        input
+    }
+
+    fn expected_lines(input: &[u8]) -> Vec<u8> {
+        let mut lines: Vec<&[u8]> = input.split(|&b| b == b'\n').collect();
+
+        // the input ends with a newline, which leaves an empty piece after it
+        lines.pop();
+        lines.sort();
+
+        lines
+            .iter()
+            .flat_map(|l| l.iter().chain(b"\n"))
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn in_memory() {
+        let input = b"pear\napple\nfig\nbanana";
+        let mut output = Vec::new();
+
+        let stats = ExternalSort::new(RecordFormat::Lines)
+            .sort(&input[..], &mut output)
+            .unwrap();
+
+        assert_eq!(b"apple\nbanana\nfig\npear\n", output.as_slice());
+        assert_eq!(4, stats.records);
+        assert_eq!(0, stats.runs);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn lines() {
+        let dir = temp_dir("lines");
+        let input = random_lines(5000);
+        let mut output = Vec::new();
+
+        let stats = ExternalSort::new(RecordFormat::Lines)
+            .memory_budget(4096)
+            .fan_in(4)
+            .temp_dir(&dir)
+            .sort(input.as_slice(), &mut output)
+            .unwrap();
+
+        assert_eq!(expected_lines(&input), output);
+        assert_eq!(5000, stats.records);
+        assert!(stats.runs > 16);
+        assert!(stats.merge_passes >= 3);
+
+        // every temporary file is removed
+        assert_eq!(0, fs::read_dir(&dir).unwrap().count());
+        fs::remove_dir(dir).unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn fixed_records() {
+        let dir = temp_dir("fixed");
+        let mut rng = Wyhash64RNG::from_seed(123);
+        let mut input = Vec::new();
+
+        // 2-byte prefix with many repeats, followed by the 2-byte big-endian record number
+        for i in 0..3000_u16 {
+            input.push(b'x');
+            input.push(rng.gen_in_range(0..10) as u8);
+            input.extend(i.to_be_bytes());
+        }
+
+        let mut expected: Vec<&[u8]> = input.chunks(4).collect();
+        expected.sort();
+
+        let mut output = Vec::new();
+        let stats = ExternalSort::new(RecordFormat::Fixed(4))
+            .memory_budget(1000)
+            .fan_in(2)
+            .temp_dir(&dir)
+            .sort(input.as_slice(), &mut output)
+            .unwrap();
+
+        assert_eq!(expected.concat(), output);
+        assert!(stats.runs > 2);
+
+        fs::remove_dir(dir).unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn partial_record() {
+        let err = ExternalSort::new(RecordFormat::Fixed(4))
+            .sort(&b"abcdef"[..], Vec::new())
+            .unwrap_err();
+
+        assert_eq!(ErrorKind::InvalidData, err.kind());
+    }
+}
@@ -28,6 +28,20 @@ pub struct Item {
     weight: u32,
 }
 
+impl Item {
+    pub fn new(value: u32, weight: u32) -> Self {
+        Self { value, weight }
+    }
+
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+
+    pub fn weight(&self) -> u32 {
+        self.weight
+    }
+}
+
 // casts are u32 -> f64 lesser than original, and back to u32
 #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
 pub fn fractional_knapsack(items: &[Item], max_weight: u32) -> Vec<Item> {
@@ -1,12 +1,36 @@
+use std::cmp::Ordering;
+
 use crate::data_structures::heap::BorrowingHeap;
 
-pub fn heap_sort<T: Ord + Copy>(a: &mut [T]) {
+pub fn heap_sort<T: Ord>(a: &mut [T]) {
     let heap = BorrowingHeap::from_slice(a);
 
     heap.sort();
 }
 
+pub fn heap_sort_by<T, F: FnMut(&T, &T) -> Ordering>(a: &mut [T], mut cmp: F) {
+    let heap = BorrowingHeap::from_slice_by(a, |x, y| cmp(x, y) == Ordering::Less);
+
+    heap.sort();
+}
+
+pub fn heap_sort_by_key<T, K: Ord, F: FnMut(&T) -> K>(a: &mut [T], mut f: F) {
+    let heap = BorrowingHeap::from_slice_by(a, |x, y| f(x) < f(y));
+
+    heap.sort();
+}
+
 #[test]
 fn test() {
     crate::tests::test_sort(heap_sort);
 }
+
+#[test]
+fn by() {
+    crate::tests::test_sort_records(|a| heap_sort_by(a, |x, y| x.key.cmp(&y.key)));
+}
+
+#[test]
+fn by_key() {
+    crate::tests::test_sort_records(|a| heap_sort_by_key(a, |r| r.key));
+}
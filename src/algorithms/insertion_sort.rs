@@ -1,41 +1,90 @@
-pub fn insertion_sort<T: PartialOrd + Copy>(a: &mut [T]) {
+use std::cmp::Ordering;
+use std::mem::ManuallyDrop;
+use std::ptr;
+
+pub fn insertion_sort<T: PartialOrd>(a: &mut [T]) {
+    insertion_sort_impl(a, &mut T::lt);
+}
+
+pub fn insertion_sort_by<T, F: FnMut(&T, &T) -> Ordering>(a: &mut [T], mut cmp: F) {
+    insertion_sort_impl(a, &mut |x, y| cmp(x, y) == Ordering::Less);
+}
+
+pub fn insertion_sort_by_key<T, K: Ord, F: FnMut(&T) -> K>(a: &mut [T], mut f: F) {
+    insertion_sort_impl(a, &mut |x, y| f(x) < f(y));
+}
+
+// The key stays in place while its position is searched for, then is rotated into it
+pub(crate) fn insertion_sort_impl<T, F: FnMut(&T, &T) -> bool>(a: &mut [T], is_less: &mut F) {
     for j in 2..=a.len() {
-        let key = a[j - 1];
         let mut i = j - 1;
 
-        while i >= 1 && a[i - 1] > key {
-            a[i] = a[i - 1];
+        while i >= 1 && is_less(&a[j - 1], &a[i - 1]) {
             i -= 1;
         }
 
-        a[i] = key;
+        a[i..j].rotate_right(1);
     }
 }
 
-// Same as above except uses 0-indexing
-pub fn insertion_sort_2<T: PartialOrd + Copy>(a: &mut [T]) {
-    'outer: for j in 1..a.len() {
-        let key = a[j];
-        let mut i = j - 1;
+// Same as above except uses 0-indexing, and shifts the greater elements up one by one into the
+// hole left by the key
+pub fn insertion_sort_2<T: PartialOrd>(a: &mut [T]) {
+    insertion_sort_2_impl(a, &mut T::lt);
+}
 
-        while a[i] > key {
-            a[i + 1] = a[i];
+pub fn insertion_sort_2_by<T, F: FnMut(&T, &T) -> Ordering>(a: &mut [T], mut cmp: F) {
+    insertion_sort_2_impl(a, &mut |x, y| cmp(x, y) == Ordering::Less);
+}
 
-            if i == 0 {
-                a[i] = key;
-                continue 'outer;
-            }
+pub fn insertion_sort_2_by_key<T, K: Ord, F: FnMut(&T) -> K>(a: &mut [T], mut f: F) {
+    insertion_sort_2_impl(a, &mut |x, y| f(x) < f(y));
+}
 
-            i -= 1;
+// Fills `a[dest]` with the key on drop, so that a panicking `is_less` can't leave the hole open
+struct Hole<'a, T> {
+    a: &'a mut [T],
+    dest: usize,
+    key: ManuallyDrop<T>,
+}
+
+impl<T> Drop for Hole<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: `a[dest]` has been moved out of, so it is overwritten without being dropped
+        unsafe { ptr::write(&mut self.a[self.dest], ManuallyDrop::take(&mut self.key)) }
+    }
+}
+
+fn insertion_sort_2_impl<T, F: FnMut(&T, &T) -> bool>(a: &mut [T], is_less: &mut F) {
+    for j in 1..a.len() {
+        if !is_less(&a[j], &a[j - 1]) {
+            continue;
         }
 
-        a[i + 1] = key;
+        // SAFETY: the hole writes the key back to `a[dest]` however this ends
+        let key = ManuallyDrop::new(unsafe { ptr::read(&a[j]) });
+        let mut hole = Hole {
+            a: &mut *a,
+            dest: j,
+            key,
+        };
+
+        while hole.dest > 0 && is_less(&hole.key, &hole.a[hole.dest - 1]) {
+            let i = hole.dest - 1;
+            let p = hole.a.as_mut_ptr();
+
+            // SAFETY: `a[i]` is moved into the hole, and becomes the hole itself
+            unsafe { ptr::copy_nonoverlapping(p.add(i), p.add(i + 1), 1) }
+
+            hole.dest = i;
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tests::test_stable_sort_records;
 
     #[test]
     fn normal() {
@@ -46,4 +95,24 @@ mod tests {
     fn alternative() {
         crate::tests::test_sort(insertion_sort_2);
     }
+
+    #[test]
+    fn alternative_by() {
+        test_stable_sort_records(|a| insertion_sort_2_by(a, |x, y| x.key.cmp(&y.key)));
+    }
+
+    #[test]
+    fn alternative_by_key() {
+        test_stable_sort_records(|a| insertion_sort_2_by_key(a, |r| r.key));
+    }
+
+    #[test]
+    fn by() {
+        test_stable_sort_records(|a| insertion_sort_by(a, |x, y| x.key.cmp(&y.key)));
+    }
+
+    #[test]
+    fn by_key() {
+        test_stable_sort_records(|a| insertion_sort_by_key(a, |r| r.key));
+    }
 }
@@ -0,0 +1,174 @@
+//! Introsort: quicksort with median-of-three pivots that falls back to heapsort once the
+//! recursion gets too deep, and leaves small slices to insertion sort
+
+use std::cmp::Ordering;
+
+use crate::algorithms::insertion_sort::insertion_sort_impl;
+use crate::data_structures::heap::BorrowingHeap;
+
+pub(crate) const INSERTION_THRESHOLD: usize = 16;
+
+pub fn introsort<T: PartialOrd>(a: &mut [T]) {
+    introsort_impl(a, &mut T::lt);
+}
+
+pub fn introsort_by<T, F: FnMut(&T, &T) -> Ordering>(a: &mut [T], mut cmp: F) {
+    introsort_impl(a, &mut |x, y| cmp(x, y) == Ordering::Less);
+}
+
+pub fn introsort_by_key<T, K: Ord, F: FnMut(&T) -> K>(a: &mut [T], mut f: F) {
+    introsort_impl(a, &mut |x, y| f(x) < f(y));
+}
+
+fn introsort_impl<T, F: FnMut(&T, &T) -> bool>(a: &mut [T], is_less: &mut F) {
+    let depth_limit = 2 * log2(a.len());
+
+    introsort_rec(a, depth_limit, is_less);
+}
+
+// Recurse into the smaller side and loop on the larger one to keep the stack logarithmic
+fn introsort_rec<T, F: FnMut(&T, &T) -> bool>(
+    mut a: &mut [T],
+    mut depth_limit: u32,
+    is_less: &mut F,
+) {
+    loop {
+        if a.len() <= INSERTION_THRESHOLD {
+            insertion_sort_impl(a, is_less);
+            return;
+        }
+
+        if depth_limit == 0 {
+            heapsort(a, is_less);
+            return;
+        }
+
+        depth_limit -= 1;
+
+        let mid = a.len() / 2;
+        let last = a.len() - 1;
+
+        sort3(a, 0, mid, last, is_less);
+        a.swap(0, mid);
+
+        let (p, _) = partition(a, is_less);
+        let (left, right) = a.split_at_mut(p);
+        let right = &mut right[1..];
+
+        if left.len() < right.len() {
+            introsort_rec(left, depth_limit, is_less);
+            a = right;
+        } else {
+            introsort_rec(right, depth_limit, is_less);
+            a = left;
+        }
+    }
+}
+
+pub(crate) fn log2(n: usize) -> u32 {
+    usize::BITS - n.leading_zeros()
+}
+
+pub(crate) fn heapsort<T, F: FnMut(&T, &T) -> bool>(a: &mut [T], is_less: &mut F) {
+    BorrowingHeap::from_slice_by(a, is_less).sort();
+}
+
+/// Order `a[i] <= a[j] <= a[k]`.
+pub(crate) fn sort3<T, F: FnMut(&T, &T) -> bool>(
+    a: &mut [T],
+    i: usize,
+    j: usize,
+    k: usize,
+    is_less: &mut F,
+) {
+    if is_less(&a[j], &a[i]) {
+        a.swap(i, j);
+    }
+
+    if is_less(&a[k], &a[j]) {
+        a.swap(j, k);
+
+        if is_less(&a[j], &a[i]) {
+            a.swap(i, j);
+        }
+    }
+}
+
+/// Partition around the pivot `a[0]`, moving it to its final position `p` so that everything in
+/// `a[..p]` is less than it and nothing in `a[p + 1..]` is. Also reports whether the slice was
+/// already partitioned, i.e. no elements had to be swapped.
+pub(crate) fn partition<T, F: FnMut(&T, &T) -> bool>(
+    a: &mut [T],
+    is_less: &mut F,
+) -> (usize, bool) {
+    let (pivot, rest) = a.split_first_mut().expect("slice should not be empty");
+    let mut l = 0;
+    let mut r = rest.len();
+    let mut was_partitioned = true;
+
+    loop {
+        while l < r && is_less(&rest[l], pivot) {
+            l += 1;
+        }
+
+        while l < r && !is_less(&rest[r - 1], pivot) {
+            r -= 1;
+        }
+
+        if l >= r {
+            break;
+        }
+
+        r -= 1;
+        rest.swap(l, r);
+        l += 1;
+
+        was_partitioned = false;
+    }
+
+    a.swap(0, l);
+
+    (l, was_partitioned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_sort_records;
+
+    #[test]
+    fn test() {
+        crate::tests::test_sort(introsort);
+    }
+
+    #[test]
+    fn by() {
+        test_sort_records(|a| introsort_by(a, |x, y| x.key.cmp(&y.key)));
+    }
+
+    #[test]
+    fn by_key() {
+        test_sort_records(|a| introsort_by_key(a, |r| r.key));
+    }
+
+    #[test]
+    fn heapsort_fallback() {
+        crate::tests::test_sort(|a| introsort_rec(a, 0, &mut u64::lt));
+        crate::tests::test_sort(|a| introsort_rec(a, 1, &mut u64::lt));
+    }
+
+    #[test]
+    fn partition_reports_order() {
+        let mut a = [5, 1, 9, 2, 3, 4, 7, 8, 6];
+
+        assert_eq!((4, false), partition(&mut a, &mut i32::lt));
+        assert!(a[..4].iter().all(|&x| x < 5));
+        assert_eq!(5, a[4]);
+        assert!(a[5..].iter().all(|&x| x > 5));
+
+        assert_eq!(
+            (4, true),
+            partition(&mut [5, 1, 2, 3, 4, 9, 7, 8, 6], &mut i32::lt)
+        );
+    }
+}
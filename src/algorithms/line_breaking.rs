@@ -0,0 +1,545 @@
+//! Breaking a paragraph into lines so as to minimize the total penalty of its lines, in the spirit
+//! of Knuth and Plass: every possible break is considered at once by dynamic programming over the
+//! break points, rather than filling lines greedily. Words may carry hyphenation points, and the
+//! lines can be rendered left, right or center aligned, or justified.
+
+use std::ops::Range;
+
+const HYPHEN: &str = "-";
+
+/// Width of a piece of text on the output, in columns. Widths are assumed to be additive, so that
+/// the width of a word is the sum of the widths of its fragments.
+pub trait Measure {
+    fn width(&self, s: &str) -> usize;
+}
+
+impl<F: Fn(&str) -> usize> Measure for F {
+    fn width(&self, s: &str) -> usize {
+        self(s)
+    }
+}
+
+/// One column per `char`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Chars;
+
+impl Measure for Chars {
+    fn width(&self, s: &str) -> usize {
+        s.chars().count()
+    }
+}
+
+/// Approximate terminal display width: combining marks and zero-width characters take no
+/// columns, East Asian wide characters and most emoji take two.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DisplayWidth;
+
+impl Measure for DisplayWidth {
+    fn width(&self, s: &str) -> usize {
+        s.chars().map(char_width).sum()
+    }
+}
+
+fn char_width(c: char) -> usize {
+    const ZERO: [(u32, u32); 7] = [
+        (0x0300, 0x036f),
+        (0x1ab0, 0x1aff),
+        (0x1dc0, 0x1dff),
+        (0x200b, 0x200f),
+        (0x20d0, 0x20ff),
+        (0xfe00, 0xfe0f),
+        (0xfe20, 0xfe2f),
+    ];
+    const WIDE: [(u32, u32); 11] = [
+        (0x1100, 0x115f),
+        (0x2e80, 0x303e),
+        (0x3040, 0xa4cf),
+        (0xac00, 0xd7a3),
+        (0xf900, 0xfaff),
+        (0xfe30, 0xfe4f),
+        (0xff00, 0xff60),
+        (0xffe0, 0xffe6),
+        (0x1f300, 0x1f64f),
+        (0x1f900, 0x1f9ff),
+        (0x20000, 0x3fffd),
+    ];
+
+    let c = c as u32;
+    let within = |ranges: &[(u32, u32)]| ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi);
+
+    if within(&ZERO) {
+        0
+    } else if within(&WIDE) {
+        2
+    } else {
+        1
+    }
+}
+
+/// A candidate line, as seen by a `Penalty`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineFit {
+    /// Columns left over on the line.
+    pub slack: usize,
+    /// Number of spaces between words on the line.
+    pub spaces: usize,
+    /// Whether the line ends in the middle of a word, with a hyphen.
+    pub hyphenated: bool,
+    /// Whether this is the last line of the paragraph.
+    pub last: bool,
+}
+
+/// Cost of a line. Only lines that fit are ever priced, except a single fragment that is wider
+/// than the line by itself, which gets a line of its own with no slack.
+pub trait Penalty {
+    fn cost(&self, line: &LineFit) -> u64;
+}
+
+impl<F: Fn(&LineFit) -> u64> Penalty for F {
+    fn cost(&self, line: &LineFit) -> u64 {
+        self(line)
+    }
+}
+
+/// The cube of the slack of every line but the last (CLRS problem 15-4), plus a fixed cost per
+/// hyphenated line.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Cubic {
+    pub hyphen: u64,
+}
+
+impl Penalty for Cubic {
+    fn cost(&self, line: &LineFit) -> u64 {
+        let slack = if line.last {
+            0
+        } else {
+            (line.slack as u64).saturating_pow(3)
+        };
+
+        if line.hyphenated {
+            slack.saturating_add(self.hyphen)
+        } else {
+            slack
+        }
+    }
+}
+
+/// TeX's demerits: `(line + badness)² + hyphen²`, where the badness grows with the cube of how
+/// far each space must stretch, capped at 10000. The last line stretches freely.
+#[derive(Clone, Copy, Debug)]
+pub struct KnuthPlass {
+    pub line: u64,
+    pub hyphen: u64,
+}
+
+impl Default for KnuthPlass {
+    fn default() -> Self {
+        Self {
+            line: 10,
+            hyphen: 50,
+        }
+    }
+}
+
+impl Penalty for KnuthPlass {
+    fn cost(&self, line: &LineFit) -> u64 {
+        let badness = if line.last {
+            0
+        } else {
+            let ratio = line.slack as f64 / line.spaces.max(1) as f64;
+
+            (100.0 * ratio.powi(3)).min(10000.0) as u64
+        };
+        let hyphen = if line.hyphenated { self.hyphen } else { 0 };
+
+        (self.line + badness).pow(2) + hyphen.pow(2)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+    Center,
+    /// Stretch the spaces so that every line but the last fills the width.
+    Justify,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Word<'a> {
+    text: &'a str,
+    hyphens: Vec<usize>,
+}
+
+impl<'a> Word<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            hyphens: vec![],
+        }
+    }
+
+    /// A word that may be broken with a hyphen at any of the given byte offsets.
+    pub fn with_hyphens(text: &'a str, hyphens: &[usize]) -> Self {
+        let mut hyphens = hyphens.to_vec();
+
+        hyphens.sort_unstable();
+        hyphens.dedup();
+
+        assert!(
+            hyphens
+                .iter()
+                .all(|&h| 0 < h && h < text.len() && text.is_char_boundary(h)),
+            "hyphens should be inside the word, on character boundaries"
+        );
+
+        Self { text, hyphens }
+    }
+
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    fn fragments(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+        let starts = std::iter::once(0).chain(self.hyphens.iter().copied());
+        let ends = self.hyphens.iter().copied().chain([self.text.len()]);
+
+        starts.zip(ends).map(|(s, e)| s..e)
+    }
+}
+
+/// Words separated by whitespace, with no hyphenation points.
+pub fn words(text: &str) -> Vec<Word<'_>> {
+    text.split_whitespace().map(Word::new).collect()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Line<'a> {
+    /// The words of the line, where the first and last may be parts of hyphenated words.
+    pub words: Vec<&'a str>,
+    /// Whether the line ends with a hyphen that is not part of `words`.
+    pub hyphenated: bool,
+    /// Width of the words, the single spaces between them and the hyphen.
+    pub width: usize,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Layout<M, P> {
+    pub width: usize,
+    pub measure: M,
+    pub penalty: P,
+    pub align: Align,
+}
+
+impl Layout<Chars, Cubic> {
+    pub fn new(width: usize) -> Self {
+        Self {
+            width,
+            measure: Chars,
+            penalty: Cubic::default(),
+            align: Align::Left,
+        }
+    }
+}
+
+impl<M, P> Layout<M, P> {
+    pub fn with_measure<N: Measure>(self, measure: N) -> Layout<N, P> {
+        Layout {
+            width: self.width,
+            measure,
+            penalty: self.penalty,
+            align: self.align,
+        }
+    }
+
+    pub fn with_penalty<Q: Penalty>(self, penalty: Q) -> Layout<M, Q> {
+        Layout {
+            width: self.width,
+            measure: self.measure,
+            penalty,
+            align: self.align,
+        }
+    }
+}
+
+struct Fragment {
+    word: usize,
+    bytes: Range<usize>,
+    width: usize,
+}
+
+impl<M: Measure, P: Penalty> Layout<M, P> {
+    /// Lines minimizing the total penalty. Among equally good layouts, later lines are preferred
+    /// long.
+    pub fn break_lines<'a>(&self, words: &[Word<'a>]) -> Vec<Line<'a>> {
+        let fragments: Vec<Fragment> = words
+            .iter()
+            .enumerate()
+            .flat_map(|(word, w)| w.fragments().map(move |bytes| (word, bytes)))
+            .map(|(word, bytes)| Fragment {
+                width: self.measure.width(&words[word].text[bytes.clone()]),
+                word,
+                bytes,
+            })
+            .collect();
+
+        let n = fragments.len();
+        let space = self.measure.width(" ");
+        let hyphen = self.measure.width(HYPHEN);
+
+        // best[j]: least penalty of the lines holding the first `j` fragments, and where the
+        // last of those lines starts
+        let mut best: Vec<Option<(u64, usize)>> = vec![None; n + 1];
+
+        best[0] = Some((0, 0));
+
+        for j in 1..=n {
+            let hyphenated = j < n && fragments[j].word == fragments[j - 1].word;
+            let mut natural = if hyphenated { hyphen } else { 0 };
+            let mut spaces = 0;
+
+            for i in (0..j).rev() {
+                natural += fragments[i].width;
+
+                if i + 1 < j && fragments[i].word != fragments[i + 1].word {
+                    natural += space;
+                    spaces += 1;
+                }
+
+                if natural > self.width && i + 1 < j {
+                    break;
+                }
+
+                let Some((before, _)) = best[i] else {
+                    continue;
+                };
+                let fit = LineFit {
+                    slack: self.width.saturating_sub(natural),
+                    spaces,
+                    hyphenated,
+                    last: j == n,
+                };
+                let cost = before.saturating_add(self.penalty.cost(&fit));
+
+                // going backwards, so ties go to the longest line ending here
+                if best[j].is_none_or(|(c, _)| cost <= c) {
+                    best[j] = Some((cost, i));
+                }
+            }
+        }
+
+        let mut bounds = vec![];
+        let mut j = n;
+
+        while j > 0 {
+            let (_, i) = best[j].expect("every prefix should be breakable");
+
+            bounds.push(i..j);
+            j = i;
+        }
+
+        bounds
+            .into_iter()
+            .rev()
+            .map(|range| self.line(words, &fragments, range))
+            .collect()
+    }
+
+    fn line<'a>(
+        &self,
+        words: &[Word<'a>],
+        fragments: &[Fragment],
+        range: Range<usize>,
+    ) -> Line<'a> {
+        let frags = &fragments[range.clone()];
+        let hyphenated =
+            range.end < fragments.len() && fragments[range.end].word == frags[frags.len() - 1].word;
+        let mut pieces: Vec<&'a str> = vec![];
+        let mut width = if hyphenated {
+            self.measure.width(HYPHEN)
+        } else {
+            0
+        };
+
+        for group in frags.chunk_by(|a, b| a.word == b.word) {
+            let text = words[group[0].word].text;
+            let bytes = group[0].bytes.start..group[group.len() - 1].bytes.end;
+
+            pieces.push(&text[bytes]);
+            width += group.iter().map(|f| f.width).sum::<usize>();
+        }
+
+        width += (pieces.len() - 1) * self.measure.width(" ");
+
+        Line {
+            words: pieces,
+            hyphenated,
+            width,
+        }
+    }
+
+    /// A line as text, padded with spaces according to `align`. Trailing padding is left out.
+    pub fn render(&self, line: &Line<'_>, last: bool) -> String {
+        let space = self.measure.width(" ").max(1);
+        let slack = self.width.saturating_sub(line.width) / space;
+        let gaps = line.words.len() - 1;
+
+        let (indent, stretch) = match self.align {
+            Align::Left => (0, 0),
+            Align::Right => (slack, 0),
+            Align::Center => (slack / 2, 0),
+            Align::Justify if last || gaps == 0 => (0, 0),
+            Align::Justify => (0, slack),
+        };
+
+        let mut out = " ".repeat(indent);
+
+        for (g, word) in line.words.iter().enumerate() {
+            if g > 0 {
+                // the leftmost gaps take the remainder
+                let extra = stretch / gaps + usize::from(g - 1 < stretch % gaps);
+
+                out.push_str(&" ".repeat(1 + extra));
+            }
+
+            out.push_str(word);
+        }
+
+        if line.hyphenated {
+            out.push_str(HYPHEN);
+        }
+
+        out
+    }
+
+    /// The paragraph broken into lines and rendered.
+    pub fn format(&self, words: &[Word<'_>]) -> Vec<String> {
+        let lines = self.break_lines(words);
+        let last = lines.len().saturating_sub(1);
+
+        lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| self.render(line, i == last))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rand::Wyhash64RNG;
+
+    #[test]
+    fn alignments() {
+        let text = words("the quick brown fox jumps over the lazy dog");
+        let mut layout = Layout::new(16);
+
+        assert_eq!(
+            vec!["the quick brown", "fox jumps over", "the lazy dog"],
+            layout.format(&text)
+        );
+
+        layout.align = Align::Justify;
+        assert_eq!(
+            vec!["the  quick brown", "fox  jumps  over", "the lazy dog"],
+            layout.format(&text)
+        );
+
+        layout.align = Align::Right;
+        assert_eq!(
+            vec![" the quick brown", "  fox jumps over", "    the lazy dog"],
+            layout.format(&text)
+        );
+
+        layout.align = Align::Center;
+        assert_eq!(
+            vec!["the quick brown", " fox jumps over", "  the lazy dog"],
+            layout.format(&text)
+        );
+
+        assert!(layout.format(&[]).is_empty());
+    }
+
+    #[test]
+    fn hyphenation() {
+        let text = [
+            Word::new("a"),
+            Word::with_hyphens("hyphenation", &[2, 6]),
+            Word::new("is"),
+        ];
+        let layout = Layout::new(9).with_penalty(Cubic { hyphen: 1 });
+        let lines = layout.break_lines(&text);
+
+        assert_eq!(vec!["a hyphen-", "ation is"], layout.format(&text));
+        assert_eq!(vec!["a", "hyphen"], lines[0].words);
+        assert_eq!((true, 9), (lines[0].hyphenated, lines[0].width));
+
+        // without hyphens the long word can't share a line
+        let plain = words("a hyphenation is");
+
+        assert_eq!(vec!["a", "hyphenation", "is"], layout.format(&plain));
+    }
+
+    #[test]
+    fn display_width() {
+        let text = words("日本語 の 文章 ab");
+        let layout = Layout::new(9).with_measure(DisplayWidth);
+
+        assert_eq!(3, DisplayWidth.width("e\u{301}日"));
+        assert_eq!(vec!["日本語 の", "文章 ab"], layout.format(&text));
+        assert_eq!(vec!["日本語 の 文章", "ab"], Layout::new(9).format(&text));
+    }
+
+    #[test]
+    fn random_paragraphs() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+        let vocabulary = [
+            "a",
+            "an",
+            "the",
+            "word",
+            "longer",
+            "paragraph",
+            "extraordinary",
+        ];
+
+        for width in [8, 13, 20, 40] {
+            let chosen: Vec<&str> = (0..60)
+                .map(|_| vocabulary[rng.gen_in_range(0..vocabulary.len() as u64) as usize])
+                .collect();
+            let text: Vec<Word> = chosen.iter().map(|w| Word::new(w)).collect();
+
+            for align in [Align::Left, Align::Justify] {
+                let layout = Layout {
+                    align,
+                    ..Layout::new(width).with_penalty(KnuthPlass::default())
+                };
+                let lines = layout.format(&text);
+                let rejoined: Vec<&str> = lines.iter().flat_map(|l| l.split_whitespace()).collect();
+
+                assert_eq!(chosen, rejoined);
+
+                for (i, line) in lines.iter().enumerate() {
+                    let words = line.split_whitespace().count();
+
+                    assert!(line.len() <= width || words == 1);
+
+                    if align == Align::Justify && i + 1 < lines.len() && words > 1 {
+                        assert_eq!(width, line.len());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn custom_penalty() {
+        // one word per line
+        let text = words("aaa bb cc dddd");
+        let layout = Layout::new(7).with_penalty(|fit: &LineFit| fit.spaces as u64);
+
+        assert_eq!(vec!["aaa", "bb", "cc", "dddd"], layout.format(&text));
+        assert_eq!(vec!["aaa bb", "cc dddd"], Layout::new(7).format(&text));
+    }
+}
@@ -0,0 +1,302 @@
+//! Matrix operations (CLRS chapter 28): LUP decomposition with partial pivoting, and solving
+//! linear systems, inversion, determinants and least squares on top of it.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::data_structures::array_2d::Array2D;
+
+// Pivots this small relative to the largest entry of the matrix are treated as zero
+const SINGULAR_TOLERANCE: f64 = 1e-12;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SingularMatrix;
+
+impl fmt::Display for SingularMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "matrix is singular")
+    }
+}
+
+impl Error for SingularMatrix {}
+
+/// `PA = LU`, with `L` unit lower triangular and `U` upper triangular stored together in `lu`,
+/// and `P` stored as `perm`: row `i` of `PA` is row `perm[i]` of `A`.
+#[derive(Clone, Debug)]
+pub struct Lup {
+    lu: Array2D<f64>,
+    perm: Vec<usize>,
+    swaps: usize,
+}
+
+fn assert_square(a: &Array2D<f64>) -> usize {
+    assert_eq!(a.width(), a.height(), "matrix should be square");
+
+    a.width()
+}
+
+pub fn lup_decomposition(a: &Array2D<f64>) -> Result<Lup, SingularMatrix> {
+    let n = assert_square(a);
+    let mut lu = a.clone();
+    let mut perm: Vec<usize> = (0..n).collect();
+    let mut swaps = 0;
+
+    let largest = a.as_ref().iter().fold(0.0, |m: f64, x| m.max(x.abs()));
+    let tolerance = largest * SINGULAR_TOLERANCE;
+
+    for k in 0..n {
+        let (p, pivot) = (k..n)
+            .map(|i| (i, lu[i][k].abs()))
+            .fold((k, -1.0), |best, c| if c.1 > best.1 { c } else { best });
+
+        if pivot <= tolerance {
+            return Err(SingularMatrix);
+        }
+
+        if p != k {
+            perm.swap(k, p);
+            swap_rows(&mut lu, k, p);
+            swaps += 1;
+        }
+
+        for i in k + 1..n {
+            lu[i][k] /= lu[k][k];
+
+            let factor = lu[i][k];
+
+            for j in k + 1..n {
+                lu[i][j] -= factor * lu[k][j];
+            }
+        }
+    }
+
+    Ok(Lup { lu, perm, swaps })
+}
+
+fn swap_rows(a: &mut Array2D<f64>, i: usize, j: usize) {
+    let w = a.width();
+    let (lo, hi) = (i.min(j), i.max(j));
+    let (top, bottom) = a.as_mut().split_at_mut(hi * w);
+
+    top[lo * w..(lo + 1) * w].swap_with_slice(&mut bottom[..w]);
+}
+
+impl Lup {
+    pub fn lu(&self) -> &Array2D<f64> {
+        &self.lu
+    }
+
+    pub fn permutation(&self) -> &[usize] {
+        &self.perm
+    }
+
+    /// Solve `Ax = b` by forward substitution with `L` and back substitution with `U`.
+    pub fn solve(&self, b: &[f64]) -> Vec<f64> {
+        let n = self.perm.len();
+
+        assert_eq!(n, b.len(), "right-hand side should have one entry per row");
+
+        let mut y = vec![0.0; n];
+
+        for i in 0..n {
+            let sum: f64 = (0..i).map(|j| self.lu[i][j] * y[j]).sum();
+            y[i] = b[self.perm[i]] - sum;
+        }
+
+        let mut x = vec![0.0; n];
+
+        for i in (0..n).rev() {
+            let sum: f64 = (i + 1..n).map(|j| self.lu[i][j] * x[j]).sum();
+            x[i] = (y[i] - sum) / self.lu[i][i];
+        }
+
+        x
+    }
+
+    pub fn determinant(&self) -> f64 {
+        let n = self.perm.len();
+        let diagonal: f64 = (0..n).map(|i| self.lu[i][i]).product();
+
+        if self.swaps % 2 == 0 {
+            diagonal
+        } else {
+            -diagonal
+        }
+    }
+
+    /// Solve for each column of the identity matrix in turn.
+    pub fn inverse(&self) -> Array2D<f64> {
+        let n = self.perm.len();
+        let mut inv = Array2D::new(0.0, n, n);
+        let mut e = vec![0.0; n];
+
+        for j in 0..n {
+            e[j] = 1.0;
+
+            for (i, x) in self.solve(&e).into_iter().enumerate() {
+                inv[i][j] = x;
+            }
+
+            e[j] = 0.0;
+        }
+
+        inv
+    }
+}
+
+pub fn solve(a: &Array2D<f64>, b: &[f64]) -> Result<Vec<f64>, SingularMatrix> {
+    Ok(lup_decomposition(a)?.solve(b))
+}
+
+pub fn inverse(a: &Array2D<f64>) -> Result<Array2D<f64>, SingularMatrix> {
+    Ok(lup_decomposition(a)?.inverse())
+}
+
+/// Zero for singular matrices rather than an error.
+pub fn determinant(a: &Array2D<f64>) -> f64 {
+    lup_decomposition(a).map_or(0.0, |lup| lup.determinant())
+}
+
+/// The `x` minimizing `|Ax - b|`, for an `A` with at least as many rows as columns, by solving the
+/// normal equations `AᵀAx = Aᵀb`. Fails when the columns of `A` are linearly dependent.
+pub fn least_squares(a: &Array2D<f64>, b: &[f64]) -> Result<Vec<f64>, SingularMatrix> {
+    let (m, n) = (a.height(), a.width());
+
+    assert_eq!(m, b.len(), "right-hand side should have one entry per row");
+
+    let mut ata = Array2D::new(0.0, n, n);
+    let mut atb = vec![0.0; n];
+
+    for i in 0..n {
+        for j in 0..n {
+            ata[i][j] = (0..m).map(|k| a[k][i] * a[k][j]).sum();
+        }
+
+        atb[i] = (0..m).map(|k| a[k][i] * b[k]).sum();
+    }
+
+    solve(&ata, &atb)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rand::Wyhash64RNG;
+
+    const EPS: f64 = 1e-9;
+
+    fn assert_close(expected: &[f64], actual: &[f64]) {
+        assert_eq!(expected.len(), actual.len());
+
+        for (e, a) in expected.iter().zip(actual) {
+            assert!((e - a).abs() < EPS, "expected {expected:?}, got {actual:?}");
+        }
+    }
+
+    fn multiply(a: &Array2D<f64>, b: &Array2D<f64>) -> Array2D<f64> {
+        let mut c = Array2D::new(0.0, b.width(), a.height());
+
+        for i in 0..a.height() {
+            for j in 0..b.width() {
+                c[i][j] = (0..a.width()).map(|k| a[i][k] * b[k][j]).sum();
+            }
+        }
+
+        c
+    }
+
+    #[test]
+    fn clrs_example() {
+        // CLRS figure 28.2
+        let rows = [
+            [2.0, 0.0, 2.0, 0.6],
+            [3.0, 3.0, 4.0, -2.0],
+            [5.0, 5.0, 4.0, 2.0],
+            [-1.0, -2.0, 3.4, -1.0],
+        ];
+        let a = Array2D::from_slice(4, 4, &rows.concat());
+        let lup = lup_decomposition(&a).unwrap();
+        let lu = [
+            [5.0, 5.0, 4.0, 2.0],
+            [0.4, -2.0, 0.4, -0.2],
+            [-0.2, 0.5, 4.0, -0.5],
+            [0.6, 0.0, 0.4, -3.0],
+        ];
+
+        assert_eq!(&[2, 0, 3, 1], lup.permutation());
+        assert_close(&lu.concat(), lup.lu().as_ref());
+        assert!((determinant(&a) - -120.0).abs() < EPS);
+    }
+
+    #[test]
+    fn solve_simple() {
+        // CLRS section 28.1
+        let a = Array2D::from_slice(3, 3, &[1.0, 2.0, 0.0, 3.0, 4.0, 4.0, 5.0, 6.0, 3.0]);
+
+        assert_close(&[-1.4, 2.2, 0.6], &solve(&a, &[3.0, 7.0, 8.0]).unwrap());
+    }
+
+    #[test]
+    fn singular() {
+        let a = Array2D::from_slice(3, 3, &[1.0, 2.0, 3.0, 2.0, 4.0, 6.0, 0.0, 1.0, 1.0]);
+
+        assert_eq!(Err(SingularMatrix), solve(&a, &[1.0, 2.0, 3.0]));
+        assert!(inverse(&a).is_err());
+        assert_eq!(0.0, determinant(&a));
+        assert_eq!(
+            Err(SingularMatrix),
+            inverse(&Array2D::new(0.0, 2, 2)).map(|_| ())
+        );
+        assert_eq!("matrix is singular", SingularMatrix.to_string());
+    }
+
+    #[test]
+    fn random_inverse() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+
+        for n in [1, 2, 5, 20] {
+            let data: Vec<f64> = (0..n * n)
+                .map(|_| rng.gen_in_range_i64(-100..100) as f64 / 10.0)
+                .collect();
+            let a = Array2D::from_slice(n, n, &data);
+            let inv = inverse(&a).unwrap();
+            let product = multiply(&a, &inv);
+
+            for i in 0..n {
+                let mut e = vec![0.0; n];
+                e[i] = 1.0;
+
+                assert_close(&e, &product[i]);
+            }
+
+            let det = determinant(&a) * determinant(&inv);
+            assert!((det - 1.0).abs() < EPS);
+        }
+    }
+
+    #[test]
+    fn least_squares_line() {
+        // CLRS section 28.3: fitting F(x) = c1 + c2 x + c3 x^2 to five points
+        let xs = [-1.0, 1.0, 2.0, 3.0, 5.0];
+        let ys = [2.0, 1.0, 1.0, 0.0, 3.0];
+        let data: Vec<f64> = xs.iter().flat_map(|&x| [1.0, x, x * x]).collect();
+        let a = Array2D::from_slice(3, 5, &data);
+        let c = least_squares(&a, &ys).unwrap();
+
+        assert_close(
+            &[1.2, -0.757, 0.214],
+            &c.iter()
+                .map(|x| (x * 1000.0).round() / 1000.0)
+                .collect::<Vec<_>>(),
+        );
+
+        // an exact fit is recovered exactly
+        let exact: Vec<f64> = xs.iter().map(|x| 1.0 + 2.0 * x).collect();
+        let line: Vec<f64> = xs.iter().flat_map(|&x| [1.0, x]).collect();
+
+        assert_close(
+            &[1.0, 2.0],
+            &least_squares(&Array2D::from_slice(2, 5, &line), &exact).unwrap(),
+        );
+    }
+}
@@ -1,24 +1,56 @@
+use std::cmp::Ordering;
+
 pub fn merge_sort<T: Copy + PartialOrd>(a: &mut [T]) {
+    merge_sort_impl(a, &mut T::le);
+}
+
+/// Stable. Elements are never copied: the sort runs on their indices, and the resulting
+/// permutation is then applied to the slice with swaps.
+pub fn merge_sort_by<T, F: FnMut(&T, &T) -> Ordering>(a: &mut [T], mut cmp: F) {
+    let mut idx: Vec<usize> = (0..a.len()).collect();
+
+    merge_sort_impl(&mut idx, &mut |&i, &j| {
+        cmp(&a[i], &a[j]) != Ordering::Greater
+    });
+    apply_permutation(a, &mut idx);
+}
+
+pub fn merge_sort_by_key<T, K: Ord, F: FnMut(&T) -> K>(a: &mut [T], mut f: F) {
+    merge_sort_by(a, |x, y| f(x).cmp(&f(y)));
+}
+
+fn merge_sort_impl<T: Copy, F: FnMut(&T, &T) -> bool>(a: &mut [T], is_less_or_equal: &mut F) {
     if a.len() <= 1 {
         return;
     }
 
-    merge_rec(a, 0, a.len() - 1);
+    merge_rec(a, 0, a.len() - 1, is_less_or_equal);
 }
 
-fn merge_rec<T: Copy + PartialOrd>(a: &mut [T], l: usize, h: usize) {
+fn merge_rec<T: Copy, F: FnMut(&T, &T) -> bool>(
+    a: &mut [T],
+    l: usize,
+    h: usize,
+    is_less_or_equal: &mut F,
+) {
     let m = l + (h - l) / 2;
 
     if l == h {
         return;
     }
 
-    merge_rec(a, l, m);
-    merge_rec(a, m + 1, h);
-    merge(a, l, m, h);
+    merge_rec(a, l, m, is_less_or_equal);
+    merge_rec(a, m + 1, h, is_less_or_equal);
+    merge(a, l, m, h, is_less_or_equal);
 }
 
-fn merge<T: Copy + PartialOrd>(a: &mut [T], l: usize, m: usize, h: usize) {
+fn merge<T: Copy, F: FnMut(&T, &T) -> bool>(
+    a: &mut [T],
+    l: usize,
+    m: usize,
+    h: usize,
+    is_less_or_equal: &mut F,
+) {
     let left = &a[l..=m].to_vec();
     let right = &a[m + 1..=h].to_vec();
 
@@ -27,7 +59,7 @@ fn merge<T: Copy + PartialOrd>(a: &mut [T], l: usize, m: usize, h: usize) {
     let mut k = l;
 
     while i < left.len() && j < right.len() {
-        if left[i] <= right[j] {
+        if is_less_or_equal(&left[i], &right[j]) {
             a[k] = left[i];
             i += 1;
         } else {
@@ -50,7 +82,48 @@ fn merge<T: Copy + PartialOrd>(a: &mut [T], l: usize, m: usize, h: usize) {
     }
 }
 
+// Rearrange `a` so that `a[i]` becomes the old `a[perm[i]]`, following each cycle of the
+// permutation. `perm` is left as the identity.
+pub(crate) fn apply_permutation<T>(a: &mut [T], perm: &mut [usize]) {
+    for i in 0..a.len() {
+        let mut j = i;
+
+        loop {
+            let k = perm[j];
+            perm[j] = j;
+
+            if k == i {
+                break;
+            }
+
+            a.swap(j, k);
+            j = k;
+        }
+    }
+}
+
 #[test]
 fn test() {
     crate::tests::test_sort(merge_sort);
 }
+
+#[test]
+fn by() {
+    crate::tests::test_stable_sort_records(|a| merge_sort_by(a, |x, y| x.key.cmp(&y.key)));
+}
+
+#[test]
+fn by_key() {
+    crate::tests::test_stable_sort_records(|a| merge_sort_by_key(a, |r| r.key));
+}
+
+#[test]
+fn permutation() {
+    let mut a = ['a', 'b', 'c', 'd', 'e'];
+    let mut perm = [3, 0, 4, 1, 2];
+
+    apply_permutation(&mut a, &mut perm);
+
+    assert_eq!(['d', 'a', 'e', 'b', 'c'], a);
+    assert_eq!([0, 1, 2, 3, 4], perm);
+}
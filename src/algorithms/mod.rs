@@ -2,14 +2,26 @@ pub mod bubble_sort;
 pub mod count_inversions;
 pub mod counting_sort;
 pub mod cycle_sort;
+pub mod dp_table;
 pub mod dynamic_programming;
+pub mod edit_distance;
+pub mod external_sort;
 pub mod greedy;
 pub mod heap_sort;
 pub mod insertion_sort;
+pub mod introsort;
+pub mod line_breaking;
+pub mod linear_algebra;
 pub mod linear_programming;
 pub mod max_subarray_sum;
 pub mod merge_sort;
+pub mod natural_merge_sort;
+pub mod parallel_sort;
+pub mod pdqsort;
 pub mod quicksort;
 pub mod radix_sort;
 pub mod select;
 pub mod selection_sort;
+pub mod sequence_alignment;
+pub mod sorting_network;
+pub mod string_matching;
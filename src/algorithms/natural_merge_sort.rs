@@ -0,0 +1,955 @@
+//! Natural merge sort in the style of Timsort: the input is split into the runs it already
+//! contains, short runs are extended with binary insertion sort, and runs are merged following
+//! Timsort's stack invariants so that merges stay balanced
+
+use std::cmp::Ordering;
+
+use crate::algorithms::insertion_sort::insertion_sort_impl;
+use crate::algorithms::merge_sort::apply_permutation;
+
+// Enter galloping mode once one side has won this many times in a row
+const MIN_GALLOP: usize = 7;
+
+// In-place merges shorter than this are done with rotations, cheaper there than taking out keys
+const MIN_BLOCK_MERGE: usize = 64;
+
+/// Stable. Every merge copies its shorter run into the same scratch buffer, which holds at most
+/// half the slice.
+pub fn natural_merge_sort<T: Copy + PartialOrd>(a: &mut [T]) {
+    buffered_sort(a, &mut T::lt);
+}
+
+/// Stable. Sorts a vector of the element indices as [`natural_merge_sort`] would, then moves the
+/// elements into place with swaps, so on top of the half-length scratch buffer of indices it
+/// allocates one index per element.
+pub fn natural_merge_sort_by<T, F: FnMut(&T, &T) -> Ordering>(a: &mut [T], mut cmp: F) {
+    let mut idx: Vec<usize> = (0..a.len()).collect();
+
+    buffered_sort(&mut idx, &mut |&i, &j| cmp(&a[i], &a[j]) == Ordering::Less);
+    apply_permutation(a, &mut idx);
+}
+
+pub fn natural_merge_sort_by_key<T, K: Ord, F: FnMut(&T) -> K>(a: &mut [T], mut f: F) {
+    natural_merge_sort_by(a, |x, y| f(x).cmp(&f(y)));
+}
+
+/// Stable and without any extra memory: runs are merged in place by a block merge, in O(n) moves
+/// per merge. Merges whose first run has fewer than about 2√n distinct elements are done with
+/// rotations instead, at the cost of O(n log n) moves.
+pub fn natural_merge_sort_in_place<T: PartialOrd>(a: &mut [T]) {
+    sort_runs(a, &mut T::lt, |a, mid, is_less| {
+        block_merge(a, mid, is_less)
+    });
+}
+
+pub fn natural_merge_sort_in_place_by<T, F: FnMut(&T, &T) -> Ordering>(a: &mut [T], mut cmp: F) {
+    let mut is_less = |x: &T, y: &T| cmp(x, y) == Ordering::Less;
+
+    sort_runs(a, &mut is_less, |a, mid, is_less| {
+        block_merge(a, mid, is_less)
+    });
+}
+
+pub fn natural_merge_sort_in_place_by_key<T, K: Ord, F: FnMut(&T) -> K>(a: &mut [T], mut f: F) {
+    let mut is_less = |x: &T, y: &T| f(x) < f(y);
+
+    sort_runs(a, &mut is_less, |a, mid, is_less| {
+        block_merge(a, mid, is_less)
+    });
+}
+
+fn buffered_sort<T: Copy, F: FnMut(&T, &T) -> bool>(a: &mut [T], is_less: &mut F) {
+    let mut buf = Vec::with_capacity(a.len() / 2);
+    let mut min_gallop = MIN_GALLOP;
+
+    sort_runs(a, is_less, |a, mid, is_less| {
+        merge_buffered(a, mid, &mut buf, &mut min_gallop, is_less);
+    });
+}
+
+#[derive(Clone, Copy)]
+struct Run {
+    start: usize,
+    len: usize,
+}
+
+// Find the runs left to right, pushing them on a stack and merging the topmost ones as long as
+// their lengths do not decrease at least as fast as the Fibonacci numbers
+fn sort_runs<T, F, M>(a: &mut [T], is_less: &mut F, mut merge: M)
+where
+    F: FnMut(&T, &T) -> bool,
+    M: FnMut(&mut [T], usize, &mut F),
+{
+    let n = a.len();
+    let min_run = min_run_length(n);
+    let mut runs: Vec<Run> = Vec::new();
+    let mut start = 0;
+
+    while start < n {
+        let rest = &mut a[start..];
+        let mut len = count_run(rest, is_less);
+
+        if len < min_run {
+            let forced = min_run.min(rest.len());
+
+            binary_insertion_sort(&mut rest[..forced], len, is_less);
+
+            len = forced;
+        }
+
+        runs.push(Run { start, len });
+        start += len;
+
+        while let Some(i) = collapse_at(&runs) {
+            merge_at(a, &mut runs, i, is_less, &mut merge);
+        }
+    }
+
+    while runs.len() > 1 {
+        let i = runs.len() - 2;
+
+        merge_at(a, &mut runs, i, is_less, &mut merge);
+    }
+}
+
+// Index of the lower of the two runs to merge next, if any invariant is violated. Also checks the
+// fourth run from the top, which the original Timsort did not.
+fn collapse_at(runs: &[Run]) -> Option<usize> {
+    let n = runs.len();
+    let len = |i: usize| runs[i].len;
+
+    if n >= 3 && len(n - 3) <= len(n - 2) + len(n - 1)
+        || n >= 4 && len(n - 4) <= len(n - 3) + len(n - 2)
+    {
+        if len(n - 3) < len(n - 1) {
+            Some(n - 3)
+        } else {
+            Some(n - 2)
+        }
+    } else if n >= 2 && len(n - 2) <= len(n - 1) {
+        Some(n - 2)
+    } else {
+        None
+    }
+}
+
+fn merge_at<T, F, M>(a: &mut [T], runs: &mut Vec<Run>, i: usize, is_less: &mut F, merge: &mut M)
+where
+    F: FnMut(&T, &T) -> bool,
+    M: FnMut(&mut [T], usize, &mut F),
+{
+    let left = runs[i];
+    let right = runs.remove(i + 1);
+
+    merge(
+        &mut a[left.start..right.start + right.len],
+        left.len,
+        is_less,
+    );
+
+    runs[i].len += right.len;
+}
+
+// Between 32 and 64, chosen so that n / min_run is a power of two or slightly less than one
+fn min_run_length(mut n: usize) -> usize {
+    let mut r = 0;
+
+    while n >= 64 {
+        r |= n & 1;
+        n >>= 1;
+    }
+
+    n + r
+}
+
+/// Length of the run at the start of `a`. A strictly descending run is reversed, which keeps the
+/// sort stable since it cannot contain equal elements.
+fn count_run<T, F: FnMut(&T, &T) -> bool>(a: &mut [T], is_less: &mut F) -> usize {
+    if a.len() < 2 {
+        return a.len();
+    }
+
+    let mut end = 2;
+
+    if is_less(&a[1], &a[0]) {
+        while end < a.len() && is_less(&a[end], &a[end - 1]) {
+            end += 1;
+        }
+
+        a[..end].reverse();
+    } else {
+        while end < a.len() && !is_less(&a[end], &a[end - 1]) {
+            end += 1;
+        }
+    }
+
+    end
+}
+
+/// Sort `a` given that `a[..sorted]` already is, inserting every other element after the equal
+/// ones found by binary search.
+fn binary_insertion_sort<T, F: FnMut(&T, &T) -> bool>(a: &mut [T], sorted: usize, is_less: &mut F) {
+    for i in sorted.max(1)..a.len() {
+        let pos = a[..i].partition_point(|x| !is_less(&a[i], x));
+
+        a[pos..=i].rotate_right(1);
+    }
+}
+
+/// Number of leading elements of `s` satisfying `pred`, which must hold for a prefix of `s`.
+/// Probes exponentially growing distances before binary searching, so it is fast when the answer
+/// is small.
+fn gallop<T>(s: &[T], mut pred: impl FnMut(&T) -> bool) -> usize {
+    let mut lo = 0;
+    let mut step = 1;
+
+    while lo + step <= s.len() && pred(&s[lo + step - 1]) {
+        lo += step;
+        step *= 2;
+    }
+
+    let hi = (lo + step).min(s.len());
+
+    lo + s[lo..hi].partition_point(pred)
+}
+
+/// Number of trailing elements of `s` satisfying `pred`, which must hold for a suffix of `s`.
+fn gallop_back<T>(s: &[T], mut pred: impl FnMut(&T) -> bool) -> usize {
+    let n = s.len();
+    let mut count = 0;
+    let mut step = 1;
+
+    while count + step <= n && pred(&s[n - count - step]) {
+        count += step;
+        step *= 2;
+    }
+
+    let lo = n - (count + step).min(n);
+    let p = s[lo..n - count].partition_point(|x| !pred(x));
+
+    n - lo - p
+}
+
+/// Merge the sorted `a[..mid]` and `a[mid..]`, copying the shorter one into `buf`.
+fn merge_buffered<T: Copy, F: FnMut(&T, &T) -> bool>(
+    a: &mut [T],
+    mid: usize,
+    buf: &mut Vec<T>,
+    min_gallop: &mut usize,
+    is_less: &mut F,
+) {
+    // Elements of the left run not greater than the first one of the right run, and elements of
+    // the right run not less than the last one of the left run, are already in place
+    let lo = gallop(&a[..mid], |x| !is_less(&a[mid], x));
+    let hi = a.len() - gallop_back(&a[mid..], |x| !is_less(x, &a[mid - 1]));
+
+    if lo == mid || hi == mid {
+        return;
+    }
+
+    let a = &mut a[lo..hi];
+    let mid = mid - lo;
+
+    buf.clear();
+
+    if mid <= a.len() - mid {
+        buf.extend_from_slice(&a[..mid]);
+        merge_lo(a, mid, buf, min_gallop, is_less);
+    } else {
+        buf.extend_from_slice(&a[mid..]);
+        merge_hi(a, mid, buf, min_gallop, is_less);
+    }
+}
+
+// Merge front to back, the left run being in `buf`
+fn merge_lo<T: Copy, F: FnMut(&T, &T) -> bool>(
+    a: &mut [T],
+    mid: usize,
+    buf: &[T],
+    min_gallop: &mut usize,
+    is_less: &mut F,
+) {
+    let hi = a.len();
+    let mut i = 0;
+    let mut j = mid;
+    let mut k = 0;
+    let mut left_wins = 0;
+    let mut right_wins = 0;
+
+    while i < buf.len() && j < hi {
+        if is_less(&a[j], &buf[i]) {
+            a[k] = a[j];
+            j += 1;
+            right_wins += 1;
+            left_wins = 0;
+        } else {
+            a[k] = buf[i];
+            i += 1;
+            left_wins += 1;
+            right_wins = 0;
+        }
+
+        k += 1;
+
+        if left_wins < *min_gallop && right_wins < *min_gallop {
+            continue;
+        }
+
+        while i < buf.len() && j < hi {
+            let n = gallop(&buf[i..], |x| !is_less(&a[j], x));
+
+            a[k..k + n].copy_from_slice(&buf[i..i + n]);
+            i += n;
+            k += n;
+
+            if i == buf.len() {
+                break;
+            }
+
+            let m = gallop(&a[j..], |x| is_less(x, &buf[i]));
+
+            a.copy_within(j..j + m, k);
+            j += m;
+            k += m;
+
+            if n < MIN_GALLOP && m < MIN_GALLOP {
+                *min_gallop += 1;
+                break;
+            }
+
+            *min_gallop = min_gallop.saturating_sub(1).max(1);
+        }
+
+        left_wins = 0;
+        right_wins = 0;
+    }
+
+    a[k..k + buf.len() - i].copy_from_slice(&buf[i..]);
+}
+
+// Merge back to front, the right run being in `buf`
+fn merge_hi<T: Copy, F: FnMut(&T, &T) -> bool>(
+    a: &mut [T],
+    mid: usize,
+    buf: &[T],
+    min_gallop: &mut usize,
+    is_less: &mut F,
+) {
+    let mut i = mid;
+    let mut j = buf.len();
+    let mut k = a.len();
+    let mut left_wins = 0;
+    let mut right_wins = 0;
+
+    while i > 0 && j > 0 {
+        if is_less(&buf[j - 1], &a[i - 1]) {
+            a[k - 1] = a[i - 1];
+            i -= 1;
+            left_wins += 1;
+            right_wins = 0;
+        } else {
+            a[k - 1] = buf[j - 1];
+            j -= 1;
+            right_wins += 1;
+            left_wins = 0;
+        }
+
+        k -= 1;
+
+        if left_wins < *min_gallop && right_wins < *min_gallop {
+            continue;
+        }
+
+        while i > 0 && j > 0 {
+            let n = gallop_back(&a[..i], |x| is_less(&buf[j - 1], x));
+
+            a.copy_within(i - n..i, k - n);
+            i -= n;
+            k -= n;
+
+            if i == 0 {
+                break;
+            }
+
+            let m = gallop_back(&buf[..j], |x| !is_less(x, &a[i - 1]));
+
+            a[k - m..k].copy_from_slice(&buf[j - m..j]);
+            j -= m;
+            k -= m;
+
+            if n < MIN_GALLOP && m < MIN_GALLOP {
+                *min_gallop += 1;
+                break;
+            }
+
+            *min_gallop = min_gallop.saturating_sub(1).max(1);
+        }
+
+        left_wins = 0;
+        right_wins = 0;
+    }
+
+    a[..j].copy_from_slice(&buf[..j]);
+}
+
+/// Block merge of the sorted `a[..mid]` and `a[mid..]` in O(n) moves without a buffer, in the
+/// manner of GrailSort. Keys taken from the distinct elements of `a[..mid]` serve as tags for the
+/// blocks and as an internal buffer; when there are too few of them, or the merge is short, it
+/// falls back to `rotation_merge`.
+fn block_merge<T, F: FnMut(&T, &T) -> bool>(a: &mut [T], mid: usize, is_less: &mut F) {
+    if mid == 0 || mid == a.len() || !is_less(&a[mid], &a[mid - 1]) {
+        return;
+    }
+
+    // elements already in their final position at either end are left out
+    let start = gallop(&a[..mid], |x| !is_less(&a[mid], x));
+    let end = mid + gallop(&a[mid..], |x| is_less(x, &a[mid - 1]));
+    let a = &mut a[start..end];
+    let mid = mid - start;
+
+    if !try_block_merge(a, mid, is_less) {
+        rotation_merge(a, mid, is_less);
+    }
+}
+
+/// The block merge proper. Returns `false`, leaving `a` untouched, if the merge is too short or
+/// `a[..mid]` has too few distinct elements.
+///
+/// With blocks of about √n elements, about √n keys tag the blocks and as many form the buffer.
+/// The keys are the first occurrences of their values in `a[..mid]`, so once sorted again they
+/// can be merged back in front of any elements equal to them, which keeps the merge stable.
+fn try_block_merge<T, F: FnMut(&T, &T) -> bool>(a: &mut [T], mid: usize, is_less: &mut F) -> bool {
+    let n = a.len();
+
+    if n < MIN_BLOCK_MERGE {
+        return false;
+    }
+
+    let block = n.isqrt();
+    let num_tags = n / block;
+    let num_keys = num_tags + block;
+
+    if count_distinct(&a[..mid], num_keys, is_less) < num_keys {
+        return false;
+    }
+
+    collect_keys(&mut a[..mid], num_keys, is_less);
+
+    let (tags, rest) = a.split_at_mut(num_tags);
+
+    merge_blocks(rest, mid - num_tags, block, tags, is_less);
+
+    // the buffer ends up behind the merged elements, scrambled like the tags
+    rest.rotate_right(block);
+    insertion_sort_impl(&mut a[..num_keys], is_less);
+    merge_keys(a, num_keys, is_less);
+
+    true
+}
+
+/// Number of distinct elements of the sorted `a`, counting no further than `limit`.
+fn count_distinct<T, F: FnMut(&T, &T) -> bool>(a: &[T], limit: usize, is_less: &mut F) -> usize {
+    let mut count = usize::from(!a.is_empty());
+
+    for i in 1..a.len() {
+        if count >= limit {
+            break;
+        }
+
+        if is_less(&a[i - 1], &a[i]) {
+            count += 1;
+        }
+    }
+
+    count
+}
+
+/// Move the first occurrences of the first `k` distinct values of the sorted `a` to its front,
+/// keeping the other elements in order behind them. The keys travel right as a block, so this
+/// takes O(n + k²) moves.
+fn collect_keys<T, F: FnMut(&T, &T) -> bool>(a: &mut [T], k: usize, is_less: &mut F) {
+    let mut start = 0;
+    let mut len = 1;
+
+    for i in 1..a.len() {
+        if len == k {
+            break;
+        }
+
+        if is_less(&a[start + len - 1], &a[i]) {
+            a[start..i].rotate_left(len);
+            start = i - len;
+            len += 1;
+        }
+    }
+
+    a[..start + len].rotate_right(len);
+}
+
+/// Merge the sorted keys `a[..k]`, all distinct, into the sorted `a[k..]`, putting every key in
+/// front of the elements equal to it. Each key is rotated past the elements smaller than it, in
+/// O(n + k²) moves overall.
+fn merge_keys<T, F: FnMut(&T, &T) -> bool>(a: &mut [T], mut k: usize, is_less: &mut F) {
+    let mut start = 0;
+
+    while k > 0 && start + k < a.len() {
+        let smaller = gallop(&a[start + k..], |x| is_less(x, &a[start]));
+
+        a[start..start + k + smaller].rotate_left(k);
+        start += smaller + 1;
+        k -= 1;
+    }
+}
+
+/// Merge `a[block..mid]` and `a[mid..]` into `a[..a.len() - block]`, through the buffer
+/// `a[..block]` which ends up at the back. Both runs are cut into blocks of `block` elements,
+/// except for the front of the first and the back of the second. The whole blocks are sorted by
+/// their first element, ties going to the first run, and `tags` keeps track of which run each
+/// came from. Then every block only has to be merged with what is left over of the blocks
+/// before it that came from the other run.
+fn merge_blocks<T, F: FnMut(&T, &T) -> bool>(
+    a: &mut [T],
+    mid: usize,
+    block: usize,
+    tags: &mut [T],
+    is_less: &mut F,
+) {
+    let n = a.len();
+    let first = block + (mid - block) % block;
+    let num_a = (mid - first) / block;
+    let blocks = num_a + (n - mid) / block;
+    let tail = (n - mid) % block;
+
+    // the tag of the first block of the second run, if any: blocks tagged lower come from the
+    // first run
+    let mut mid_tag = num_a;
+
+    for q in 0..blocks {
+        let mut min = q;
+
+        for i in q + 1..blocks {
+            let (x, y) = (&a[first + i * block], &a[first + min * block]);
+
+            if is_less(x, y) || !is_less(y, x) && is_less(&tags[i], &tags[min]) {
+                min = i;
+            }
+        }
+
+        if min != q {
+            let (l, r) = a.split_at_mut(first + min * block);
+
+            l[first + q * block..][..block].swap_with_slice(&mut r[..block]);
+            tags.swap(q, min);
+
+            if mid_tag == q {
+                mid_tag = min;
+            } else if mid_tag == min {
+                mid_tag = q;
+            }
+        }
+    }
+
+    // the partial last block of the second run goes in front of the blocks that start after it,
+    // which all come from the first run
+    let mut after_tail = 0;
+
+    if tail > 0 {
+        while after_tail < blocks
+            && is_less(&a[n - tail], &a[first + (blocks - after_tail - 1) * block])
+        {
+            after_tail += 1;
+        }
+
+        a[first + (blocks - after_tail) * block..].rotate_right(tail);
+    }
+
+    let mut merge = BlockMerge {
+        out: 0,
+        pending: first - block,
+        pending_from_a: true,
+    };
+    let mut start = first;
+
+    for q in 0..blocks - after_tail {
+        let from_a = mid_tag == blocks || is_less(&tags[q], &tags[mid_tag]);
+
+        merge.push(a, start, block, from_a, is_less);
+        start += block;
+    }
+
+    if tail > 0 {
+        merge.push(a, start, tail, false, is_less);
+        start += tail;
+    }
+
+    for _ in 0..after_tail {
+        merge.push(a, start, block, true, is_less);
+        start += block;
+    }
+
+    merge.flush(a, start);
+}
+
+// State of `merge_blocks` after some blocks: everything before `out` is merged, followed by the
+// buffer, then the `pending` elements left over from the last blocks, which come from the first
+// run if `pending_from_a`
+struct BlockMerge {
+    out: usize,
+    pending: usize,
+    pending_from_a: bool,
+}
+
+impl BlockMerge {
+    /// Take in the block `a[start..start + len]`, which is at most as long as the buffer.
+    fn push<T, F: FnMut(&T, &T) -> bool>(
+        &mut self,
+        a: &mut [T],
+        start: usize,
+        len: usize,
+        from_a: bool,
+        is_less: &mut F,
+    ) {
+        if self.pending == 0 || self.pending_from_a == from_a {
+            self.flush(a, start);
+            self.pending = len;
+            self.pending_from_a = from_a;
+
+            return;
+        }
+
+        let mut i = start - self.pending;
+        let mut j = start;
+        let end = start + len;
+
+        while i < start && j < end {
+            // equal elements are taken from the first run first
+            let take_pending = if self.pending_from_a {
+                !is_less(&a[j], &a[i])
+            } else {
+                is_less(&a[i], &a[j])
+            };
+
+            if take_pending {
+                a.swap(self.out, i);
+                i += 1;
+            } else {
+                a.swap(self.out, j);
+                j += 1;
+            }
+
+            self.out += 1;
+        }
+
+        if i == start {
+            self.pending = end - j;
+            self.pending_from_a = from_a;
+        } else {
+            // the block ran out first and was replaced by buffer elements, which go in front of
+            // what is left over of the pending elements
+            for k in (i..start).rev() {
+                a.swap(k, k + len);
+            }
+
+            self.pending = start - i;
+        }
+    }
+
+    /// Move the pending elements, which end at `end`, to the merged ones.
+    fn flush<T>(&mut self, a: &mut [T], end: usize) {
+        for i in end - self.pending..end {
+            a.swap(self.out, i);
+            self.out += 1;
+        }
+
+        self.pending = 0;
+    }
+}
+
+/// Rotation merge of the sorted `a[..mid]` and `a[mid..]`, without a buffer and in O(log n) stack,
+/// for the merges `block_merge` cannot take keys for. Each level of recursion moves every element
+/// at most once, for O(n log n) moves overall. The middle element of the longer run is the
+/// pivot: the elements of the other run that belong before it are found by binary search, the
+/// block between them is rotated to put the pivot in its final position, and both sides are
+/// merged recursively.
+fn rotation_merge<T, F: FnMut(&T, &T) -> bool>(a: &mut [T], mid: usize, is_less: &mut F) {
+    if mid == 0 || mid == a.len() {
+        return;
+    }
+
+    let (p, left_before, left_after) = if mid >= a.len() - mid {
+        let c1 = mid / 2;
+        let c2 = mid + a[mid..].partition_point(|x| is_less(x, &a[c1]));
+
+        a[c1..c2].rotate_left(mid - c1);
+
+        (c1 + c2 - mid, c1, mid - c1 - 1)
+    } else {
+        let c2 = mid + (a.len() - mid) / 2;
+        let c1 = a[..mid].partition_point(|x| !is_less(&a[c2], x));
+
+        a[c1..=c2].rotate_left(mid - c1);
+
+        (c1 + c2 - mid, c1, mid - c1)
+    };
+
+    let (before, after) = a.split_at_mut(p);
+
+    rotation_merge(before, left_before, is_less);
+    rotation_merge(&mut after[1..], left_after, is_less);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rand::Wyhash64RNG;
+    use crate::tests::test_stable_sort_records;
+
+    #[test]
+    fn buffered() {
+        crate::tests::test_sort(natural_merge_sort);
+    }
+
+    #[test]
+    fn in_place() {
+        crate::tests::test_sort(natural_merge_sort_in_place);
+    }
+
+    #[test]
+    fn by() {
+        test_stable_sort_records(|a| natural_merge_sort_by(a, |x, y| x.key.cmp(&y.key)));
+        test_stable_sort_records(|a| natural_merge_sort_in_place_by(a, |x, y| x.key.cmp(&y.key)));
+    }
+
+    #[test]
+    fn by_key() {
+        test_stable_sort_records(|a| natural_merge_sort_by_key(a, |r| r.key));
+        test_stable_sort_records(|a| natural_merge_sort_in_place_by_key(a, |r| r.key));
+    }
+
+    // Sorted blocks of random lengths with few distinct values, so that merges both gallop and
+    // have to keep equal elements in order
+    fn runs_input(rng: &mut Wyhash64RNG, n: usize) -> Vec<(u64, usize)> {
+        let mut a: Vec<(u64, usize)> = Vec::with_capacity(n);
+
+        while a.len() < n {
+            let len = rng.gen_in_range(1..300) as usize;
+            let mut block: Vec<u64> = (0..len).map(|_| rng.gen_in_range(0..50)).collect();
+
+            block.sort_unstable();
+
+            if rng.gen_in_range(0..4) == 0 {
+                block.reverse();
+            }
+
+            a.extend(block.into_iter().map(|x| (x, 0)));
+        }
+
+        a.truncate(n);
+
+        a.iter_mut().enumerate().for_each(|(i, x)| x.1 = i);
+
+        a
+    }
+
+    #[test]
+    fn runs_stable() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+
+        for n in [10, 100, 1000, 5000] {
+            let a = runs_input(&mut rng, n);
+            let mut expected = a.clone();
+            expected.sort_by_key(|x| x.0);
+
+            let mut b = a.clone();
+            natural_merge_sort_by_key(&mut b, |x| x.0);
+            assert_eq!(expected, b);
+
+            let mut c = a.clone();
+            natural_merge_sort_in_place_by_key(&mut c, |x| x.0);
+            assert_eq!(expected, c);
+
+            let mut d = a;
+            natural_merge_sort(&mut d);
+            expected.sort_unstable();
+            assert_eq!(expected, d);
+        }
+    }
+
+    #[test]
+    fn two_long_runs() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+
+        for _ in 0..50 {
+            let l = rng.gen_in_range(100..2000) as usize;
+            let r = rng.gen_in_range(100..2000) as usize;
+            let spread = rng.gen_in_range(1..1000);
+
+            let mut a: Vec<(u64, usize)> = (0..l + r)
+                .map(|i| (rng.gen_in_range(0..spread) * (i as u64 % 3 + 1), i))
+                .collect();
+
+            a[..l].sort_by_key(|x| x.0);
+            a[l..].sort_by_key(|x| x.0);
+
+            let mut expected = a.clone();
+            expected.sort_by_key(|x| x.0);
+
+            natural_merge_sort_by_key(&mut a, |x| x.0);
+
+            assert_eq!(expected, a);
+        }
+    }
+
+    #[test]
+    fn sorted_input_is_linear() {
+        let n = 10_000;
+        let mut a: Vec<u64> = (0..n).collect();
+        let mut count = 0;
+
+        natural_merge_sort_by(&mut a, |x, y| {
+            count += 1;
+            x.cmp(y)
+        });
+
+        assert_eq!(n as usize - 1, count);
+
+        a.reverse();
+        count = 0;
+
+        natural_merge_sort_by(&mut a, |x, y| {
+            count += 1;
+            x.cmp(y)
+        });
+
+        assert_eq!(n as usize - 1, count);
+        assert!(a.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn gallops() {
+        let s = [1, 2, 3, 5, 8, 13, 21, 34, 55, 89];
+
+        for x in 0..100 {
+            assert_eq!(s.partition_point(|&y| y < x), gallop(&s, |&y| y < x));
+            assert_eq!(
+                s.len() - s.partition_point(|&y| y < x),
+                gallop_back(&s, |&y| y >= x)
+            );
+        }
+    }
+
+    type Merge = fn(&mut [(u64, usize)], usize);
+
+    // Two sorted runs of keys below `spread`, numbered in order so that stability shows
+    fn two_runs(rng: &mut Wyhash64RNG, l: usize, r: usize, spread: u64) -> Vec<(u64, usize)> {
+        let mut a: Vec<(u64, usize)> = (0..l + r)
+            .map(|i| (rng.gen_in_range(0..spread), i))
+            .collect();
+
+        a[..l].sort_unstable();
+        a[l..].sort_unstable();
+
+        a
+    }
+
+    #[test]
+    fn in_place_merges_stable() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+        let merges: [Merge; 2] = [
+            |a, mid| rotation_merge(a, mid, &mut |x, y| x.0 < y.0),
+            |a, mid| block_merge(a, mid, &mut |x, y| x.0 < y.0),
+        ];
+
+        for merge in merges {
+            for (max_len, spread) in [(40, 10), (1000, 5), (1000, 100), (1000, 1_000_000)] {
+                for _ in 0..100 {
+                    let l = rng.gen_in_range(0..max_len) as usize;
+                    let r = rng.gen_in_range(0..max_len) as usize;
+                    let mut a = two_runs(&mut rng, l, r, spread);
+
+                    let mut expected = a.clone();
+                    expected.sort_by_key(|x| x.0);
+
+                    merge(&mut a, l);
+
+                    assert_eq!(expected, a);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn block_merge_needs_keys() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+
+        // the block path is taken as long as the first run has about 2√n distinct elements
+        for (spread, taken) in [(1_000_000, true), (20, false)] {
+            for _ in 0..100 {
+                let l = rng.gen_in_range(200..1000) as usize;
+                let r = rng.gen_in_range(1..1000) as usize;
+                let mut a = two_runs(&mut rng, l, r, spread);
+
+                let mut expected = a.clone();
+
+                if taken {
+                    expected.sort_by_key(|x| x.0);
+                }
+
+                assert_eq!(taken, try_block_merge(&mut a, l, &mut |x, y| x.0 < y.0));
+                assert_eq!(expected, a);
+            }
+        }
+    }
+
+    #[test]
+    fn block_merge_equal_blocks() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+
+        // enough distinct keys in the first run, but many blocks starting with the same element
+        for _ in 0..100 {
+            let l = rng.gen_in_range(400..1000) as usize;
+            let r = rng.gen_in_range(1..1000) as usize;
+            let mut a: Vec<(u64, usize)> = (0..l + r)
+                .map(|i| match i {
+                    _ if i < l / 2 => rng.gen_in_range(0..1_000_000),
+                    _ if i < l => 500_000,
+                    _ => rng.gen_in_range(0..4) * 250_000,
+                })
+                .enumerate()
+                .map(|(i, x)| (x, i))
+                .collect();
+
+            a[..l].sort_by_key(|x| x.0);
+            a[l..].sort_by_key(|x| x.0);
+
+            let mut expected = a.clone();
+            expected.sort_by_key(|x| x.0);
+
+            assert!(try_block_merge(&mut a, l, &mut |x, y| x.0 < y.0));
+            assert_eq!(expected, a);
+        }
+    }
+
+    #[test]
+    fn in_place_block_merges() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+
+        for spread in [3, 100, 1_000_000] {
+            for n in [100, 1000, 5000] {
+                let a: Vec<(u64, usize)> =
+                    (0..n).map(|i| (rng.gen_in_range(0..spread), i)).collect();
+
+                let mut expected = a.clone();
+                expected.sort_by_key(|x| x.0);
+
+                let mut b = a.clone();
+                natural_merge_sort_in_place_by_key(&mut b, |x| x.0);
+                assert_eq!(expected, b);
+
+                let mut c = a;
+                natural_merge_sort_in_place(&mut c);
+                assert_eq!(expected, c);
+            }
+        }
+    }
+}
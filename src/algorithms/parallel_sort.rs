@@ -0,0 +1,473 @@
+//! Parallel sorting and selection on top of `std::thread::scope`. Work is split recursively
+//! between the available threads, and slices below a threshold are handed to the sequential
+//! algorithms.
+
+use std::num::NonZero;
+use std::thread;
+
+use crate::algorithms::introsort::{introsort, partition, sort3};
+use crate::algorithms::merge_sort::merge_sort;
+use crate::algorithms::pdqsort::pdqsort;
+use crate::algorithms::select::nth_element;
+use crate::rand::Wyhash64RNG;
+
+// Sample size per bucket in sample sort and selection
+const OVERSAMPLING: usize = 16;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Parallelism {
+    threads: usize,
+    threshold: usize,
+}
+
+impl Parallelism {
+    pub fn new(threads: usize) -> Self {
+        assert!(threads >= 1, "at least one thread is needed");
+
+        Self {
+            threads,
+            threshold: 1 << 13,
+        }
+    }
+
+    /// As many threads as the system reports to be available.
+    pub fn available() -> Self {
+        Self::new(thread::available_parallelism().map_or(1, NonZero::get))
+    }
+
+    /// Slices of at most this many elements are not split between threads.
+    pub fn threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    fn is_sequential(self, len: usize) -> bool {
+        self.threads <= 1 || len <= self.threshold
+    }
+
+    // Give each side of a split its share of the threads
+    fn split(self) -> (Self, Self) {
+        let left = self.threads / 2;
+
+        (
+            Self {
+                threads: left,
+                ..self
+            },
+            Self {
+                threads: self.threads - left,
+                ..self
+            },
+        )
+    }
+}
+
+/// Stable. Sorts both halves in parallel, then merges them in parallel by splitting the output
+/// around the median of the longer half. Falls back to `merge_sort`.
+pub fn parallel_merge_sort<T: Copy + PartialOrd + Send + Sync>(a: &mut [T], par: Parallelism) {
+    if par.is_sequential(a.len()) {
+        merge_sort(a);
+        return;
+    }
+
+    let mut buf = a.to_vec();
+
+    merge_sort_rec(a, &mut buf, par);
+}
+
+fn merge_sort_rec<T: Copy + PartialOrd + Send + Sync>(
+    a: &mut [T],
+    buf: &mut [T],
+    par: Parallelism,
+) {
+    if par.is_sequential(a.len()) {
+        merge_sort(a);
+        return;
+    }
+
+    let mid = a.len() / 2;
+    let (left_par, right_par) = par.split();
+
+    {
+        let (al, ar) = a.split_at_mut(mid);
+        let (bl, br) = buf.split_at_mut(mid);
+
+        thread::scope(|s| {
+            s.spawn(|| merge_sort_rec(al, bl, left_par));
+            merge_sort_rec(ar, br, right_par);
+        });
+    }
+
+    buf.copy_from_slice(a);
+
+    let (l, r) = buf.split_at(mid);
+
+    parallel_merge(l, r, a, par);
+}
+
+fn parallel_merge<T: Copy + PartialOrd + Send + Sync>(
+    l: &[T],
+    r: &[T],
+    out: &mut [T],
+    par: Parallelism,
+) {
+    if par.is_sequential(out.len()) {
+        merge(l, r, out);
+        return;
+    }
+
+    // Elements equal to the split element stay on the side that keeps them after the ones from
+    // the left half
+    let (i, j) = if l.len() >= r.len() {
+        let i = l.len() / 2;
+
+        (i, r.partition_point(|x| x < &l[i]))
+    } else {
+        let j = r.len() / 2;
+
+        (l.partition_point(|x| x <= &r[j]), j)
+    };
+
+    let (left_par, right_par) = par.split();
+    let (o1, o2) = out.split_at_mut(i + j);
+
+    thread::scope(|s| {
+        s.spawn(|| parallel_merge(&l[..i], &r[..j], o1, left_par));
+        parallel_merge(&l[i..], &r[j..], o2, right_par);
+    });
+}
+
+fn merge<T: Copy + PartialOrd>(l: &[T], r: &[T], out: &mut [T]) {
+    let mut i = 0;
+    let mut j = 0;
+
+    for x in out.iter_mut() {
+        if j == r.len() || i < l.len() && l[i] <= r[j] {
+            *x = l[i];
+            i += 1;
+        } else {
+            *x = r[j];
+            j += 1;
+        }
+    }
+}
+
+/// Partitions sequentially, then sorts the two sides in parallel. Falls back to `introsort`, as
+/// plain `quicksort` has no protection against quadratic inputs.
+pub fn parallel_quicksort<T: PartialOrd + Send>(a: &mut [T], par: Parallelism) {
+    if par.is_sequential(a.len()) {
+        introsort(a);
+        return;
+    }
+
+    let mid = a.len() / 2;
+    let last = a.len() - 1;
+
+    sort3(a, 0, mid, last, &mut T::lt);
+    a.swap(0, mid);
+
+    let (p, _) = partition(a, &mut T::lt);
+    let (left, right) = a.split_at_mut(p);
+    let right = &mut right[1..];
+
+    // threads are shared out in proportion to the sizes of the sides
+    let total = left.len() + right.len();
+    let left_threads = (par.threads * left.len() / total.max(1)).clamp(1, par.threads - 1);
+    let left_par = Parallelism {
+        threads: left_threads,
+        ..par
+    };
+    let right_par = Parallelism {
+        threads: par.threads - left_threads,
+        ..par
+    };
+
+    thread::scope(|s| {
+        s.spawn(|| parallel_quicksort(left, left_par));
+        parallel_quicksort(right, right_par);
+    });
+}
+
+/// Splits the elements into about one bucket per thread around splitters picked from a random
+/// sample, then sorts all buckets in parallel. Falls back to `pdqsort`.
+pub fn parallel_sample_sort<T: Copy + PartialOrd + Send + Sync>(a: &mut [T], par: Parallelism) {
+    let buckets = par.threads.min(a.len() / par.threshold.max(1));
+
+    if par.is_sequential(a.len()) || buckets <= 1 {
+        pdqsort(a);
+        return;
+    }
+
+    let splitters = &choose_splitters(a, buckets);
+    let chunk_len = a.len().div_ceil(par.threads);
+
+    // every thread distributes its own chunk into local buckets
+    let local: Vec<Vec<Vec<T>>> = thread::scope(|s| {
+        let handles: Vec<_> = a
+            .chunks(chunk_len)
+            .map(|chunk| s.spawn(move || distribute(chunk, splitters)))
+            .collect();
+
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    thread::scope(|s| {
+        let mut rest = a;
+
+        for b in 0..num_buckets(splitters) {
+            let len = local.iter().map(|l| l[b].len()).sum();
+            let (bucket, tail) = rest.split_at_mut(len);
+            let local = &local;
+
+            rest = tail;
+
+            if len == 0 {
+                continue;
+            }
+
+            s.spawn(move || {
+                let mut k = 0;
+
+                for l in local {
+                    bucket[k..k + l[b].len()].copy_from_slice(&l[b]);
+                    k += l[b].len();
+                }
+
+                if b % 2 == 0 {
+                    pdqsort(bucket);
+                }
+            });
+        }
+    });
+}
+
+/// The `k`th smallest element (starting from 1), like `select`. Counts how many elements fall
+/// into every bucket in parallel, then selects sequentially among the elements of the bucket
+/// containing the answer with `nth_element`.
+pub fn parallel_select<T: Copy + PartialOrd + Send + Sync>(
+    a: &[T],
+    k: usize,
+    par: Parallelism,
+) -> Option<T> {
+    if k == 0 || k > a.len() {
+        return None;
+    }
+
+    let buckets = par.threads.min(a.len() / par.threshold.max(1));
+
+    if par.is_sequential(a.len()) || buckets <= 1 {
+        return Some(kth(a.to_vec(), k));
+    }
+
+    let splitters = &choose_splitters(a, buckets);
+    let chunk_len = a.len().div_ceil(par.threads);
+
+    let counts: Vec<Vec<usize>> = thread::scope(|s| {
+        let handles: Vec<_> = a
+            .chunks(chunk_len)
+            .map(|chunk| {
+                s.spawn(move || {
+                    let mut c = vec![0; num_buckets(splitters)];
+
+                    for x in chunk {
+                        c[bucket_of(x, splitters)] += 1;
+                    }
+
+                    c
+                })
+            })
+            .collect();
+
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    let mut before = 0;
+    let mut target = 0;
+
+    for b in 0..num_buckets(splitters) {
+        let size: usize = counts.iter().map(|c| c[b]).sum();
+
+        if before + size >= k {
+            target = b;
+            break;
+        }
+
+        before += size;
+    }
+
+    let candidates: Vec<T> = thread::scope(|s| {
+        let handles: Vec<_> = a
+            .chunks(chunk_len)
+            .map(|chunk| {
+                s.spawn(move || {
+                    chunk
+                        .iter()
+                        .filter(|&x| bucket_of(x, splitters) == target)
+                        .copied()
+                        .collect::<Vec<T>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect()
+    });
+
+    Some(kth(candidates, k - before))
+}
+
+// Quickselect falling back to heapsort, so that buckets full of equal elements stay fast
+fn kth<T: Copy + PartialOrd>(mut a: Vec<T>, k: usize) -> T {
+    nth_element(&mut a, k - 1);
+
+    a[k - 1]
+}
+
+// `buckets - 1` evenly spaced elements of a sorted random sample
+#[allow(clippy::cast_possible_truncation)]
+fn choose_splitters<T: Copy + PartialOrd>(a: &[T], buckets: usize) -> Vec<T> {
+    let mut rng = Wyhash64RNG::from_seed(123);
+    let mut sample: Vec<T> = (0..buckets * OVERSAMPLING)
+        .map(|_| a[rng.gen_in_range(0..a.len() as u64) as usize])
+        .collect();
+
+    pdqsort(&mut sample);
+
+    (1..buckets).map(|i| sample[i * OVERSAMPLING]).collect()
+}
+
+// Bucket `2 * i + 1` holds the elements equal to splitter `i`, and bucket `2 * i` the ones between
+// splitters `i - 1` and `i`. Keeping equal elements apart stops duplicates from piling up in one
+// bucket that then needs sorting.
+fn bucket_of<T: PartialOrd>(x: &T, splitters: &[T]) -> usize {
+    let i = splitters.partition_point(|s| s < x);
+
+    if splitters.get(i).is_some_and(|s| s == x) {
+        2 * i + 1
+    } else {
+        2 * i
+    }
+}
+
+fn num_buckets<T>(splitters: &[T]) -> usize {
+    2 * splitters.len() + 1
+}
+
+fn distribute<T: Copy + PartialOrd>(chunk: &[T], splitters: &[T]) -> Vec<Vec<T>> {
+    let mut buckets = vec![Vec::new(); num_buckets(splitters)];
+
+    for &x in chunk {
+        buckets[bucket_of(&x, splitters)].push(x);
+    }
+
+    buckets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const N: u64 = if cfg!(miri) { 500 } else { 20_000 };
+
+    fn inputs() -> Vec<Vec<u64>> {
+        let mut rng = Wyhash64RNG::from_seed(123);
+
+        vec![
+            (0..N).map(|_| rng.gen()).collect(),
+            (0..N).map(|_| rng.gen_in_range(0..5)).collect(),
+            (0..N).collect(),
+            (0..N).rev().collect(),
+            vec![7; N as usize],
+        ]
+    }
+
+    fn check(sort: impl Fn(&mut [u64], Parallelism)) {
+        for threads in [1, 2, 3, 8] {
+            let par = Parallelism::new(threads).threshold(64);
+
+            crate::tests::test_sort(|a| sort(a, par));
+
+            for input in inputs() {
+                let mut a = input.clone();
+                let mut expected = input;
+
+                expected.sort_unstable();
+                sort(&mut a, par);
+
+                assert_eq!(expected, a);
+            }
+        }
+    }
+
+    #[test]
+    fn merge_sort() {
+        check(parallel_merge_sort);
+    }
+
+    #[test]
+    fn merge_sort_stable() {
+        let par = Parallelism::new(4).threshold(16);
+        let mut rng = Wyhash64RNG::from_seed(123);
+        let mut a: Vec<(u8, u32)> = (0..1000)
+            .map(|i| (rng.gen_in_range(0..4) as u8, i))
+            .collect();
+        let mut expected = a.clone();
+
+        expected.sort_by_key(|x| x.0);
+
+        // only the first field is compared
+        #[derive(Clone, Copy, Debug)]
+        struct Key((u8, u32));
+
+        impl PartialEq for Key {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 .0 == other.0 .0
+            }
+        }
+
+        impl PartialOrd for Key {
+            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                self.0 .0.partial_cmp(&other.0 .0)
+            }
+        }
+
+        let mut keys: Vec<Key> = a.iter().copied().map(Key).collect();
+
+        parallel_merge_sort(&mut keys, par);
+        a = keys.into_iter().map(|k| k.0).collect();
+
+        assert_eq!(expected, a);
+    }
+
+    #[test]
+    fn quicksort() {
+        check(parallel_quicksort);
+    }
+
+    #[test]
+    fn sample_sort() {
+        check(parallel_sample_sort);
+    }
+
+    #[test]
+    fn selection() {
+        for threads in [1, 2, 5] {
+            let par = Parallelism::new(threads).threshold(64);
+
+            for input in inputs() {
+                let n = input.len();
+                let mut sorted = input.clone();
+                sorted.sort_unstable();
+
+                for k in [1, 2, n / 3, n / 2, n] {
+                    assert_eq!(Some(sorted[k - 1]), parallel_select(&input, k, par));
+                }
+
+                assert_eq!(None, parallel_select(&input, 0, par));
+                assert_eq!(None, parallel_select(&input, n + 1, par));
+            }
+        }
+    }
+}
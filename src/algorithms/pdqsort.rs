@@ -0,0 +1,245 @@
+//! Pattern-defeating quicksort: introsort extended to run in linear time on sorted inputs and on
+//! slices with few distinct values, and to shuffle away patterns that produce bad pivots
+
+use std::cmp::Ordering;
+
+use crate::algorithms::insertion_sort::insertion_sort_impl;
+use crate::algorithms::introsort::{heapsort, log2, partition, sort3, INSERTION_THRESHOLD};
+use crate::rand::Wyhash64RNG;
+
+// Above this length the pivot is the median of three medians of three
+const NINTHER_THRESHOLD: usize = 128;
+
+// Give up on a partial insertion sort after this many element moves
+const PARTIAL_INSERTION_MOVES: usize = 8;
+
+pub fn pdqsort<T: PartialOrd>(a: &mut [T]) {
+    pdqsort_impl(a, &mut T::lt);
+}
+
+pub fn pdqsort_by<T, F: FnMut(&T, &T) -> Ordering>(a: &mut [T], mut cmp: F) {
+    pdqsort_impl(a, &mut |x, y| cmp(x, y) == Ordering::Less);
+}
+
+pub fn pdqsort_by_key<T, K: Ord, F: FnMut(&T) -> K>(a: &mut [T], mut f: F) {
+    pdqsort_impl(a, &mut |x, y| f(x) < f(y));
+}
+
+fn pdqsort_impl<T, F: FnMut(&T, &T) -> bool>(a: &mut [T], is_less: &mut F) {
+    let bad_allowed = log2(a.len());
+
+    pdqsort_rec(a, false, bad_allowed, is_less);
+}
+
+// If `has_pred` is set, `v[0]` is not part of the slice being sorted but the element right before
+// it, which is known to be not greater than any element of the slice.
+fn pdqsort_rec<T, F: FnMut(&T, &T) -> bool>(
+    mut v: &mut [T],
+    mut has_pred: bool,
+    mut bad_allowed: u32,
+    is_less: &mut F,
+) {
+    loop {
+        let off = usize::from(has_pred);
+        let len = v.len() - off;
+
+        if len <= INSERTION_THRESHOLD {
+            insertion_sort_impl(&mut v[off..], is_less);
+            return;
+        }
+
+        if bad_allowed == 0 {
+            heapsort(&mut v[off..], is_less);
+            return;
+        }
+
+        choose_pivot(&mut v[off..], is_less);
+
+        // The pivot equals the predecessor, so it is the minimum. Everything equal to it is
+        // already in place and only the greater elements are left to sort.
+        if has_pred && !is_less(&v[0], &v[1]) {
+            let eq = partition_equal(&mut v[1..], is_less);
+            let tmp = v;
+
+            v = &mut tmp[eq..];
+            continue;
+        }
+
+        let (mid, was_partitioned) = partition(&mut v[off..], is_less);
+        let tmp = v;
+        let (left, right) = tmp.split_at_mut(off + mid);
+
+        let balanced = mid >= len / 8 && len - mid > len / 8;
+
+        if !balanced {
+            bad_allowed -= 1;
+
+            break_patterns(&mut left[off..]);
+            break_patterns(&mut right[1..]);
+        } else if was_partitioned
+            && partial_insertion_sort(&mut left[off..], is_less)
+            && partial_insertion_sort(&mut right[1..], is_less)
+        {
+            return;
+        }
+
+        // the pivot becomes the predecessor of the right side
+        if mid < len - mid {
+            pdqsort_rec(left, has_pred, bad_allowed, is_less);
+
+            v = right;
+            has_pred = true;
+        } else {
+            pdqsort_rec(right, true, bad_allowed, is_less);
+
+            v = left;
+        }
+    }
+}
+
+fn choose_pivot<T, F: FnMut(&T, &T) -> bool>(a: &mut [T], is_less: &mut F) {
+    let len = a.len();
+    let mid = len / 2;
+
+    if len > NINTHER_THRESHOLD {
+        sort3(a, 1, mid - 1, len - 2, is_less);
+        sort3(a, 2, mid + 1, len - 3, is_less);
+        sort3(a, 0, mid, len - 1, is_less);
+        sort3(a, mid - 1, mid, mid + 1, is_less);
+    } else {
+        sort3(a, 0, mid, len - 1, is_less);
+    }
+
+    a.swap(0, mid);
+}
+
+/// Move the elements not greater than the pivot `a[0]` to the front, returning their count. Only
+/// valid when the pivot is the minimum, so that they are all equal to it.
+fn partition_equal<T, F: FnMut(&T, &T) -> bool>(a: &mut [T], is_less: &mut F) -> usize {
+    let (pivot, rest) = a.split_first_mut().expect("slice should not be empty");
+    let mut l = 0;
+    let mut r = rest.len();
+
+    loop {
+        while l < r && !is_less(pivot, &rest[l]) {
+            l += 1;
+        }
+
+        while l < r && is_less(pivot, &rest[r - 1]) {
+            r -= 1;
+        }
+
+        if l >= r {
+            break;
+        }
+
+        r -= 1;
+        rest.swap(l, r);
+        l += 1;
+    }
+
+    l + 1
+}
+
+/// Insertion sort that gives up once it has moved too many elements. Returns whether the slice
+/// ended up sorted.
+fn partial_insertion_sort<T, F: FnMut(&T, &T) -> bool>(a: &mut [T], is_less: &mut F) -> bool {
+    let mut moves = 0;
+
+    for i in 1..a.len() {
+        let mut j = i;
+
+        while j > 0 && is_less(&a[j], &a[j - 1]) {
+            a.swap(j, j - 1);
+            j -= 1;
+            moves += 1;
+        }
+
+        if moves > PARTIAL_INSERTION_MOVES {
+            return false;
+        }
+    }
+
+    true
+}
+
+// Swap a few elements around the middle with pseudorandom ones
+#[allow(clippy::cast_possible_truncation)]
+fn break_patterns<T>(a: &mut [T]) {
+    let len = a.len();
+
+    if len < 8 {
+        return;
+    }
+
+    let mut rng = Wyhash64RNG::from_seed(len as u64);
+
+    for i in [len / 4, len / 2, len / 4 * 3] {
+        let j = rng.gen_in_range(0..len as u64) as usize;
+
+        a.swap(i, j);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rand::Wyhash64RNG;
+    use crate::tests::test_sort_records;
+
+    #[test]
+    fn test() {
+        crate::tests::test_sort(pdqsort);
+    }
+
+    #[test]
+    fn by() {
+        test_sort_records(|a| pdqsort_by(a, |x, y| x.key.cmp(&y.key)));
+    }
+
+    #[test]
+    fn by_key() {
+        test_sort_records(|a| pdqsort_by_key(a, |r| r.key));
+    }
+
+    fn count_comparisons(a: &mut [u64]) -> usize {
+        let mut count = 0;
+
+        pdqsort_by(a, |x, y| {
+            count += 1;
+            x.cmp(y)
+        });
+
+        assert!(a.windows(2).all(|w| w[0] <= w[1]));
+
+        count
+    }
+
+    #[test]
+    fn linear_on_easy_inputs() {
+        let n = 10_000;
+
+        let mut sorted: Vec<u64> = (0..n).collect();
+        let mut few_unique: Vec<u64> = (0..n).map(|i| i * 7919 % 4).collect();
+        let mut equal = vec![42; 10_000];
+
+        assert!(count_comparisons(&mut sorted) < 3 * n as usize);
+        assert!(count_comparisons(&mut few_unique) < 10 * n as usize);
+        assert!(count_comparisons(&mut equal) < 3 * n as usize);
+    }
+
+    #[test]
+    fn many_duplicates() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+
+        for distinct in [1, 2, 3, 10, 100] {
+            let mut a: Vec<u64> = (0..5000).map(|_| rng.gen_in_range(0..distinct)).collect();
+            let mut expected = a.clone();
+
+            expected.sort_unstable();
+            pdqsort(&mut a);
+
+            assert_eq!(expected, a);
+        }
+    }
+}
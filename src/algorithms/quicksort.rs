@@ -1,35 +1,60 @@
+use std::cmp::Ordering;
+
 use crate::rand::Wyhash64RNG;
 
-pub fn quicksort<T: PartialOrd + Copy>(arr: &mut [T]) {
+pub fn quicksort<T: PartialOrd>(arr: &mut [T]) {
+    quicksort_impl(arr, &mut T::lt);
+}
+
+pub fn quicksort_by<T, F: FnMut(&T, &T) -> Ordering>(arr: &mut [T], mut cmp: F) {
+    quicksort_impl(arr, &mut |a, b| cmp(a, b) == Ordering::Less);
+}
+
+pub fn quicksort_by_key<T, K: Ord, F: FnMut(&T) -> K>(arr: &mut [T], mut f: F) {
+    quicksort_impl(arr, &mut |a, b| f(a) < f(b));
+}
+
+fn quicksort_impl<T, F: FnMut(&T, &T) -> bool>(arr: &mut [T], is_less: &mut F) {
     if arr.len() <= 1 {
         return;
     }
 
-    quicksort_rec(arr, 0, arr.len() - 1);
+    quicksort_rec(arr, 0, arr.len() - 1, is_less);
 }
 
-fn quicksort_rec<T: PartialOrd + Copy>(arr: &mut [T], low: usize, high: usize) {
+fn quicksort_rec<T, F: FnMut(&T, &T) -> bool>(
+    arr: &mut [T],
+    low: usize,
+    high: usize,
+    is_less: &mut F,
+) {
     if high <= low {
         return;
     }
 
-    let p = partition_hoare(arr, low, high);
+    let p = partition_hoare(arr, low, high, is_less);
 
-    quicksort_rec(arr, low, p);
-    quicksort_rec(arr, p + 1, high);
+    quicksort_rec(arr, low, p, is_less);
+    quicksort_rec(arr, p + 1, high, is_less);
 }
 
-fn partition_hoare<T: PartialOrd + Copy>(arr: &mut [T], low: usize, high: usize) -> usize {
+// The pivot is not copied out of the slice, so its position is tracked across swaps instead
+fn partition_hoare<T, F: FnMut(&T, &T) -> bool>(
+    arr: &mut [T],
+    low: usize,
+    high: usize,
+    is_less: &mut F,
+) -> usize {
     let mut l = low.wrapping_sub(1);
     let mut r = high.wrapping_add(1);
 
-    let pivot = arr[low];
+    let mut pivot = low;
 
     loop {
         loop {
             l = l.wrapping_add(1);
 
-            if arr[l] >= pivot {
+            if !is_less(&arr[l], &arr[pivot]) {
                 break;
             }
         }
@@ -37,7 +62,7 @@ fn partition_hoare<T: PartialOrd + Copy>(arr: &mut [T], low: usize, high: usize)
         loop {
             r = r.wrapping_sub(1);
 
-            if arr[r] <= pivot {
+            if !is_less(&arr[pivot], &arr[r]) {
                 break;
             }
         }
@@ -47,53 +72,74 @@ fn partition_hoare<T: PartialOrd + Copy>(arr: &mut [T], low: usize, high: usize)
         }
 
         arr.swap(l, r);
+
+        if pivot == l {
+            pivot = r;
+        } else if pivot == r {
+            pivot = l;
+        }
     }
 }
 
-pub fn randomized_quicksort<T: PartialOrd + Copy>(arr: &mut [T]) {
+pub fn randomized_quicksort<T: PartialOrd>(arr: &mut [T]) {
+    randomized_quicksort_impl(arr, &mut T::lt);
+}
+
+pub fn randomized_quicksort_by<T, F: FnMut(&T, &T) -> Ordering>(arr: &mut [T], mut cmp: F) {
+    randomized_quicksort_impl(arr, &mut |a, b| cmp(a, b) == Ordering::Less);
+}
+
+pub fn randomized_quicksort_by_key<T, K: Ord, F: FnMut(&T) -> K>(arr: &mut [T], mut f: F) {
+    randomized_quicksort_impl(arr, &mut |a, b| f(a) < f(b));
+}
+
+fn randomized_quicksort_impl<T, F: FnMut(&T, &T) -> bool>(arr: &mut [T], is_less: &mut F) {
     if arr.len() <= 1 {
         return;
     }
 
     let mut rng = Wyhash64RNG::from_seed(123);
 
-    randomized_quicksort_rec(arr, &mut rng, 0, arr.len() - 1);
+    randomized_quicksort_rec(arr, &mut rng, 0, arr.len() - 1, is_less);
 }
 
-fn randomized_quicksort_rec<T: PartialOrd + Copy>(
+fn randomized_quicksort_rec<T, F: FnMut(&T, &T) -> bool>(
     arr: &mut [T],
     rng: &mut Wyhash64RNG,
     low: usize,
     high: usize,
+    is_less: &mut F,
 ) {
     if low >= high {
         return;
     }
 
-    let p = randomized_partition(arr, rng, low, high);
+    let p = randomized_partition(arr, rng, low, high, is_less);
 
-    randomized_quicksort_rec(arr, rng, low, p);
-    randomized_quicksort_rec(arr, rng, p + 1, high);
+    randomized_quicksort_rec(arr, rng, low, p, is_less);
+    randomized_quicksort_rec(arr, rng, p + 1, high, is_less);
 }
 
 #[allow(clippy::cast_possible_truncation)]
-fn randomized_partition<T: PartialOrd + Copy>(
+fn randomized_partition<T, F: FnMut(&T, &T) -> bool>(
     arr: &mut [T],
     rng: &mut Wyhash64RNG,
     low: usize,
     high: usize,
+    is_less: &mut F,
 ) -> usize {
     let range = (low as u64)..(high as u64);
     let pivot = rng.gen_in_range(range) as usize;
 
     arr.swap(low, pivot);
 
-    partition_hoare(arr, low, high)
+    partition_hoare(arr, low, high, is_less)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tests::test_sort_records;
 
     #[test]
     fn normal() {
@@ -104,4 +150,28 @@ mod tests {
     fn randomized() {
         crate::tests::test_sort(randomized_quicksort);
     }
+
+    #[test]
+    fn by() {
+        test_sort_records(|a| quicksort_by(a, |x, y| x.key.cmp(&y.key)));
+        test_sort_records(|a| randomized_quicksort_by(a, |x, y| x.key.cmp(&y.key)));
+    }
+
+    #[test]
+    fn by_key() {
+        test_sort_records(|a| quicksort_by_key(a, |r| r.key));
+        test_sort_records(|a| randomized_quicksort_by_key(a, |r| r.key));
+    }
+
+    #[test]
+    fn strings_descending() {
+        let mut words = ["pear", "apple", "fig", "banana"].map(String::from);
+
+        quicksort_by(&mut words, |a, b| b.cmp(a));
+        assert_eq!(["pear", "fig", "banana", "apple"], words);
+
+        quicksort_by_key(&mut words, String::len);
+        assert_eq!(3, words[0].len());
+        assert_eq!(6, words[3].len());
+    }
 }
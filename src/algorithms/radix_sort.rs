@@ -1,3 +1,56 @@
+use crate::algorithms::counting_sort::counting_pass;
+use crate::algorithms::insertion_sort::insertion_sort_by_key;
+use crate::algorithms::merge_sort::apply_permutation;
+
+// Buckets this small are finished off with insertion sort instead of another MSD pass
+const MSD_CUTOFF: usize = 16;
+
+/// Keys that can be mapped to unsigned integers so that the integer order matches the key order.
+pub trait RadixKey {
+    fn to_radix(self) -> u64;
+}
+
+macro_rules! impl_radix_key_unsigned {
+    ($($t:ty)*) => {$(
+        impl RadixKey for $t {
+            fn to_radix(self) -> u64 {
+                self as u64
+            }
+        }
+    )*};
+}
+
+// Flipping the sign bit moves negative numbers below the positive ones
+macro_rules! impl_radix_key_signed {
+    ($($t:ty => $u:ty),*) => {$(
+        impl RadixKey for $t {
+            fn to_radix(self) -> u64 {
+                (self as $u ^ (1 << (<$u>::BITS - 1))) as u64
+            }
+        }
+    )*};
+}
+
+// Positive floats get their sign bit set, negative ones have all bits flipped so that larger
+// magnitudes come first. The result agrees with `total_cmp`: -0.0 sorts before 0.0, and NaNs end
+// up at either end depending on their sign.
+macro_rules! impl_radix_key_float {
+    ($($t:ty => $u:ty),*) => {$(
+        impl RadixKey for $t {
+            fn to_radix(self) -> u64 {
+                let bits = self.to_bits();
+                let sign = 1 << (<$u>::BITS - 1);
+
+                (if bits & sign == 0 { bits | sign } else { !bits }) as u64
+            }
+        }
+    )*};
+}
+
+impl_radix_key_unsigned!(u8 u16 u32 u64 usize);
+impl_radix_key_signed!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, isize => usize);
+impl_radix_key_float!(f32 => u32, f64 => u64);
+
 pub fn radix_sort(xs: &mut [u64]) {
     if xs.len() <= 1 {
         return;
@@ -35,7 +88,285 @@ pub fn radix_sort(xs: &mut [u64]) {
     }
 }
 
-#[test]
-fn test() {
-    crate::tests::test_sort(radix_sort);
+/// Stable least significant digit first radix sort with digits `radix_bits` wide. Only the bits
+/// that differ between keys are looked at, so small keys take fewer passes.
+pub fn lsd_radix_sort_by_key<T, K: RadixKey, F: FnMut(&T) -> K>(
+    xs: &mut [T],
+    radix_bits: u32,
+    mut key: F,
+) {
+    check_radix_bits(radix_bits);
+
+    let keys: Vec<u64> = xs.iter().map(|x| key(x).to_radix()).collect();
+    let significant = significant_bits(&keys);
+    let mask = (1 << radix_bits) - 1;
+
+    let mut idx: Vec<usize> = (0..xs.len()).collect();
+    let mut buf = vec![0; xs.len()];
+    let mut shift = 0;
+
+    while shift < significant {
+        counting_pass(&idx, &mut buf, 1 << radix_bits, |i| {
+            ((keys[i] >> shift) & mask) as usize
+        });
+
+        std::mem::swap(&mut idx, &mut buf);
+
+        shift += radix_bits;
+    }
+
+    apply_permutation(xs, &mut idx);
+}
+
+/// Stable most significant digit first radix sort with digits `radix_bits` wide.
+pub fn msd_radix_sort_by_key<T, K: RadixKey, F: FnMut(&T) -> K>(
+    xs: &mut [T],
+    radix_bits: u32,
+    mut key: F,
+) {
+    check_radix_bits(radix_bits);
+
+    let keys: Vec<u64> = xs.iter().map(|x| key(x).to_radix()).collect();
+    let significant = significant_bits(&keys);
+
+    let mut idx: Vec<usize> = (0..xs.len()).collect();
+    let mut buf = vec![0; xs.len()];
+
+    if significant > 0 {
+        msd_rec(&keys, &mut idx, &mut buf, significant, radix_bits);
+    }
+
+    apply_permutation(xs, &mut idx);
+}
+
+// Sort by the digit just below bit `hi`, then each bucket by the following digits
+fn msd_rec(keys: &[u64], idx: &mut [usize], buf: &mut [usize], hi: u32, radix_bits: u32) {
+    if idx.len() <= MSD_CUTOFF {
+        insertion_sort_by_key(idx, |&i| keys[i]);
+        return;
+    }
+
+    let lo = hi.saturating_sub(radix_bits);
+    let mask = (1 << (hi - lo)) - 1;
+
+    let ends = counting_pass(idx, buf, 1 << (hi - lo), |i| {
+        ((keys[i] >> lo) & mask) as usize
+    });
+
+    idx.copy_from_slice(buf);
+
+    if lo == 0 {
+        return;
+    }
+
+    let mut start = 0;
+
+    for end in ends {
+        if end - start > 1 {
+            msd_rec(
+                keys,
+                &mut idx[start..end],
+                &mut buf[start..end],
+                lo,
+                radix_bits,
+            );
+        }
+
+        start = end;
+    }
+}
+
+/// Stable most significant digit first radix sort by byte string keys, one byte per digit. A key
+/// sorts before all keys it is a proper prefix of.
+pub fn msd_radix_sort_by_bytes<T, F: Fn(&T) -> &[u8]>(xs: &mut [T], key: F) {
+    let mut idx: Vec<usize> = (0..xs.len()).collect();
+    let mut buf = vec![0; xs.len()];
+
+    let keys: Vec<&[u8]> = xs.iter().map(key).collect();
+
+    bytes_rec(&keys, &mut idx, &mut buf, 0);
+
+    apply_permutation(xs, &mut idx);
+}
+
+// All keys in `idx` share their first `depth` bytes
+fn bytes_rec(keys: &[&[u8]], idx: &mut [usize], buf: &mut [usize], depth: usize) {
+    if idx.len() <= MSD_CUTOFF {
+        insertion_sort_by_key(idx, |&i| keys[i]);
+        return;
+    }
+
+    // bucket 0 holds the keys that end here
+    let ends = counting_pass(idx, buf, 257, |i| {
+        keys[i].get(depth).map_or(0, |&b| usize::from(b) + 1)
+    });
+
+    idx.copy_from_slice(buf);
+
+    let mut start = ends[0];
+
+    for &end in &ends[1..] {
+        if end - start > 1 {
+            bytes_rec(keys, &mut idx[start..end], &mut buf[start..end], depth + 1);
+        }
+
+        start = end;
+    }
+}
+
+fn check_radix_bits(radix_bits: u32) {
+    assert!(
+        (1..=16).contains(&radix_bits),
+        "radix width should be between 1 and 16 bits"
+    );
+}
+
+// Number of low bits in which the keys can differ
+fn significant_bits(keys: &[u64]) -> u32 {
+    let Some(&first) = keys.first() else {
+        return 0;
+    };
+
+    let diff = keys.iter().fold(0, |acc, k| acc | (k ^ first));
+
+    u64::BITS - diff.leading_zeros()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rand::Wyhash64RNG;
+    use crate::tests::test_stable_sort_records;
+
+    #[test]
+    fn test() {
+        crate::tests::test_sort(radix_sort);
+    }
+
+    #[test]
+    fn lsd() {
+        for bits in [1, 3, 8, 16] {
+            crate::tests::test_sort(|a| lsd_radix_sort_by_key(a, bits, |&x| x));
+            test_stable_sort_records(|a| lsd_radix_sort_by_key(a, bits, |r| r.key));
+        }
+    }
+
+    #[test]
+    fn msd() {
+        for bits in [1, 3, 8, 16] {
+            crate::tests::test_sort(|a| msd_radix_sort_by_key(a, bits, |&x| x));
+            test_stable_sort_records(|a| msd_radix_sort_by_key(a, bits, |r| r.key));
+        }
+    }
+
+    #[test]
+    fn signed() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+        let mut a: Vec<i32> = (0..1000)
+            .map(|_| rng.gen_in_range(0..2000) as i32 - 1000)
+            .collect();
+
+        a.extend([i32::MIN, i32::MAX, 0, -1]);
+
+        let mut expected = a.clone();
+        expected.sort_unstable();
+
+        let mut b = a.clone();
+        lsd_radix_sort_by_key(&mut a, 8, |&x| x);
+        msd_radix_sort_by_key(&mut b, 5, |&x| x);
+
+        assert_eq!(expected, a);
+        assert_eq!(expected, b);
+
+        let mut small: Vec<i8> = vec![3, -128, 127, 0, -1, 1, -3];
+        lsd_radix_sort_by_key(&mut small, 4, |&x| x);
+        assert_eq!(vec![-128, -3, -1, 0, 1, 3, 127], small);
+    }
+
+    #[test]
+    fn floats() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+        let mut a: Vec<f64> = (0..1000)
+            .map(|_| (rng.gen_in_range(0..2_000_000) as f64 - 1e6) / 1e3)
+            .collect();
+
+        a.extend([
+            0.0,
+            -0.0,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::MIN_POSITIVE,
+            -f64::MIN_POSITIVE,
+            f64::MAX,
+            f64::MIN,
+        ]);
+
+        let mut expected = a.clone();
+        expected.sort_by(f64::total_cmp);
+
+        let mut b = a.clone();
+        lsd_radix_sort_by_key(&mut a, 11, |&x| x);
+        msd_radix_sort_by_key(&mut b, 8, |&x| x);
+
+        let bits = |v: &[f64]| v.iter().map(|x| x.to_bits()).collect::<Vec<_>>();
+
+        assert_eq!(bits(&expected), bits(&a));
+        assert_eq!(bits(&expected), bits(&b));
+
+        let mut c: Vec<f32> = vec![1.5, -0.5, f32::NAN, -2.0, 0.0];
+        lsd_radix_sort_by_key(&mut c, 8, |&x| x);
+        assert_eq!(vec![-2.0, -0.5, 0.0, 1.5], c[..4]);
+        assert!(c[4].is_nan());
+    }
+
+    #[test]
+    fn multi_key() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+        let mut a: Vec<(u8, i16, usize)> = (0..1000)
+            .map(|i| {
+                (
+                    rng.gen_in_range(0..4) as u8,
+                    rng.gen_in_range(0..10) as i16 - 5,
+                    i,
+                )
+            })
+            .collect();
+
+        let mut expected = a.clone();
+        expected.sort_unstable();
+
+        // least significant key first, relying on stability
+        lsd_radix_sort_by_key(&mut a, 8, |t| t.1);
+        msd_radix_sort_by_key(&mut a, 8, |t| t.0);
+
+        assert_eq!(expected, a);
+    }
+
+    #[test]
+    fn bytes() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+        let mut words: Vec<(String, usize)> = (0..1000)
+            .map(|i| {
+                let len = rng.gen_in_range(0..6) as usize;
+                let word = (0..len)
+                    .map(|_| char::from(b'a' + rng.gen_in_range(0..3) as u8))
+                    .collect();
+
+                (word, i)
+            })
+            .collect();
+
+        let mut expected = words.clone();
+        expected.sort_by(|x, y| x.0.cmp(&y.0));
+
+        msd_radix_sort_by_bytes(&mut words, |w| w.0.as_bytes());
+
+        assert_eq!(expected, words);
+    }
+
+    #[test]
+    #[should_panic(expected = "radix width")]
+    fn bad_width() {
+        lsd_radix_sort_by_key(&mut [1u32, 2], 0, |&x| x);
+    }
 }
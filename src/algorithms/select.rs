@@ -1,4 +1,11 @@
-use crate::algorithms::insertion_sort::insertion_sort;
+use std::cmp::{Ordering, Reverse};
+
+use crate::algorithms::insertion_sort::{insertion_sort, insertion_sort_impl};
+use crate::algorithms::introsort::{heapsort, introsort_by, log2, partition, sort3};
+use crate::data_structures::priority_queue::PriorityQueue;
+
+// Floyd-Rivest narrows the range with a recursive call on a sample only above this size
+const FLOYD_RIVEST_SAMPLE_THRESHOLD: usize = 600;
 
 pub fn select<T: PartialOrd + Copy>(a: &mut [T], k: usize) -> Option<T> {
     if a.is_empty() {
@@ -36,47 +43,319 @@ fn select_aux<T: PartialOrd + Copy>(a: &mut [T], l: usize, h: usize, k: usize) -
         select_aux(&mut medians, 0, ml - 1, mk).expect("median should be available")
     };
 
-    let p = partition_lomuto(a, l, h, mm);
-    let pl = p - l + 1;
+    let (lt, gt) = partition_three_way(a, l, h, mm);
+    let less = lt - l;
+    let equal = gt - lt;
 
-    if pl == k {
-        return Some(mm);
+    if k <= less {
+        select_aux(a, l, lt - 1, k)
+    } else if k <= less + equal {
+        Some(mm)
+    } else {
+        select_aux(a, gt, h, k - less - equal)
     }
+}
 
-    if pl > k {
-        select_aux(a, l, p - 1, k)
-    } else {
-        select_aux(a, p + 1, h, k - pl)
+// Rearrange `a[l..=h]` into elements less than, equal to and greater than the pivot, returning the
+// bounds of the middle part. Recursing only past the equal elements keeps inputs with many
+// duplicates linear.
+fn partition_three_way<T: PartialOrd + Copy>(
+    a: &mut [T],
+    l: usize,
+    h: usize,
+    pivot: T,
+) -> (usize, usize) {
+    let mut lt = l;
+    let mut i = l;
+    let mut gt = h + 1;
+
+    while i < gt {
+        if a[i] < pivot {
+            a.swap(lt, i);
+            lt += 1;
+            i += 1;
+        } else if a[i] > pivot {
+            gt -= 1;
+            a.swap(i, gt);
+        } else {
+            i += 1;
+        }
     }
+
+    (lt, gt)
+}
+
+/// Rearrange `a` so that `a[k]` (`k` starting from 0) is the element that would be there if `a`
+/// was sorted, with no greater elements before it and no smaller ones after it. Quickselect with
+/// median-of-three pivots, switching to heapsort if the pivots keep being bad.
+pub fn nth_element<T: PartialOrd>(a: &mut [T], k: usize) {
+    nth_element_impl(a, k, &mut T::lt);
+}
+
+pub fn nth_element_by<T, F: FnMut(&T, &T) -> Ordering>(a: &mut [T], k: usize, mut cmp: F) {
+    nth_element_impl(a, k, &mut |x, y| cmp(x, y) == Ordering::Less);
 }
 
-fn partition_lomuto<T: PartialOrd + Copy>(a: &mut [T], l: usize, h: usize, pivot: T) -> usize {
-    for p in l..=h {
-        if a[p] == pivot {
-            a.swap(p, h);
-            break;
+fn nth_element_impl<T, F: FnMut(&T, &T) -> bool>(mut a: &mut [T], mut k: usize, is_less: &mut F) {
+    assert!(k < a.len(), "position should be within the slice");
+
+    let mut depth_limit = 2 * log2(a.len());
+
+    loop {
+        if a.len() <= 16 {
+            insertion_sort_impl(a, is_less);
+            return;
+        }
+
+        if depth_limit == 0 {
+            heapsort(a, is_less);
+            return;
+        }
+
+        depth_limit -= 1;
+
+        let mid = a.len() / 2;
+        let last = a.len() - 1;
+
+        sort3(a, 0, mid, last, is_less);
+        a.swap(0, mid);
+
+        let (p, _) = partition(a, is_less);
+        let tmp = a;
+
+        match k.cmp(&p) {
+            Ordering::Equal => return,
+            Ordering::Less => a = &mut tmp[..p],
+            Ordering::Greater => {
+                a = &mut tmp[p + 1..];
+                k -= p + 1;
+            }
         }
     }
+}
 
-    let mut i = l;
+/// Same contract as `nth_element`, using the Floyd-Rivest algorithm: the pivots are picked by
+/// recursively selecting from a small sample around where the `k`th element is expected, so
+/// that the range shrinks to about `k`'s neighbourhood after few partitions.
+pub fn floyd_rivest_select<T: PartialOrd>(a: &mut [T], k: usize) {
+    assert!(k < a.len(), "position should be within the slice");
+
+    floyd_rivest(a, 0, a.len() - 1, k, &mut T::lt);
+}
 
-    for j in l..h {
-        if a[j] <= a[h] {
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+fn floyd_rivest<T, F: FnMut(&T, &T) -> bool>(
+    a: &mut [T],
+    mut left: usize,
+    mut right: usize,
+    k: usize,
+    is_less: &mut F,
+) {
+    while right > left {
+        if right - left > FLOYD_RIVEST_SAMPLE_THRESHOLD {
+            let n = (right - left + 1) as f64;
+            let i = (k - left + 1) as f64;
+            let z = n.ln();
+            let s = 0.5 * (2.0 * z / 3.0).exp();
+            let sd = 0.5 * (z * s * (n - s) / n).sqrt() * (i - n / 2.0).signum();
+
+            let new_left = (k as f64 - i * s / n + sd).max(left as f64) as usize;
+            let new_right = (k as f64 + (n - i) * s / n + sd).min(right as f64) as usize;
+
+            floyd_rivest(a, new_left, new_right, k, is_less);
+        }
+
+        // the pivot is not copied out, so its position is tracked across swaps
+        let mut p = left;
+        let mut i = left;
+        let mut j = right;
+
+        a.swap(left, k);
+
+        if is_less(&a[p], &a[right]) {
+            a.swap(left, right);
+            p = right;
+        }
+
+        while i < j {
             a.swap(i, j);
+
+            if p == i {
+                p = j;
+            } else if p == j {
+                p = i;
+            }
+
             i += 1;
+            j -= 1;
+
+            while is_less(&a[i], &a[p]) {
+                i += 1;
+            }
+
+            while is_less(&a[p], &a[j]) {
+                j -= 1;
+            }
+        }
+
+        if p == left {
+            a.swap(left, j);
+        } else {
+            j += 1;
+            a.swap(j, right);
+        }
+
+        if j <= k {
+            left = j + 1;
         }
+
+        if k <= j {
+            if j == 0 {
+                break;
+            }
+
+            right = j - 1;
+        }
+    }
+}
+
+/// Index in `a` of the `k`th smallest element (starting from 1, like `select`), leaving `a`
+/// untouched.
+pub fn select_index<T: PartialOrd>(a: &[T], k: usize) -> Option<usize> {
+    if k == 0 || k > a.len() {
+        return None;
+    }
+
+    let mut idx: Vec<usize> = (0..a.len()).collect();
+    let last = idx.len() - 1;
+
+    floyd_rivest(&mut idx, 0, last, k - 1, &mut |&i, &j| a[i] < a[j]);
+
+    Some(idx[k - 1])
+}
+
+/// Sort the `k` smallest elements into `a[..k]`, leaving the rest in unspecified order.
+pub fn partial_sort<T: PartialOrd>(a: &mut [T], k: usize) {
+    partial_sort_by(a, k, |x, y| x.partial_cmp(y).unwrap_or(Ordering::Equal));
+}
+
+pub fn partial_sort_by<T, F: FnMut(&T, &T) -> Ordering>(a: &mut [T], k: usize, mut cmp: F) {
+    let k = k.min(a.len());
+
+    if k == 0 {
+        return;
     }
 
-    a.swap(i, h);
+    if k < a.len() {
+        nth_element_by(a, k - 1, &mut cmp);
+    }
 
-    i
+    introsort_by(&mut a[..k], cmp);
+}
+
+/// Indices of the `k` smallest elements of `a`, ordered by their elements.
+pub fn smallest_k_indices<T: PartialOrd>(a: &[T], k: usize) -> Vec<usize> {
+    let mut idx: Vec<usize> = (0..a.len()).collect();
+
+    partial_sort_by(&mut idx, k, |&i, &j| {
+        a[i].partial_cmp(&a[j]).unwrap_or(Ordering::Equal)
+    });
+
+    idx.truncate(k);
+    idx
+}
+
+/// The `k` largest items of a stream, kept in a heap of at most `k` elements whose root is the
+/// smallest of them. Every item is remembered together with its position in the stream; of equal
+/// items the earliest ones are kept.
+pub struct TopK<T> {
+    k: usize,
+    seen: usize,
+    kept: usize,
+    heap: PriorityQueue<(Reverse<T>, usize)>,
+}
+
+impl<T: Copy + PartialOrd> TopK<T> {
+    pub fn new(k: usize) -> Self {
+        Self {
+            k,
+            seen: 0,
+            kept: 0,
+            heap: PriorityQueue::new(),
+        }
+    }
+
+    pub fn push(&mut self, x: T) {
+        let entry = (Reverse(x), self.seen);
+
+        self.seen += 1;
+
+        if self.kept < self.k {
+            self.heap.insert(entry);
+            self.kept += 1;
+        } else if self.heap.max().is_some_and(|m| x > m.0 .0) {
+            self.heap.change_priority(0, entry);
+        }
+    }
+
+    /// Smallest of the items kept so far, which a new item has to beat once `k` items are kept.
+    pub fn threshold(&self) -> Option<T> {
+        self.heap.max().map(|m| m.0 .0)
+    }
+
+    /// Positions and values of the kept items, largest first.
+    pub fn into_sorted_vec(mut self) -> Vec<(usize, T)> {
+        let mut out = Vec::with_capacity(self.kept);
+
+        while let Some((Reverse(x), i)) = self.heap.extract_max() {
+            out.push((i, x));
+        }
+
+        out.reverse();
+        out
+    }
+}
+
+/// Positions and values of the `k` largest items, largest first.
+pub fn top_k<T: Copy + PartialOrd>(
+    items: impl IntoIterator<Item = T>,
+    k: usize,
+) -> Vec<(usize, T)> {
+    let mut top = TopK::new(k);
+
+    for x in items {
+        top.push(x);
+    }
+
+    top.into_sorted_vec()
 }
 
 #[cfg(test)]
 mod tests {
+    use std::cell::Cell;
+
     use super::*;
     use crate::tests;
 
+    thread_local! {
+        static COMPARISONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    // Element counting how many times it is ordered against another
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Counted(u64);
+
+    impl PartialOrd for Counted {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            COMPARISONS.with(|c| c.set(c.get() + 1));
+            self.0.partial_cmp(&other.0)
+        }
+    }
+
     #[test]
     fn test() {
         let len = 10;
@@ -91,6 +370,27 @@ mod tests {
         }
     }
 
+    #[test]
+    fn many_duplicates_are_linear() {
+        let n = if cfg!(miri) { 100 } else { 2000 };
+        let mut rng = crate::rand::Wyhash64RNG::from_seed(123);
+        let few: Vec<Counted> = (0..n).map(|_| Counted(rng.gen_in_range(0..3))).collect();
+
+        for input in [vec![Counted(7); n], few] {
+            let mut sorted = input.clone();
+            sorted.sort_by_key(|x| x.0);
+
+            for k in [1, n / 2, n] {
+                COMPARISONS.with(|c| c.set(0));
+
+                assert_eq!(Some(sorted[k - 1]), select(&mut input.clone(), k));
+
+                // recursing on all the elements equal to the pivot would take about n^2 / 2
+                assert!(COMPARISONS.with(Cell::get) < 20 * n);
+            }
+        }
+    }
+
     fn single_test(a: &[u64], k: usize) {
         let mut copy = a.to_vec();
         let naive = select_naive(a, k);
@@ -99,6 +399,120 @@ mod tests {
         assert_eq!(naive, check);
     }
 
+    fn check_nth(a: &[u64], k: usize) {
+        let mut sorted = a.to_vec();
+        sorted.sort_unstable();
+
+        assert_eq!(sorted[k], a[k]);
+        assert!(a[..k].iter().all(|x| x <= &a[k]));
+        assert!(a[k + 1..].iter().all(|x| x >= &a[k]));
+    }
+
+    fn large_inputs() -> Vec<Vec<u64>> {
+        let n = if cfg!(miri) { 200 } else { 5000 };
+        let mut rng = crate::rand::Wyhash64RNG::from_seed(123);
+        let mut inputs: Vec<Vec<u64>> = tests::generate_test_arrays(n)
+            .into_iter()
+            .map(|t| t.1)
+            .collect();
+
+        inputs.push((0..n).map(|_| rng.gen_in_range(0..3)).collect());
+
+        inputs
+    }
+
+    #[test]
+    fn nth() {
+        for a in large_inputs() {
+            for k in [0, 1, a.len() / 3, a.len() / 2, a.len().saturating_sub(1)] {
+                if k >= a.len() {
+                    continue;
+                }
+
+                let mut b = a.clone();
+                nth_element(&mut b, k);
+                check_nth(&b, k);
+
+                let mut c = a.clone();
+                floyd_rivest_select(&mut c, k);
+                check_nth(&c, k);
+            }
+        }
+    }
+
+    #[test]
+    fn indices() {
+        for a in large_inputs() {
+            let mut sorted = a.clone();
+            sorted.sort_unstable();
+
+            for k in [1, 2, a.len() / 2, a.len()] {
+                match select_index(&a, k) {
+                    Some(i) => assert_eq!(sorted[k - 1], a[i]),
+                    None => assert!(k == 0 || k > a.len()),
+                }
+            }
+
+            let k = a.len().min(10);
+            let smallest: Vec<u64> = smallest_k_indices(&a, k).iter().map(|&i| a[i]).collect();
+
+            assert_eq!(sorted[..k], smallest);
+        }
+
+        assert_eq!(None, select_index(&[1, 2], 3));
+        assert_eq!(Some(2), select_index(&[5, 9, 1, 7], 1));
+    }
+
+    #[test]
+    fn partial() {
+        for a in large_inputs() {
+            let mut sorted = a.clone();
+            sorted.sort_unstable();
+
+            for k in [0, 1, 5, a.len() / 2, a.len(), a.len() + 1] {
+                let mut b = a.clone();
+                let k = k.min(a.len());
+
+                partial_sort(&mut b, k);
+
+                assert_eq!(sorted[..k], b[..k]);
+            }
+        }
+    }
+
+    #[test]
+    fn streaming_top_k() {
+        let data = [5, 1, 9, 3, 9, 7, 2, 8];
+
+        assert_eq!(vec![(2, 9), (4, 9), (7, 8)], top_k(data, 3));
+        assert_eq!(vec![(2, 9)], top_k(data, 1));
+        assert!(top_k(data, 0).is_empty());
+        assert_eq!(data.len(), top_k(data, 100).len());
+
+        let mut top = TopK::new(2);
+        assert_eq!(None, top.threshold());
+
+        for x in [4, 6, 5] {
+            top.push(x);
+        }
+
+        assert_eq!(Some(5), top.threshold());
+        assert_eq!(vec![(1, 6), (2, 5)], top.into_sorted_vec());
+
+        for a in large_inputs() {
+            let mut sorted = a.clone();
+            sorted.sort_unstable_by(|x, y| y.cmp(x));
+
+            let k = a.len().min(50);
+            let top: Vec<u64> = top_k(a.iter().copied(), k)
+                .into_iter()
+                .map(|t| t.1)
+                .collect();
+
+            assert_eq!(sorted[..k], top);
+        }
+    }
+
     fn select_naive<T: Ord + Copy>(a: &[T], k: usize) -> Option<T> {
         let mut copy = a.to_vec();
         copy.sort_unstable();
@@ -1,4 +1,18 @@
+use std::cmp::Ordering;
+
 pub fn selection_sort<T: PartialOrd>(a: &mut [T]) {
+    selection_sort_impl(a, &mut T::lt);
+}
+
+pub fn selection_sort_by<T, F: FnMut(&T, &T) -> Ordering>(a: &mut [T], mut cmp: F) {
+    selection_sort_impl(a, &mut |x, y| cmp(x, y) == Ordering::Less);
+}
+
+pub fn selection_sort_by_key<T, K: Ord, F: FnMut(&T) -> K>(a: &mut [T], mut f: F) {
+    selection_sort_impl(a, &mut |x, y| f(x) < f(y));
+}
+
+fn selection_sort_impl<T, F: FnMut(&T, &T) -> bool>(a: &mut [T], is_less: &mut F) {
     if a.len() <= 1 {
         return;
     }
@@ -7,7 +21,7 @@ pub fn selection_sort<T: PartialOrd>(a: &mut [T]) {
         let mut smallest = i;
 
         for j in (i + 1)..a.len() {
-            if a[j] < a[smallest] {
+            if is_less(&a[j], &a[smallest]) {
                 smallest = j;
             }
         }
@@ -20,3 +34,13 @@ pub fn selection_sort<T: PartialOrd>(a: &mut [T]) {
 fn test() {
     crate::tests::test_sort(selection_sort);
 }
+
+#[test]
+fn by() {
+    crate::tests::test_sort_records(|a| selection_sort_by(a, |x, y| x.key.cmp(&y.key)));
+}
+
+#[test]
+fn by_key() {
+    crate::tests::test_sort_records(|a| selection_sort_by_key(a, |r| r.key));
+}
@@ -0,0 +1,842 @@
+//! Global (Needleman-Wunsch) and local (Smith-Waterman) alignment of two sequences under a
+//! substitution score and affine gap penalties, with Gotoh's three-table recurrences, and a
+//! linear-space global alignment (Hirschberg's divide and conquer, in Myers and Miller's form for
+//! affine gaps).
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Range;
+
+use crate::data_structures::array_2d::Array2D;
+
+// Low enough to never win, high enough to take a few penalties without overflowing
+const NEG: i64 = i64::MIN / 4;
+
+/// Score for aligning two elements with each other; higher is better.
+pub trait Substitution<T> {
+    fn score(&self, a: &T, b: &T) -> i64;
+}
+
+impl<T, F: Fn(&T, &T) -> i64> Substitution<T> for F {
+    fn score(&self, a: &T, b: &T) -> i64 {
+        self(a, b)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct MatchMismatch {
+    pub matched: i64,
+    pub mismatched: i64,
+}
+
+impl<T: PartialEq> Substitution<T> for MatchMismatch {
+    fn score(&self, a: &T, b: &T) -> i64 {
+        if a == b {
+            self.matched
+        } else {
+            self.mismatched
+        }
+    }
+}
+
+/// Scores for every pair of symbols of an alphabet, like BLOSUM or PAM: `scores[y][x]` is the
+/// score of aligning `alphabet[y]` with `alphabet[x]`.
+pub struct SubstitutionMatrix<T> {
+    index: HashMap<T, usize>,
+    scores: Array2D<i64>,
+}
+
+impl<T: Eq + Hash + Clone> SubstitutionMatrix<T> {
+    pub fn new(alphabet: &[T], scores: Array2D<i64>) -> Self {
+        assert!(
+            scores.width() == alphabet.len() && scores.height() == alphabet.len(),
+            "scores should have a row and a column per symbol"
+        );
+
+        let index = alphabet
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (s.clone(), i))
+            .collect();
+
+        Self { index, scores }
+    }
+}
+
+impl<T: Eq + Hash> Substitution<T> for SubstitutionMatrix<T> {
+    fn score(&self, a: &T, b: &T) -> i64 {
+        let i = self.index.get(a).expect("symbol should be in the alphabet");
+        let j = self.index.get(b).expect("symbol should be in the alphabet");
+
+        self.scores[*i][*j]
+    }
+}
+
+/// A gap of `k` elements costs `open + k * extend`, so `open` is zero for linear gap penalties.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GapPenalty {
+    open: i64,
+    extend: i64,
+}
+
+impl GapPenalty {
+    pub fn linear(extend: i64) -> Self {
+        Self::affine(0, extend)
+    }
+
+    pub fn affine(open: i64, extend: i64) -> Self {
+        assert!(open >= 0 && extend >= 0, "penalties should not be negative");
+
+        Self { open, extend }
+    }
+
+    pub fn cost(&self, k: usize) -> i64 {
+        if k == 0 {
+            0
+        } else {
+            self.open + k as i64 * self.extend
+        }
+    }
+}
+
+pub struct Scoring<S> {
+    pub substitution: S,
+    pub gap: GapPenalty,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlignOp {
+    Match,
+    Substitute,
+    /// An element of the first sequence aligned with a gap.
+    Delete,
+    /// An element of the second sequence aligned with a gap.
+    Insert,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Alignment {
+    pub score: i64,
+    pub ops: Vec<AlignOp>,
+    /// The aligned parts of the two sequences, which are all of them for a global alignment.
+    pub a_range: Range<usize>,
+    pub b_range: Range<usize>,
+}
+
+impl Alignment {
+    /// The two aligned rows, with `-` for gaps.
+    pub fn render<T: Copy + Into<char>>(&self, a: &[T], b: &[T]) -> (String, String) {
+        let mut i = self.a_range.start;
+        let mut j = self.b_range.start;
+        let mut top = String::new();
+        let mut bottom = String::new();
+
+        for op in &self.ops {
+            match op {
+                AlignOp::Match | AlignOp::Substitute => {
+                    top.push(a[i].into());
+                    bottom.push(b[j].into());
+                    i += 1;
+                    j += 1;
+                }
+                AlignOp::Delete => {
+                    top.push(a[i].into());
+                    bottom.push('-');
+                    i += 1;
+                }
+                AlignOp::Insert => {
+                    top.push('-');
+                    bottom.push(b[j].into());
+                    j += 1;
+                }
+            }
+        }
+
+        (top, bottom)
+    }
+}
+
+fn pair_op<T: PartialEq>(a: &T, b: &T) -> AlignOp {
+    if a == b {
+        AlignOp::Match
+    } else {
+        AlignOp::Substitute
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    // a[i - 1] aligned with b[j - 1]
+    Pair,
+    // a[i - 1] aligned with a gap
+    Del,
+    // b[j - 1] aligned with a gap
+    Ins,
+}
+
+// Gotoh's tables: the best score of a prefix alignment ending in each state
+struct Tables {
+    pair: Array2D<i64>,
+    del: Array2D<i64>,
+    ins: Array2D<i64>,
+}
+
+impl Tables {
+    fn fill<T, S: Substitution<T>>(a: &[T], b: &[T], scoring: &Scoring<S>, local: bool) -> Self {
+        let (m, n) = (a.len(), b.len());
+        let open = scoring.gap.open + scoring.gap.extend;
+        let extend = scoring.gap.extend;
+
+        let mut t = Self {
+            pair: Array2D::new(NEG, n + 1, m + 1),
+            del: Array2D::new(NEG, n + 1, m + 1),
+            ins: Array2D::new(NEG, n + 1, m + 1),
+        };
+
+        if !local {
+            t.pair[0][0] = 0;
+        }
+
+        for i in 0..=m {
+            for j in 0..=n {
+                if i > 0 && j > 0 {
+                    let prev = t.best(i - 1, j - 1).max(if local { 0 } else { NEG });
+
+                    t.pair[i][j] = prev + scoring.substitution.score(&a[i - 1], &b[j - 1]);
+                }
+
+                if i > 0 {
+                    t.del[i][j] = (t.pair[i - 1][j] - open)
+                        .max(t.del[i - 1][j] - extend)
+                        .max(t.ins[i - 1][j] - open)
+                        .max(NEG);
+                }
+
+                if j > 0 {
+                    t.ins[i][j] = (t.pair[i][j - 1] - open)
+                        .max(t.ins[i][j - 1] - extend)
+                        .max(t.del[i][j - 1] - open)
+                        .max(NEG);
+                }
+            }
+        }
+
+        t
+    }
+
+    fn best(&self, i: usize, j: usize) -> i64 {
+        self.pair[i][j].max(self.del[i][j]).max(self.ins[i][j])
+    }
+
+    fn get(&self, state: State, i: usize, j: usize) -> i64 {
+        match state {
+            State::Pair => self.pair[i][j],
+            State::Del => self.del[i][j],
+            State::Ins => self.ins[i][j],
+        }
+    }
+
+    // The state at (i, j) from which `value` was reached, preferring pairs
+    fn source(
+        &self,
+        i: usize,
+        j: usize,
+        pair_value: i64,
+        gap_value: i64,
+        extending: State,
+    ) -> State {
+        [State::Pair, State::Del, State::Ins]
+            .into_iter()
+            .find(|&s| {
+                let v = self.get(s, i, j);
+                v > NEG
+                    && if s == extending {
+                        v == gap_value
+                    } else {
+                        v == pair_value
+                    }
+            })
+            .expect("cell should have a source")
+    }
+
+    // Follow the recurrences back from `state` at (i, j). Stops at the origin, or for local
+    // alignments at the pair that started it. Returns where the alignment starts.
+    fn trace_back<T: PartialEq, S: Substitution<T>>(
+        &self,
+        a: &[T],
+        b: &[T],
+        scoring: &Scoring<S>,
+        mut state: State,
+        (mut i, mut j): (usize, usize),
+        ops: &mut Vec<AlignOp>,
+    ) -> (usize, usize) {
+        let open = scoring.gap.open + scoring.gap.extend;
+        let extend = scoring.gap.extend;
+
+        while i > 0 || j > 0 {
+            let value = self.get(state, i, j);
+
+            match state {
+                State::Pair => {
+                    ops.push(pair_op(&a[i - 1], &b[j - 1]));
+
+                    let prev = value - scoring.substitution.score(&a[i - 1], &b[j - 1]);
+
+                    i -= 1;
+                    j -= 1;
+
+                    if prev == 0 && (self.pair[0][0] == NEG || (i == 0 && j == 0)) {
+                        break;
+                    }
+
+                    state = self.source(i, j, prev, prev, State::Pair);
+                }
+                State::Del => {
+                    ops.push(AlignOp::Delete);
+                    i -= 1;
+                    state = self.source(i, j, value + open, value + extend, State::Del);
+                }
+                State::Ins => {
+                    ops.push(AlignOp::Insert);
+                    j -= 1;
+                    state = self.source(i, j, value + open, value + extend, State::Ins);
+                }
+            }
+        }
+
+        ops.reverse();
+
+        (i, j)
+    }
+}
+
+/// Best alignment of all of `a` with all of `b`, in O(mn) time and space.
+pub fn needleman_wunsch<T: PartialEq, S: Substitution<T>>(
+    a: &[T],
+    b: &[T],
+    scoring: &Scoring<S>,
+) -> Alignment {
+    let (m, n) = (a.len(), b.len());
+    let t = Tables::fill(a, b, scoring, false);
+    let score = t.best(m, n);
+    let state = [State::Pair, State::Del, State::Ins]
+        .into_iter()
+        .find(|&s| t.get(s, m, n) == score)
+        .expect("some state should be best");
+
+    let mut ops = vec![];
+
+    t.trace_back(a, b, scoring, state, (m, n), &mut ops);
+
+    Alignment {
+        score,
+        ops,
+        a_range: 0..m,
+        b_range: 0..n,
+    }
+}
+
+/// Best alignment of a part of `a` with a part of `b`. Empty if no pair of elements scores above
+/// zero.
+pub fn smith_waterman<T: PartialEq, S: Substitution<T>>(
+    a: &[T],
+    b: &[T],
+    scoring: &Scoring<S>,
+) -> Alignment {
+    let t = Tables::fill(a, b, scoring, true);
+    let mut end = (0, 0);
+    let mut score = 0;
+
+    // an optimal local alignment never ends with a gap
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            if t.pair[i][j] > score {
+                score = t.pair[i][j];
+                end = (i, j);
+            }
+        }
+    }
+
+    let mut ops = vec![];
+
+    if score == 0 {
+        return Alignment {
+            score,
+            ops,
+            a_range: 0..0,
+            b_range: 0..0,
+        };
+    }
+
+    let start = t.trace_back(a, b, scoring, State::Pair, end, &mut ops);
+
+    Alignment {
+        score,
+        ops,
+        a_range: start.0..end.0,
+        b_range: start.1..end.1,
+    }
+}
+
+/// Same result as `needleman_wunsch`, in O(mn) time but only O(m + n) space: the score of the
+/// middle row is computed from both ends to find where an optimal alignment crosses it, and the
+/// two halves are aligned recursively.
+pub fn hirschberg<T: PartialEq, S: Substitution<T>>(
+    a: &[T],
+    b: &[T],
+    scoring: &Scoring<S>,
+) -> Alignment {
+    let mut mm = MyersMiller {
+        a,
+        b,
+        substitution: &scoring.substitution,
+        g: scoring.gap.open,
+        h: scoring.gap.extend,
+        ops: Vec::with_capacity(a.len() + b.len()),
+    };
+
+    mm.diff(0, a.len(), 0, b.len(), mm.g, mm.g);
+
+    let score = score_ops(a, b, &mm.ops, scoring);
+
+    Alignment {
+        score,
+        ops: mm.ops,
+        a_range: 0..a.len(),
+        b_range: 0..b.len(),
+    }
+}
+
+/// Score of aligning `a` and `b` as described by `ops`.
+pub fn score_ops<T, S: Substitution<T>>(
+    a: &[T],
+    b: &[T],
+    ops: &[AlignOp],
+    scoring: &Scoring<S>,
+) -> i64 {
+    let (mut i, mut j) = (0, 0);
+    let mut score = 0;
+    let mut prev = None;
+
+    for &op in ops {
+        match op {
+            AlignOp::Match | AlignOp::Substitute => {
+                score += scoring.substitution.score(&a[i], &b[j]);
+                i += 1;
+                j += 1;
+            }
+            AlignOp::Delete | AlignOp::Insert => {
+                if prev != Some(op) {
+                    score -= scoring.gap.open;
+                }
+
+                score -= scoring.gap.extend;
+
+                if op == AlignOp::Delete {
+                    i += 1;
+                } else {
+                    j += 1;
+                }
+            }
+        }
+
+        prev = Some(op);
+    }
+
+    score
+}
+
+// Works with costs, the negated scores, as in the paper. `tb` and `te` are the costs of opening
+// a deletion gap at the start and end of a subproblem: zero when the gap continues one that the
+// caller has already paid for.
+struct MyersMiller<'a, T, S> {
+    a: &'a [T],
+    b: &'a [T],
+    substitution: &'a S,
+    g: i64,
+    h: i64,
+    ops: Vec<AlignOp>,
+}
+
+impl<T: PartialEq, S: Substitution<T>> MyersMiller<'_, T, S> {
+    fn w(&self, i: usize, j: usize) -> i64 {
+        -self.substitution.score(&self.a[i], &self.b[j])
+    }
+
+    fn gap(&self, k: usize) -> i64 {
+        if k == 0 {
+            0
+        } else {
+            self.g + k as i64 * self.h
+        }
+    }
+
+    fn push(&mut self, op: AlignOp, k: usize) {
+        self.ops.extend(std::iter::repeat_n(op, k));
+    }
+
+    fn diff(&mut self, a0: usize, m: usize, b0: usize, n: usize, tb: i64, te: i64) {
+        if n == 0 {
+            self.push(AlignOp::Delete, m);
+            return;
+        }
+
+        if m == 0 {
+            self.push(AlignOp::Insert, n);
+            return;
+        }
+
+        if m == 1 {
+            // either a[a0] is deleted next to whichever end is cheaper, or paired with some b[j]
+            let mut best = tb.min(te) + self.h + self.gap(n);
+            let mut best_j = None;
+
+            for j in 0..n {
+                let c = self.gap(j) + self.w(a0, b0 + j) + self.gap(n - j - 1);
+
+                if c < best {
+                    best = c;
+                    best_j = Some(j);
+                }
+            }
+
+            match best_j {
+                Some(j) => {
+                    self.push(AlignOp::Insert, j);
+                    self.ops.push(pair_op(&self.a[a0], &self.b[b0 + j]));
+                    self.push(AlignOp::Insert, n - j - 1);
+                }
+                None if tb <= te => {
+                    self.ops.push(AlignOp::Delete);
+                    self.push(AlignOp::Insert, n);
+                }
+                None => {
+                    self.push(AlignOp::Insert, n);
+                    self.ops.push(AlignOp::Delete);
+                }
+            }
+
+            return;
+        }
+
+        let mid = m / 2;
+        let (cc, dd) = self.forward(a0, mid, b0, n, tb);
+        let (rr, ss) = self.reverse(a0 + mid, m - mid, b0, n, te);
+
+        // crossing the middle either between elements, or inside a deletion gap whose opening
+        // cost both halves have counted
+        let mut best = i64::MAX;
+        let mut best_j = 0;
+        let mut in_gap = false;
+
+        for j in 0..=n {
+            if cc[j] + rr[j] < best {
+                best = cc[j] + rr[j];
+                best_j = j;
+                in_gap = false;
+            }
+
+            if dd[j] + ss[j] - self.g < best {
+                best = dd[j] + ss[j] - self.g;
+                best_j = j;
+                in_gap = true;
+            }
+        }
+
+        if in_gap {
+            self.diff(a0, mid - 1, b0, best_j, tb, 0);
+            self.push(AlignOp::Delete, 2);
+            self.diff(a0 + mid + 1, m - mid - 1, b0 + best_j, n - best_j, 0, te);
+        } else {
+            self.diff(a0, mid, b0, best_j, tb, self.g);
+            self.diff(a0 + mid, m - mid, b0 + best_j, n - best_j, self.g, te);
+        }
+    }
+
+    // `cc[j]`: least cost of aligning a[a0..a0 + rows] with b[b0..b0 + j]; `dd[j]`: the same,
+    // ending with a deletion
+    fn forward(
+        &self,
+        a0: usize,
+        rows: usize,
+        b0: usize,
+        n: usize,
+        tb: i64,
+    ) -> (Vec<i64>, Vec<i64>) {
+        let (g, h) = (self.g, self.h);
+        let mut cc = vec![0; n + 1];
+        let mut dd = vec![0; n + 1];
+        let mut t = g;
+
+        for j in 1..=n {
+            t += h;
+            cc[j] = t;
+            dd[j] = t + g;
+        }
+
+        t = tb;
+
+        for i in 0..rows {
+            let mut s = cc[0];
+
+            t += h;
+
+            let mut c = t;
+            let mut e = t + g;
+
+            cc[0] = c;
+
+            for j in 1..=n {
+                e = e.min(c + g) + h;
+                dd[j] = dd[j].min(cc[j] + g) + h;
+                c = dd[j].min(e).min(s + self.w(a0 + i, b0 + j - 1));
+                s = cc[j];
+                cc[j] = c;
+            }
+        }
+
+        dd[0] = cc[0];
+
+        (cc, dd)
+    }
+
+    // Mirror image of `forward`: `rr[j]` and `ss[j]` are for a[a0..a0 + rows] with b[b0 + j..b0 + n]
+    fn reverse(
+        &self,
+        a0: usize,
+        rows: usize,
+        b0: usize,
+        n: usize,
+        te: i64,
+    ) -> (Vec<i64>, Vec<i64>) {
+        let (g, h) = (self.g, self.h);
+        let mut rr = vec![0; n + 1];
+        let mut ss = vec![0; n + 1];
+        let mut t = g;
+
+        for j in (0..n).rev() {
+            t += h;
+            rr[j] = t;
+            ss[j] = t + g;
+        }
+
+        t = te;
+
+        for i in (0..rows).rev() {
+            let mut s = rr[n];
+
+            t += h;
+
+            let mut c = t;
+            let mut e = t + g;
+
+            rr[n] = c;
+
+            for j in (0..n).rev() {
+                e = e.min(c + g) + h;
+                ss[j] = ss[j].min(rr[j] + g) + h;
+                c = ss[j].min(e).min(s + self.w(a0 + i, b0 + j));
+                s = rr[j];
+                rr[j] = c;
+            }
+        }
+
+        ss[n] = rr[n];
+
+        (rr, ss)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rand::Wyhash64RNG;
+
+    const UNIT: MatchMismatch = MatchMismatch {
+        matched: 1,
+        mismatched: -1,
+    };
+
+    fn dna() -> SubstitutionMatrix<u8> {
+        // transitions (A <-> G, C <-> T) are penalized less than transversions
+        let scores = [
+            [2, -2, -1, -2],
+            [-2, 2, -2, -1],
+            [-1, -2, 2, -2],
+            [-2, -1, -2, 2],
+        ];
+
+        SubstitutionMatrix::new(b"ACGT", Array2D::from_slice(4, 4, &scores.concat()))
+    }
+
+    fn random_dna(rng: &mut Wyhash64RNG, n: usize) -> Vec<u8> {
+        (0..n)
+            .map(|_| b"ACGT"[rng.gen_in_range(0..4) as usize])
+            .collect()
+    }
+
+    fn check_ops<T>(a: &[T], b: &[T], alignment: &Alignment) {
+        let used_a = alignment
+            .ops
+            .iter()
+            .filter(|&&op| op != AlignOp::Insert)
+            .count();
+        let used_b = alignment
+            .ops
+            .iter()
+            .filter(|&&op| op != AlignOp::Delete)
+            .count();
+
+        assert_eq!(alignment.a_range.len(), used_a);
+        assert_eq!(alignment.b_range.len(), used_b);
+        assert!(alignment.a_range.end <= a.len() && alignment.b_range.end <= b.len());
+    }
+
+    #[test]
+    fn global_simple() {
+        let scoring = Scoring {
+            substitution: UNIT,
+            gap: GapPenalty::linear(1),
+        };
+        let alignment = needleman_wunsch(b"GATTACA", b"GCATGCU", &scoring);
+
+        assert_eq!(0, alignment.score);
+        assert_eq!(
+            0,
+            score_ops(b"GATTACA", b"GCATGCU", &alignment.ops, &scoring)
+        );
+
+        let (top, bottom) = alignment.render(b"GATTACA", b"GCATGCU");
+
+        assert_eq!(top.replace('-', ""), "GATTACA");
+        assert_eq!(bottom.replace('-', ""), "GCATGCU");
+        assert_eq!(top.len(), bottom.len());
+    }
+
+    #[test]
+    fn affine_gaps() {
+        let scoring = Scoring {
+            substitution: UNIT,
+            gap: GapPenalty::affine(5, 1),
+        };
+
+        // a single long gap beats several short ones
+        let alignment = needleman_wunsch(b"ACGTTTTACG", b"ACGACG", &scoring);
+        let (top, bottom) = alignment.render(b"ACGTTTTACG", b"ACGACG");
+
+        assert_eq!("ACGTTTTACG", top);
+        assert_eq!("ACG----ACG", bottom);
+        assert_eq!(6 - 5 - 4, alignment.score);
+        assert_eq!(alignment, hirschberg(b"ACGTTTTACG", b"ACGACG", &scoring));
+    }
+
+    #[test]
+    fn local_simple() {
+        let scoring = Scoring {
+            substitution: MatchMismatch {
+                matched: 3,
+                mismatched: -3,
+            },
+            gap: GapPenalty::linear(2),
+        };
+        let a = b"TGTTACGG";
+        let b = b"GGTTGACTA";
+        let alignment = smith_waterman(a, b, &scoring);
+
+        assert_eq!(13, alignment.score);
+        assert_eq!(1..6, alignment.a_range);
+        assert_eq!(1..7, alignment.b_range);
+        assert_eq!(
+            ("GTT-AC".to_string(), "GTTGAC".to_string()),
+            alignment.render(a, b)
+        );
+
+        let none = smith_waterman(b"AAA", b"TTT", &scoring);
+
+        assert_eq!(0, none.score);
+        assert!(none.ops.is_empty());
+    }
+
+    #[test]
+    fn generic_elements() {
+        let a = ["the", "quick", "brown", "fox"];
+        let b = ["the", "brown", "dog"];
+        let scoring = Scoring {
+            substitution: |x: &&str, y: &&str| if x == y { 2 } else { -1 },
+            gap: GapPenalty::linear(1),
+        };
+        let alignment = needleman_wunsch(&a, &b, &scoring);
+
+        assert_eq!(
+            vec![
+                AlignOp::Match,
+                AlignOp::Delete,
+                AlignOp::Match,
+                AlignOp::Substitute
+            ],
+            alignment.ops
+        );
+        assert_eq!(2, alignment.score);
+
+        // multi-byte characters are single elements
+        let a: Vec<char> = "español".chars().collect();
+        let b: Vec<char> = "espanol".chars().collect();
+        let scoring = Scoring {
+            substitution: UNIT,
+            gap: GapPenalty::linear(1),
+        };
+        let alignment = needleman_wunsch(&a, &b, &scoring);
+
+        assert_eq!(5, alignment.score);
+        assert_eq!(
+            ("español".into(), "espanol".into()),
+            alignment.render(&a, &b)
+        );
+    }
+
+    #[test]
+    fn random_against_each_other() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+        let sizes: &[usize] = if cfg!(miri) {
+            &[0, 1, 5]
+        } else {
+            &[0, 1, 2, 7, 30]
+        };
+        let gaps = [
+            GapPenalty::linear(2),
+            GapPenalty::affine(3, 1),
+            GapPenalty::affine(10, 0),
+        ];
+
+        for &m in sizes {
+            for &n in sizes {
+                for gap in gaps {
+                    let a = random_dna(&mut rng, m);
+                    let b = random_dna(&mut rng, n + m / 2);
+                    let scoring = Scoring {
+                        substitution: dna(),
+                        gap,
+                    };
+
+                    let global = needleman_wunsch(&a, &b, &scoring);
+                    let linear = hirschberg(&a, &b, &scoring);
+                    let local = smith_waterman(&a, &b, &scoring);
+
+                    assert_eq!(global.score, linear.score, "{m} {n} {gap:?}");
+                    assert_eq!(global.score, score_ops(&a, &b, &global.ops, &scoring));
+                    assert!(local.score >= global.score.max(0));
+
+                    let sub_a = &a[local.a_range.clone()];
+                    let sub_b = &b[local.b_range.clone()];
+
+                    assert_eq!(local.score, score_ops(sub_a, sub_b, &local.ops, &scoring));
+                    assert_eq!(local.score, needleman_wunsch(sub_a, sub_b, &scoring).score);
+
+                    check_ops(&a, &b, &global);
+                    check_ops(&a, &b, &linear);
+                    check_ops(&a, &b, &local);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,429 @@
+//! Sorting networks: fixed sequences of compare-exchange operations that sort any input of a
+//! given size, independently of the data. Networks for `[T; N]` are built at compile time; bitonic
+//! sort and Batcher's odd-even merge sort sort slices whose length is a power of two.
+
+// Enough for the merge-exchange network on 32 inputs
+const MAX_COMPARATORS: usize = 191;
+
+pub const MAX_NETWORK_SIZE: usize = 32;
+
+// Smallest known networks, from Knuth, TAOCP vol. 3, 5.3.4, and Codish, Cruz-Filipe, Ehlers,
+// Müller and Schneider-Kamp, "Sorting networks: to the end and back again" (2019). Those up to
+// 12 inputs are proven optimal. The one for 15 inputs is Green's 16-input network with a channel
+// pruned. One layer of comparators per line.
+#[rustfmt::skip]
+const OPTIMAL: [&[(usize, usize)]; 17] = [
+    &[],
+    &[],
+    &[(0, 1)],
+    &[
+        (0, 2),
+        (0, 1),
+        (1, 2),
+    ],
+    &[
+        (0, 1), (2, 3),
+        (0, 2), (1, 3),
+        (1, 2),
+    ],
+    &[
+        (0, 1), (3, 4),
+        (2, 4),
+        (2, 3),
+        (0, 3),
+        (0, 2), (1, 4),
+        (1, 3),
+        (1, 2),
+    ],
+    &[
+        (1, 2), (4, 5),
+        (0, 2), (3, 5),
+        (0, 1), (3, 4),
+        (1, 4), (0, 3), (2, 5),
+        (1, 3), (2, 4),
+        (2, 3),
+    ],
+    &[
+        (1, 2), (3, 4), (5, 6),
+        (0, 2), (3, 5), (4, 6),
+        (0, 1), (4, 5), (2, 6),
+        (0, 4), (1, 5),
+        (0, 3), (2, 5),
+        (1, 3), (2, 4),
+        (2, 3),
+    ],
+    &[
+        (0, 4), (1, 5), (2, 6), (3, 7),
+        (0, 2), (1, 3), (4, 6), (5, 7),
+        (2, 4), (3, 5), (0, 1),
+        (2, 3), (4, 5), (6, 7),
+        (1, 4), (3, 6),
+        (1, 2), (3, 4), (5, 6),
+    ],
+    &[
+        (0, 3), (1, 7), (2, 5), (4, 8),
+        (0, 7), (2, 4), (3, 8), (5, 6),
+        (0, 2), (1, 3), (4, 5), (7, 8),
+        (1, 4), (3, 6), (5, 7),
+        (0, 1), (2, 4), (3, 5), (6, 8),
+        (2, 3), (4, 5), (6, 7),
+        (1, 2), (3, 4), (5, 6),
+    ],
+    &[
+        (0, 8), (1, 9), (2, 7), (3, 5), (4, 6),
+        (0, 2), (1, 4), (5, 8), (7, 9),
+        (0, 3), (2, 4), (5, 7), (6, 9),
+        (0, 1), (3, 6), (8, 9),
+        (1, 5), (2, 3), (4, 8), (6, 7),
+        (1, 2), (3, 5), (4, 6), (7, 8),
+        (2, 3), (4, 5), (6, 7),
+        (3, 4), (5, 6),
+    ],
+    &[
+        (0, 9), (1, 6), (2, 4), (3, 7), (5, 8),
+        (0, 1), (3, 5), (4, 10), (6, 9), (7, 8),
+        (1, 3), (2, 5), (4, 7), (8, 10),
+        (0, 4), (1, 2), (3, 7), (5, 9), (6, 8),
+        (0, 1), (2, 6), (4, 5), (7, 8), (9, 10),
+        (2, 4), (3, 6), (5, 7), (8, 9),
+        (1, 2), (3, 4), (5, 6), (7, 8),
+        (2, 3), (4, 5), (6, 7),
+    ],
+    &[
+        (0, 8), (1, 7), (2, 6), (3, 11), (4, 10), (5, 9),
+        (0, 1), (2, 5), (3, 4), (6, 9), (7, 8), (10, 11),
+        (0, 2), (1, 6), (5, 10), (9, 11),
+        (0, 3), (1, 2), (4, 6), (5, 7), (8, 11), (9, 10),
+        (1, 4), (3, 5), (6, 8), (7, 10),
+        (1, 3), (2, 5), (6, 9), (8, 10),
+        (2, 3), (4, 5), (6, 7), (8, 9),
+        (4, 6), (5, 7),
+        (3, 4), (5, 6), (7, 8),
+    ],
+    &[
+        (0, 12), (1, 10), (2, 9), (3, 7), (5, 11), (6, 8),
+        (1, 6), (2, 3), (4, 11), (7, 9), (8, 10),
+        (0, 4), (1, 2), (3, 6), (7, 8), (9, 10), (11, 12),
+        (4, 6), (5, 9), (8, 11), (10, 12),
+        (0, 5), (3, 8), (4, 7), (6, 11), (9, 10),
+        (0, 1), (2, 5), (6, 9), (7, 8), (10, 11),
+        (1, 3), (2, 4), (5, 6), (9, 10),
+        (1, 2), (3, 4), (5, 7), (6, 8),
+        (2, 3), (4, 5), (6, 7), (8, 9),
+        (3, 4), (5, 6),
+    ],
+    &[
+        (0, 1), (2, 3), (4, 5), (6, 7), (8, 9), (10, 11), (12, 13),
+        (0, 2), (1, 3), (4, 8), (5, 9), (10, 12), (11, 13),
+        (0, 4), (1, 2), (3, 7), (5, 8), (6, 10), (9, 13), (11, 12),
+        (0, 6), (1, 5), (3, 9), (4, 10), (7, 13), (8, 12),
+        (2, 10), (3, 11), (4, 6), (7, 9),
+        (1, 3), (2, 8), (5, 11), (6, 7), (10, 12),
+        (1, 4), (2, 6), (3, 5), (7, 11), (8, 10), (9, 12),
+        (2, 4), (3, 6), (5, 8), (7, 10), (9, 11),
+        (3, 4), (5, 6), (7, 8), (9, 10),
+        (6, 7),
+    ],
+    &[
+        (0, 11), (1, 14), (2, 13), (3, 7), (4, 5), (6, 10), (8, 9),
+        (4, 12), (0, 6), (1, 8), (2, 3), (7, 13), (9, 14), (10, 11),
+        (0, 4), (1, 2), (3, 12), (5, 7), (6, 8), (9, 10), (13, 14),
+        (0, 1), (2, 4), (3, 9), (10, 12), (5, 6), (7, 8), (11, 13),
+        (1, 2), (4, 11), (3, 5), (6, 10), (7, 9), (8, 12), (13, 14),
+        (1, 3), (2, 5), (6, 7), (9, 10), (8, 13), (12, 14),
+        (2, 3), (4, 5), (8, 11), (12, 13),
+        (4, 6), (5, 7), (8, 9), (10, 11),
+        (3, 4), (5, 6), (7, 8), (9, 10), (11, 12),
+        (6, 7), (8, 9),
+    ],
+    &[
+        (0, 13), (1, 12), (2, 15), (3, 14), (4, 8), (5, 6), (7, 11), (9, 10),
+        (0, 5), (1, 7), (2, 9), (3, 4), (6, 13), (8, 14), (10, 15), (11, 12),
+        (0, 1), (2, 3), (4, 5), (6, 8), (7, 9), (10, 11), (12, 13), (14, 15),
+        (0, 2), (1, 3), (4, 10), (5, 11), (6, 7), (8, 9), (12, 14), (13, 15),
+        (1, 2), (3, 12), (4, 6), (5, 7), (8, 10), (9, 11), (13, 14),
+        (1, 4), (2, 6), (5, 8), (7, 10), (9, 13), (11, 14),
+        (2, 4), (3, 6), (9, 12), (11, 13),
+        (3, 5), (6, 8), (7, 9), (10, 12),
+        (3, 4), (5, 6), (7, 8), (9, 10), (11, 12),
+        (6, 7), (8, 9),
+    ],
+];
+
+/// Compare-exchange operations of a sorting network, in the order they are applied.
+pub struct Network {
+    pairs: [(usize, usize); MAX_COMPARATORS],
+    len: usize,
+}
+
+impl Network {
+    /// The smallest known network for up to 16 inputs, Batcher's merge-exchange network above that.
+    pub const fn new(n: usize) -> Self {
+        assert!(n <= MAX_NETWORK_SIZE, "network is too large");
+
+        let mut net = Self {
+            pairs: [(0, 0); MAX_COMPARATORS],
+            len: 0,
+        };
+
+        if n < OPTIMAL.len() {
+            let optimal = OPTIMAL[n];
+            let mut i = 0;
+
+            while i < optimal.len() {
+                net.push(optimal[i]);
+                i += 1;
+            }
+        } else {
+            net.merge_exchange(n);
+        }
+
+        net
+    }
+
+    const fn push(&mut self, pair: (usize, usize)) {
+        self.pairs[self.len] = pair;
+        self.len += 1;
+    }
+
+    // Knuth's Algorithm 5.2.2M, Batcher's odd-even merge generalised to any n
+    const fn merge_exchange(&mut self, n: usize) {
+        let t = usize::BITS - (n - 1).leading_zeros();
+        let mut p = 1 << (t - 1);
+
+        while p > 0 {
+            let mut q = 1 << (t - 1);
+            let mut r = 0;
+            let mut d = p;
+
+            loop {
+                let mut i = 0;
+
+                while i + d < n {
+                    if i & p == r {
+                        self.push((i, i + d));
+                    }
+
+                    i += 1;
+                }
+
+                if q == p {
+                    break;
+                }
+
+                d = q - p;
+                q /= 2;
+                r = p;
+            }
+
+            p /= 2;
+        }
+    }
+
+    pub const fn comparators(&self) -> &[(usize, usize)] {
+        self.pairs.split_at(self.len).0
+    }
+}
+
+/// Sorting network for arrays of `N` elements, built at compile time.
+pub struct SortingNetwork<const N: usize>;
+
+impl<const N: usize> SortingNetwork<N> {
+    pub const NETWORK: Network = Network::new(N);
+
+    pub fn sort<T: PartialOrd>(a: &mut [T; N]) {
+        for &(i, j) in Self::NETWORK.comparators() {
+            if a[j] < a[i] {
+                a.swap(i, j);
+            }
+        }
+    }
+}
+
+pub fn network_sort<T: PartialOrd, const N: usize>(a: &mut [T; N]) {
+    SortingNetwork::<N>::sort(a);
+}
+
+/// Sort a slice whose length is a power of two with Batcher's bitonic sorter: sorted runs are
+/// doubled by merging pairs of them sorted in opposite directions, which form bitonic sequences.
+pub fn bitonic_sort<T: PartialOrd>(a: &mut [T]) {
+    let n = a.len();
+
+    assert!(
+        n.is_power_of_two() || n == 0,
+        "length should be a power of two"
+    );
+
+    let mut k = 2;
+
+    while k <= n {
+        let mut j = k / 2;
+
+        while j > 0 {
+            for i in 0..n {
+                let l = i ^ j;
+
+                if l <= i {
+                    continue;
+                }
+
+                // blocks of length k alternate between ascending and descending order
+                let out_of_order = if i & k == 0 { a[l] < a[i] } else { a[i] < a[l] };
+
+                if out_of_order {
+                    a.swap(i, l);
+                }
+            }
+
+            j /= 2;
+        }
+
+        k *= 2;
+    }
+}
+
+/// Sort a slice whose length is a power of two with Batcher's odd-even merge sort.
+pub fn odd_even_merge_sort<T: PartialOrd>(a: &mut [T]) {
+    let n = a.len();
+
+    assert!(
+        n.is_power_of_two() || n == 0,
+        "length should be a power of two"
+    );
+
+    let mut p = 1;
+
+    while p < n {
+        let mut k = p;
+
+        while k >= 1 {
+            let mut j = k % p;
+
+            while j + k < n {
+                for i in 0..k.min(n - j - k) {
+                    // only compare elements within the same pair of merged blocks
+                    if (i + j) / (2 * p) == (i + j + k) / (2 * p) && a[i + j + k] < a[i + j] {
+                        a.swap(i + j, i + j + k);
+                    }
+                }
+
+                j += 2 * k;
+            }
+
+            k /= 2;
+        }
+
+        p *= 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::insertion_sort::insertion_sort;
+    use crate::rand::Wyhash64RNG;
+
+    // By the 0-1 principle, a network sorts everything if it sorts all sequences of zeros and ones
+    fn sorts_all_binary_inputs<const N: usize>() {
+        for bits in 0..1_u32 << N {
+            let mut a: [u8; N] = std::array::from_fn(|i| (bits >> i & 1) as u8);
+
+            network_sort(&mut a);
+
+            assert!(a.windows(2).all(|w| w[0] <= w[1]), "{N}: {bits:b}");
+        }
+    }
+
+    fn same_as_insertion_sort<const N: usize>(rng: &mut Wyhash64RNG) {
+        for _ in 0..100 {
+            let mut a: [u64; N] = std::array::from_fn(|_| rng.gen_in_range(0..10));
+            let mut expected = a;
+
+            insertion_sort(&mut expected);
+            network_sort(&mut a);
+
+            assert_eq!(expected, a);
+        }
+    }
+
+    #[test]
+    fn optimal_sizes() {
+        let sizes = [0, 0, 1, 3, 5, 9, 12, 16, 19, 25, 29, 35, 39, 45, 51, 56, 60];
+
+        for (n, &size) in sizes.iter().enumerate() {
+            assert_eq!(size, Network::new(n).comparators().len());
+        }
+
+        assert_eq!(
+            MAX_COMPARATORS,
+            Network::new(MAX_NETWORK_SIZE).comparators().len()
+        );
+    }
+
+    #[test]
+    fn zero_one_principle() {
+        sorts_all_binary_inputs::<0>();
+        sorts_all_binary_inputs::<1>();
+        sorts_all_binary_inputs::<2>();
+        sorts_all_binary_inputs::<3>();
+        sorts_all_binary_inputs::<4>();
+        sorts_all_binary_inputs::<5>();
+        sorts_all_binary_inputs::<6>();
+        sorts_all_binary_inputs::<7>();
+        sorts_all_binary_inputs::<8>();
+        sorts_all_binary_inputs::<9>();
+        sorts_all_binary_inputs::<10>();
+        sorts_all_binary_inputs::<11>();
+        sorts_all_binary_inputs::<12>();
+
+        if !cfg!(miri) {
+            sorts_all_binary_inputs::<13>();
+            sorts_all_binary_inputs::<14>();
+            sorts_all_binary_inputs::<15>();
+            sorts_all_binary_inputs::<16>();
+        }
+    }
+
+    #[test]
+    fn networks() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+
+        same_as_insertion_sort::<4>(&mut rng);
+        same_as_insertion_sort::<7>(&mut rng);
+        same_as_insertion_sort::<8>(&mut rng);
+        same_as_insertion_sort::<15>(&mut rng);
+        same_as_insertion_sort::<16>(&mut rng);
+        same_as_insertion_sort::<23>(&mut rng);
+        same_as_insertion_sort::<32>(&mut rng);
+
+        let mut words = ["pear", "fig", "apple"].map(String::from);
+        network_sort(&mut words);
+        assert_eq!(["apple", "fig", "pear"], words);
+    }
+
+    #[test]
+    fn power_of_two_sorts() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+
+        for n in [0, 1, 2, 4, 8, 64, 1024] {
+            let a: Vec<u64> = (0..n).map(|_| rng.gen_in_range(0..50)).collect();
+            let mut expected = a.clone();
+
+            insertion_sort(&mut expected);
+
+            let mut b = a.clone();
+            bitonic_sort(&mut b);
+            assert_eq!(expected, b);
+
+            let mut c = a;
+            odd_even_merge_sort(&mut c);
+            assert_eq!(expected, c);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "power of two")]
+    fn not_power_of_two() {
+        bitonic_sort(&mut [3, 2, 1]);
+    }
+}
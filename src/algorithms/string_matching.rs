@@ -0,0 +1,558 @@
+//! String matching (CLRS chapter 32 and friends). Every matcher returns the offsets of all,
+//! possibly overlapping, occurrences of the pattern in ascending order. An empty pattern occurs
+//! at every offset from 0 to `text.len()`.
+
+use std::collections::VecDeque;
+
+use crate::data_structures::array_2d::Array2D;
+
+const ALPHABET: usize = 256;
+
+pub fn naive_matcher(text: &[u8], pattern: &[u8]) -> Vec<usize> {
+    let n = text.len();
+    let m = pattern.len();
+
+    if m > n {
+        return vec![];
+    }
+
+    (0..=n - m)
+        .filter(|&s| &text[s..s + m] == pattern)
+        .collect()
+}
+
+pub fn rabin_karp_matcher(text: &[u8], pattern: &[u8]) -> Vec<usize> {
+    const D: u64 = ALPHABET as u64;
+    const Q: u64 = 1_000_000_007;
+
+    let n = text.len();
+    let m = pattern.len();
+    let mut out = vec![];
+
+    if m > n {
+        return out;
+    }
+
+    if m == 0 {
+        return (0..=n).collect();
+    }
+
+    // D^(m-1) mod Q, the weight of the high-order digit
+    let mut h = 1;
+
+    for _ in 1..m {
+        h = h * D % Q;
+    }
+
+    let mut p = 0;
+    let mut t = 0;
+
+    for i in 0..m {
+        p = (D * p + u64::from(pattern[i])) % Q;
+        t = (D * t + u64::from(text[i])) % Q;
+    }
+
+    for s in 0..=n - m {
+        if p == t && &text[s..s + m] == pattern {
+            out.push(s);
+        }
+
+        if s < n - m {
+            let high = u64::from(text[s]) * h % Q;
+
+            t = (D * (t + Q - high) + u64::from(text[s + m])) % Q;
+        }
+    }
+
+    out
+}
+
+/// Transition function of the string-matching automaton for `pattern`: row `q` holds the next
+/// state for each input byte when `q` characters of the pattern have been matched.
+pub fn compute_transition_function(pattern: &[u8]) -> Array2D<usize> {
+    let m = pattern.len();
+    let pi = compute_prefix_function(pattern);
+    let mut delta = Array2D::new(0, ALPHABET, m + 1);
+
+    for q in 0..=m {
+        for a in 0..ALPHABET {
+            delta[q][a] = if q < m && usize::from(pattern[q]) == a {
+                q + 1
+            } else if q == 0 {
+                0
+            } else {
+                // on a mismatch, fall back to the longest proper border of the matched part
+                delta[pi[q - 1]][a]
+            };
+        }
+    }
+
+    delta
+}
+
+pub fn finite_automaton_matcher(text: &[u8], pattern: &[u8]) -> Vec<usize> {
+    let m = pattern.len();
+    let delta = compute_transition_function(pattern);
+    let mut out = vec![];
+    let mut q = 0;
+
+    if m == 0 {
+        return (0..=text.len()).collect();
+    }
+
+    for (i, &c) in text.iter().enumerate() {
+        q = delta[q][usize::from(c)];
+
+        if q == m {
+            out.push(i + 1 - m);
+        }
+    }
+
+    out
+}
+
+/// `pi[q]` is the length of the longest proper prefix of `pattern[..=q]` that is also its suffix.
+pub fn compute_prefix_function<T: PartialEq>(pattern: &[T]) -> Vec<usize> {
+    let m = pattern.len();
+    let mut pi = vec![0; m];
+    let mut k = 0;
+
+    for q in 1..m {
+        while k > 0 && pattern[k] != pattern[q] {
+            k = pi[k - 1];
+        }
+
+        if pattern[k] == pattern[q] {
+            k += 1;
+        }
+
+        pi[q] = k;
+    }
+
+    pi
+}
+
+pub fn kmp_matcher(text: &[u8], pattern: &[u8]) -> Vec<usize> {
+    let m = pattern.len();
+    let pi = compute_prefix_function(pattern);
+    let mut out = vec![];
+    let mut q = 0;
+
+    if m == 0 {
+        return (0..=text.len()).collect();
+    }
+
+    for (i, c) in text.iter().enumerate() {
+        while q > 0 && pattern[q] != *c {
+            q = pi[q - 1];
+        }
+
+        if pattern[q] == *c {
+            q += 1;
+        }
+
+        if q == m {
+            out.push(i + 1 - m);
+            q = pi[q - 1];
+        }
+    }
+
+    out
+}
+
+pub fn boyer_moore_horspool_matcher(text: &[u8], pattern: &[u8]) -> Vec<usize> {
+    let n = text.len();
+    let m = pattern.len();
+    let mut out = vec![];
+
+    if m == 0 {
+        return (0..=n).collect();
+    }
+
+    let mut shift = [m; ALPHABET];
+
+    for (i, &c) in pattern[..m - 1].iter().enumerate() {
+        shift[usize::from(c)] = m - 1 - i;
+    }
+
+    let mut s = 0;
+
+    while s + m <= n {
+        if &text[s..s + m] == pattern {
+            out.push(s);
+        }
+
+        s += shift[usize::from(text[s + m - 1])];
+    }
+
+    out
+}
+
+pub fn boyer_moore_matcher(text: &[u8], pattern: &[u8]) -> Vec<usize> {
+    let n = text.len();
+    let m = pattern.len();
+    let mut out = vec![];
+
+    if m == 0 {
+        return (0..=n).collect();
+    }
+
+    let mut last = [None; ALPHABET];
+
+    for (i, &c) in pattern.iter().enumerate() {
+        last[usize::from(c)] = Some(i);
+    }
+
+    let good_suffix = compute_good_suffix(pattern);
+    let mut s = 0;
+
+    while s + m <= n {
+        // number of characters not yet matched, scanning right to left
+        let mut j = m;
+
+        while j > 0 && pattern[j - 1] == text[s + j - 1] {
+            j -= 1;
+        }
+
+        if j == 0 {
+            out.push(s);
+            s += good_suffix[0];
+        } else {
+            let bad_char = match last[usize::from(text[s + j - 1])] {
+                Some(l) if l < j - 1 => j - 1 - l,
+                Some(_) => 1,
+                None => j,
+            };
+
+            s += usize::max(good_suffix[j], bad_char);
+        }
+    }
+
+    out
+}
+
+// `shift[j]` is the safe shift when `pattern[j..]` matched and `pattern[j - 1]` did not
+fn compute_good_suffix(pattern: &[u8]) -> Vec<usize> {
+    let m = pattern.len();
+    let mut shift = vec![0; m + 1];
+    let mut border = vec![0; m + 1];
+
+    let mut i = m;
+    let mut j = m + 1;
+
+    border[i] = j;
+
+    while i > 0 {
+        while j <= m && pattern[i - 1] != pattern[j - 1] {
+            if shift[j] == 0 {
+                shift[j] = j - i;
+            }
+
+            j = border[j];
+        }
+
+        i -= 1;
+        j -= 1;
+        border[i] = j;
+    }
+
+    j = border[0];
+
+    for (i, s) in shift.iter_mut().enumerate() {
+        if *s == 0 {
+            *s = j;
+        }
+
+        if i == j {
+            j = border[j];
+        }
+    }
+
+    shift
+}
+
+/// `z[i]` is the length of the longest common prefix of `s` and `s[i..]`, with `z[0] = s.len()`.
+pub fn z_array<T: PartialEq>(s: &[T]) -> Vec<usize> {
+    let n = s.len();
+    let mut z = vec![0; n];
+    let mut l = 0;
+    let mut r = 0;
+
+    if n == 0 {
+        return z;
+    }
+
+    z[0] = n;
+
+    for i in 1..n {
+        if i < r {
+            z[i] = usize::min(r - i, z[i - l]);
+        }
+
+        while i + z[i] < n && s[z[i]] == s[i + z[i]] {
+            z[i] += 1;
+        }
+
+        if i + z[i] > r {
+            l = i;
+            r = i + z[i];
+        }
+    }
+
+    z
+}
+
+pub fn z_matcher(text: &[u8], pattern: &[u8]) -> Vec<usize> {
+    let n = text.len();
+    let m = pattern.len();
+
+    if m > n {
+        return vec![];
+    }
+
+    // `None` acts as a separator that matches nothing
+    let joined: Vec<Option<u8>> = pattern
+        .iter()
+        .copied()
+        .map(Some)
+        .chain([None])
+        .chain(text.iter().copied().map(Some))
+        .collect();
+
+    let z = z_array(&joined);
+
+    (0..=n - m)
+        .filter(|&s| m == 0 || z[m + 1 + s] >= m)
+        .collect()
+}
+
+/// Aho-Corasick automaton for finding occurrences of several patterns in a single pass.
+pub struct AhoCorasick {
+    lengths: Vec<usize>,
+    delta: Array2D<usize>,
+    // patterns ending at each state, including those reached through failure links
+    outputs: Vec<Vec<usize>>,
+    // empty patterns, which are kept out of the trie and occur at every offset
+    empty: Vec<usize>,
+}
+
+impl AhoCorasick {
+    pub fn new(patterns: &[&[u8]]) -> Self {
+        const NONE: usize = usize::MAX;
+
+        let mut goto = vec![[NONE; ALPHABET]];
+        let mut outputs = vec![vec![]];
+        let mut empty = vec![];
+
+        for (p, pattern) in patterns.iter().enumerate() {
+            // at the root, an empty pattern would only be inherited by some states
+            if pattern.is_empty() {
+                empty.push(p);
+                continue;
+            }
+
+            let mut q = 0;
+
+            for &c in *pattern {
+                let c = usize::from(c);
+
+                if goto[q][c] == NONE {
+                    goto[q][c] = goto.len();
+                    goto.push([NONE; ALPHABET]);
+                    outputs.push(vec![]);
+                }
+
+                q = goto[q][c];
+            }
+
+            outputs[q].push(p);
+        }
+
+        let states = goto.len();
+        let mut delta = Array2D::new(0, ALPHABET, states);
+        let mut fail = vec![0; states];
+        let mut queue = VecDeque::new();
+
+        for c in 0..ALPHABET {
+            let q = goto[0][c];
+
+            if q != NONE {
+                delta[0][c] = q;
+                queue.push_back(q);
+            }
+        }
+
+        // breadth-first, so that failure targets are complete before they are used
+        while let Some(r) = queue.pop_front() {
+            let inherited = outputs[fail[r]].clone();
+            outputs[r].extend(inherited);
+
+            for c in 0..ALPHABET {
+                let q = goto[r][c];
+
+                if q == NONE {
+                    delta[r][c] = delta[fail[r]][c];
+                } else {
+                    fail[q] = delta[fail[r]][c];
+                    delta[r][c] = q;
+                    queue.push_back(q);
+                }
+            }
+        }
+
+        Self {
+            lengths: patterns.iter().map(|p| p.len()).collect(),
+            delta,
+            outputs,
+            empty,
+        }
+    }
+
+    /// All occurrences as `(pattern index, offset)` pairs, ordered by the position at which each
+    /// occurrence ends, longest first among those ending together.
+    pub fn find_all(&self, text: &[u8]) -> Vec<(usize, usize)> {
+        let mut out: Vec<(usize, usize)> = self.empty.iter().map(|&p| (p, 0)).collect();
+        let mut q = 0;
+
+        for (i, &c) in text.iter().enumerate() {
+            q = self.delta[q][usize::from(c)];
+
+            for &p in &self.outputs[q] {
+                out.push((p, i + 1 - self.lengths[p]));
+            }
+
+            out.extend(self.empty.iter().map(|&p| (p, i + 1)));
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rand::Wyhash64RNG;
+
+    type Matcher = fn(&[u8], &[u8]) -> Vec<usize>;
+
+    const MATCHERS: [(&str, Matcher); 7] = [
+        ("naive", naive_matcher),
+        ("rabin-karp", rabin_karp_matcher),
+        ("automaton", finite_automaton_matcher),
+        ("kmp", kmp_matcher),
+        ("horspool", boyer_moore_horspool_matcher),
+        ("boyer-moore", boyer_moore_matcher),
+        ("z", z_matcher),
+    ];
+
+    fn random_string(rng: &mut Wyhash64RNG, len: usize, alphabet: u64) -> Vec<u8> {
+        (0..len)
+            .map(|_| b'a' + rng.gen_in_range(0..alphabet) as u8)
+            .collect()
+    }
+
+    #[test]
+    fn simple() {
+        let text = b"acaabcabacaabcaab";
+
+        for (name, f) in MATCHERS {
+            println!("Matcher: {}", name);
+
+            assert_eq!(vec![3, 11], f(text, b"abca"));
+            assert_eq!(vec![2, 10, 14], f(text, b"aab"));
+            assert_eq!(vec![0], f(text, text));
+            assert!(f(text, b"abcd").is_empty());
+            assert!(f(b"ab", b"abc").is_empty());
+            assert_eq!(vec![0, 1, 2], f(b"ab", b""));
+            assert_eq!(vec![0, 1, 2, 3], f(b"aaaaa", b"aa"));
+        }
+    }
+
+    #[test]
+    fn random() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+
+        for _ in 0..50 {
+            let text = random_string(&mut rng, 300, 3);
+            let len = rng.gen_in_range(1..6) as usize;
+            let pattern = random_string(&mut rng, len, 3);
+            let expected = naive_matcher(&text, &pattern);
+
+            for (name, f) in MATCHERS {
+                assert_eq!(expected, f(&text, &pattern), "matcher {}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn prefix_function() {
+        assert_eq!(
+            vec![0, 0, 1, 2, 3, 4, 5, 6, 0, 1],
+            compute_prefix_function(b"ababababca")
+        );
+    }
+
+    #[test]
+    fn z() {
+        assert_eq!(
+            vec![11, 0, 1, 0, 7, 0, 1, 0, 3, 0, 1],
+            z_array(b"abacabacaba")
+        );
+        assert!(z_array::<u8>(&[]).is_empty());
+    }
+
+    #[test]
+    fn aho_corasick() {
+        let patterns: [&[u8]; 4] = [b"he", b"she", b"his", b"hers"];
+        let ac = AhoCorasick::new(&patterns);
+
+        assert_eq!(vec![(1, 1), (0, 2), (3, 2)], ac.find_all(b"ushers"));
+        assert!(ac.find_all(b"xyz").is_empty());
+    }
+
+    #[test]
+    fn aho_corasick_empty_pattern() {
+        let patterns: [&[u8]; 3] = [b"", b"a", b""];
+        let ac = AhoCorasick::new(&patterns);
+
+        assert_eq!(
+            vec![(0, 0), (2, 0), (1, 0), (0, 1), (2, 1), (0, 2), (2, 2)],
+            ac.find_all(b"ab")
+        );
+        assert_eq!(vec![(0, 0), (2, 0)], ac.find_all(b""));
+
+        let ac = AhoCorasick::new(&[b""]);
+
+        assert_eq!(
+            (0..=3).map(|s| (0, s)).collect::<Vec<_>>(),
+            ac.find_all(b"abc")
+        );
+    }
+
+    #[test]
+    fn aho_corasick_random() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+        let text = random_string(&mut rng, 500, 3);
+        let patterns: Vec<Vec<u8>> = (0..20)
+            .map(|_| {
+                let len = rng.gen_in_range(0..5) as usize;
+                random_string(&mut rng, len, 3)
+            })
+            .collect();
+        let refs: Vec<&[u8]> = patterns.iter().map(Vec::as_slice).collect();
+
+        let mut found = AhoCorasick::new(&refs).find_all(&text);
+        let mut expected = vec![];
+
+        for (p, pattern) in patterns.iter().enumerate() {
+            for s in naive_matcher(&text, pattern) {
+                expected.push((p, s));
+            }
+        }
+
+        found.sort_unstable();
+        expected.sort_unstable();
+
+        assert_eq!(expected, found);
+    }
+}
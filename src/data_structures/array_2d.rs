@@ -1,14 +1,24 @@
 //! Handy 2D array abstraction over a single [Vec] supporting `[y][x]` operations
 
 use std::fmt;
-use std::ops::{Index, IndexMut};
+use std::ops::{Index, IndexMut, Range};
 
+#[derive(Clone, PartialEq, Eq)]
 pub struct Array2D<T> {
     width: usize,
     height: usize,
     data: Vec<T>,
 }
 
+/// A borrowed rectangle of an [Array2D], indexed relative to its own top left corner.
+pub struct Array2DView<'a, T> {
+    data: &'a Array2D<T>,
+    y0: usize,
+    x0: usize,
+    width: usize,
+    height: usize,
+}
+
 impl<T: Copy> Array2D<T> {
     pub fn new(init: T, width: usize, height: usize) -> Self {
         let data = vec![init; width * height];
@@ -28,6 +38,73 @@ impl<T: Copy> Array2D<T> {
         }
     }
 
+    pub fn map<U: Copy>(&self, f: impl FnMut(&T) -> U) -> Array2D<U> {
+        Array2D {
+            width: self.width,
+            height: self.height,
+            data: self.data.iter().map(f).collect(),
+        }
+    }
+
+    /// Change the dimensions, keeping the cells that are still in bounds where they were and
+    /// filling the new ones with `fill`.
+    pub fn resize(&mut self, width: usize, height: usize, fill: T) {
+        let mut data = Vec::with_capacity(width * height);
+
+        for y in 0..height {
+            if y < self.height {
+                let keep = width.min(self.width);
+
+                data.extend_from_slice(&self[y][..keep]);
+                data.resize(data.len() + width - keep, fill);
+            } else {
+                data.resize(data.len() + width, fill);
+            }
+        }
+
+        self.width = width;
+        self.height = height;
+        self.data = data;
+    }
+
+    /// Insert `row` before row `y`, moving the ones below it down.
+    pub fn insert_row(&mut self, y: usize, row: &[T]) {
+        assert!(y <= self.height, "row out of bounds");
+        assert_eq!(
+            self.width,
+            row.len(),
+            "row should be as long as the array is wide"
+        );
+
+        let at = y * self.width;
+
+        self.data.splice(at..at, row.iter().copied());
+        self.height += 1;
+    }
+
+    /// Insert `col` before column `x`, moving the ones to its right.
+    pub fn insert_col(&mut self, x: usize, col: &[T]) {
+        assert!(x <= self.width, "column out of bounds");
+        assert_eq!(
+            self.height,
+            col.len(),
+            "column should be as long as the array is high"
+        );
+
+        let mut data = Vec::with_capacity((self.width + 1) * self.height);
+
+        for (y, &c) in col.iter().enumerate() {
+            data.extend_from_slice(&self[y][..x]);
+            data.push(c);
+            data.extend_from_slice(&self[y][x..]);
+        }
+
+        self.width += 1;
+        self.data = data;
+    }
+}
+
+impl<T> Array2D<T> {
     pub fn get(&self, y: usize, x: usize) -> Option<&T> {
         let idx = self.idx(x, y)?;
         self.data.get(idx)
@@ -53,6 +130,133 @@ impl<T: Copy> Array2D<T> {
     pub fn height(&self) -> usize {
         self.height
     }
+
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &[T]> {
+        (0..self.height).map(|y| &self[y])
+    }
+
+    pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut [T]> {
+        // `chunks_mut` doesn't accept a width of zero, and wouldn't yield the empty rows anyway
+        let empty = if self.width == 0 { self.height } else { 0 };
+
+        self.data
+            .chunks_mut(self.width.max(1))
+            .chain(std::iter::repeat_with(|| &mut [][..]).take(empty))
+    }
+
+    pub fn column(&self, x: usize) -> impl DoubleEndedIterator<Item = &T> {
+        assert!(x < self.width, "column out of bounds");
+
+        self.data.iter().skip(x).step_by(self.width)
+    }
+
+    pub fn column_mut(&mut self, x: usize) -> impl Iterator<Item = &mut T> {
+        assert!(x < self.width, "column out of bounds");
+
+        self.data.iter_mut().skip(x).step_by(self.width)
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl DoubleEndedIterator<Item = &T>> {
+        (0..self.width).map(|x| self.column(x))
+    }
+
+    /// Every cell in row-major order, together with its `(y, x)` coordinates.
+    pub fn enumerate_cells(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        self.data
+            .iter()
+            .enumerate()
+            .map(|(i, v)| ((i / self.width, i % self.width), v))
+    }
+
+    /// The rectangle made of rows `ys` and columns `xs`, without copying.
+    pub fn view(&self, ys: Range<usize>, xs: Range<usize>) -> Array2DView<'_, T> {
+        Array2DView::new(self, 0, 0, self.width, self.height).view(ys, xs)
+    }
+}
+
+impl<'a, T> Array2DView<'a, T> {
+    fn new(data: &'a Array2D<T>, y0: usize, x0: usize, width: usize, height: usize) -> Self {
+        Self {
+            data,
+            y0,
+            x0,
+            width,
+            height,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, y: usize, x: usize) -> Option<&'a T> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.data.get(self.y0 + y, self.x0 + x)
+    }
+
+    /// Row `y` of the view. Unlike `Index`, the slice borrows the array rather than the view.
+    pub fn row(&self, y: usize) -> &'a [T] {
+        assert!(y < self.height);
+
+        &self.data[self.y0 + y][self.x0..self.x0 + self.width]
+    }
+
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &'a [T]> + use<'a, T> {
+        let view = *self;
+
+        (0..self.height).map(move |y| view.row(y))
+    }
+
+    /// A smaller rectangle, with `ys` and `xs` relative to this view.
+    pub fn view(&self, ys: Range<usize>, xs: Range<usize>) -> Self {
+        assert!(
+            ys.start <= ys.end && ys.end <= self.height,
+            "rows out of bounds"
+        );
+        assert!(
+            xs.start <= xs.end && xs.end <= self.width,
+            "columns out of bounds"
+        );
+
+        Self::new(
+            self.data,
+            self.y0 + ys.start,
+            self.x0 + xs.start,
+            xs.len(),
+            ys.len(),
+        )
+    }
+}
+
+impl<T: Copy> Array2DView<'_, T> {
+    pub fn to_array(&self) -> Array2D<T> {
+        let data: Vec<T> = self.rows().flatten().copied().collect();
+
+        Array2D::from_slice(self.width, self.height, &data)
+    }
+}
+
+impl<T> Clone for Array2DView<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Array2DView<'_, T> {}
+
+impl<T> Index<usize> for Array2DView<'_, T> {
+    type Output = [T];
+
+    fn index(&self, y: usize) -> &Self::Output {
+        self.row(y)
+    }
 }
 
 impl<T> Index<usize> for Array2D<T> {
@@ -106,6 +310,44 @@ impl<T: fmt::Debug> fmt::Debug for Array2D<T> {
     }
 }
 
+/// A table with a column per `x` and a line per `y`, each column right-aligned to its widest cell.
+/// The precision, as in `{:.2}`, is applied to every cell.
+impl<T: fmt::Display> fmt::Display for Array2D<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cells: Vec<String> = self
+            .data
+            .iter()
+            .map(|v| match f.precision() {
+                Some(p) => format!("{v:.p$}"),
+                None => v.to_string(),
+            })
+            .collect();
+
+        let widths: Vec<usize> = (0..self.width)
+            .map(|x| {
+                (0..self.height)
+                    .map(|y| cells[y * self.width + x].chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if x > 0 {
+                    write!(f, " ")?;
+                }
+
+                write!(f, "{:>w$}", cells[y * self.width + x], w = widths[x])?;
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -156,4 +398,85 @@ mod tests {
 
         assert_eq!(fmt, exp);
     }
+
+    #[test]
+    fn iterators() {
+        let mut arr = Array2D::from_slice(3, 2, &[1, 2, 3, 4, 5, 6]);
+
+        let rows: Vec<&[i32]> = arr.rows().collect();
+        assert_eq!(vec![&[1, 2, 3][..], &[4, 5, 6]], rows);
+
+        assert_eq!(vec![2, 5], arr.column(1).copied().collect::<Vec<_>>());
+        assert_eq!(
+            vec![vec![1, 4], vec![2, 5], vec![3, 6]],
+            arr.columns()
+                .map(|c| c.copied().collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        );
+
+        let cells: Vec<_> = arr.enumerate_cells().map(|(c, &v)| (c, v)).collect();
+        assert_eq!(((1, 0), 4), cells[3]);
+        assert_eq!(6, cells.len());
+
+        for row in arr.rows_mut() {
+            row.reverse();
+        }
+
+        for v in arr.column_mut(0) {
+            *v *= 10;
+        }
+
+        assert_eq!(&[30, 2, 1, 60, 5, 4], arr.as_ref());
+        assert_eq!(3, Array2D::new(0, 0, 3).rows_mut().count());
+    }
+
+    #[test]
+    fn views() {
+        let arr = Array2D::from_slice(4, 3, &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]);
+        let view = arr.view(1..3, 1..4);
+
+        assert_eq!((3, 2), (view.width(), view.height()));
+        assert_eq!(&[6, 7, 8], &view[0]);
+        assert_eq!(Some(&12), view.get(1, 2));
+        assert_eq!(None, view.get(2, 0));
+
+        let inner = view.view(0..2, 1..2);
+
+        assert_eq!(Array2D::from_slice(1, 2, &[7, 11]), inner.to_array());
+        assert_eq!(0, arr.view(1..1, 0..4).rows().count());
+    }
+
+    #[test]
+    fn map_and_resize() {
+        let mut arr = Array2D::from_slice(2, 2, &[1, 2, 3, 4]).map(|&v| v * 2);
+
+        assert_eq!(&[2, 4, 6, 8], arr.as_ref());
+
+        arr.resize(3, 3, 0);
+        assert_eq!(&[2, 4, 0, 6, 8, 0, 0, 0, 0], arr.as_ref());
+
+        arr.resize(1, 2, 0);
+        assert_eq!(&[2, 6], arr.as_ref());
+
+        arr.insert_row(1, &[7]);
+        arr.insert_row(3, &[9]);
+        assert_eq!(&[2, 7, 6, 9], arr.as_ref());
+        assert_eq!(4, arr.height());
+
+        arr.insert_col(0, &[1, 1, 1, 1]);
+        arr.insert_col(2, &[3, 3, 3, 3]);
+        assert_eq!((3, 4), (arr.width(), arr.height()));
+        assert_eq!(&[1, 7, 3], &arr[1]);
+    }
+
+    #[test]
+    fn display() {
+        let arr = Array2D::from_slice(3, 2, &[1, 200, 3, -45, 5, 6]);
+
+        assert_eq!("  1 200 3\n-45   5 6\n", arr.to_string());
+
+        let arr = Array2D::from_slice(2, 1, &[0.5, 12.25]);
+
+        assert_eq!("0.5 12.2\n", format!("{arr:.1}"));
+    }
 }
@@ -0,0 +1,543 @@
+//! B+ tree: a B-tree variant that keeps all values in linked leaves for sequential scans
+
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+
+use super::b_tree::{after_start, before_end};
+
+pub struct BPlusTree<K, V> {
+    t: usize,
+    len: usize,
+    root: usize,
+    nodes: Vec<Node<K, V>>,
+    recycled: Vec<usize>,
+}
+
+// Internal nodes use `keys` as separators and `children` as links, leaves use `keys`, `values`
+// and `next`. Every key in `children[i + 1]` is greater or equal to the separator `keys[i]`.
+struct Node<K, V> {
+    leaf: bool,
+    keys: Vec<K>,
+    values: Vec<V>,
+    children: Vec<usize>,
+    next: usize,
+}
+
+const NIL: usize = usize::MAX;
+
+impl<K, V> Node<K, V> {
+    fn new(leaf: bool) -> Self {
+        Self {
+            leaf,
+            keys: Vec::new(),
+            values: Vec::new(),
+            children: Vec::new(),
+            next: NIL,
+        }
+    }
+}
+
+pub struct Iter<'a, K, V> {
+    tree: &'a BPlusTree<K, V>,
+    leaf: usize,
+    pos: usize,
+}
+
+impl<K: Ord + Clone, V> BPlusTree<K, V> {
+    /// Construct an empty B+ tree in which every node except the root holds between `t - 1` and
+    /// `2t - 1` keys.
+    pub fn new(t: usize) -> Self {
+        assert!(t >= 2);
+
+        Self {
+            t,
+            len: 0,
+            root: 0,
+            nodes: vec![Node::new(true)],
+            recycled: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let x = self.find_leaf(Bound::Included(key));
+        let i = self.nodes[x].keys.binary_search(key).ok()?;
+
+        Some(&self.nodes[x].values[i])
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    fn find_leaf(&self, start: Bound<&K>) -> usize {
+        let mut x = self.root;
+
+        while !self.nodes[x].leaf {
+            let i = match start {
+                Bound::Included(k) | Bound::Excluded(k) => child_index(&self.nodes[x].keys, k),
+                Bound::Unbounded => 0,
+            };
+
+            x = self.nodes[x].children[i];
+        }
+
+        x
+    }
+
+    fn allocate(&mut self, leaf: bool) -> usize {
+        if let Some(idx) = self.recycled.pop() {
+            self.nodes[idx] = Node::new(leaf);
+            return idx;
+        }
+
+        self.nodes.push(Node::new(leaf));
+        self.nodes.len() - 1
+    }
+
+    /// Insert a key-value pair, returning the previous value if the key was already present.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let (old, split) = self.insert_at(self.root, key, value);
+
+        if let Some((sep, z)) = split {
+            let s = self.allocate(false);
+
+            self.nodes[s].keys.push(sep);
+            self.nodes[s].children.push(self.root);
+            self.nodes[s].children.push(z);
+
+            self.root = s;
+        }
+
+        if old.is_none() {
+            self.len += 1;
+        }
+
+        old
+    }
+
+    // Returns the replaced value and, if `x` overflowed, the separator and the new right sibling.
+    fn insert_at(&mut self, x: usize, key: K, value: V) -> (Option<V>, Option<(K, usize)>) {
+        if self.nodes[x].leaf {
+            match self.nodes[x].keys.binary_search(&key) {
+                Ok(i) => {
+                    let old = mem::replace(&mut self.nodes[x].values[i], value);
+                    return (Some(old), None);
+                }
+                Err(i) => {
+                    self.nodes[x].keys.insert(i, key);
+                    self.nodes[x].values.insert(i, value);
+                }
+            }
+
+            return (None, self.split_if_overflown(x));
+        }
+
+        let i = child_index(&self.nodes[x].keys, &key);
+        let (old, split) = self.insert_at(self.nodes[x].children[i], key, value);
+
+        let Some((sep, z)) = split else {
+            return (old, None);
+        };
+
+        self.nodes[x].keys.insert(i, sep);
+        self.nodes[x].children.insert(i + 1, z);
+
+        (old, self.split_if_overflown(x))
+    }
+
+    fn split_if_overflown(&mut self, x: usize) -> Option<(K, usize)> {
+        let t = self.t;
+
+        if self.nodes[x].keys.len() < 2 * t {
+            return None;
+        }
+
+        let z = self.allocate(self.nodes[x].leaf);
+
+        if self.nodes[x].leaf {
+            self.nodes[z].keys = self.nodes[x].keys.split_off(t);
+            self.nodes[z].values = self.nodes[x].values.split_off(t);
+
+            self.nodes[z].next = self.nodes[x].next;
+            self.nodes[x].next = z;
+
+            return Some((self.nodes[z].keys[0].clone(), z));
+        }
+
+        self.nodes[z].keys = self.nodes[x].keys.split_off(t + 1);
+        self.nodes[z].children = self.nodes[x].children.split_off(t + 1);
+
+        let sep = self.nodes[x]
+            .keys
+            .pop()
+            .expect("overflown node should have a median");
+
+        Some((sep, z))
+    }
+
+    /// Remove a key, returning its value if it was present.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let removed = self.remove_at(self.root, key);
+
+        let root = self.root;
+
+        if !self.nodes[root].leaf && self.nodes[root].keys.is_empty() {
+            self.root = self.nodes[root].children[0];
+            self.recycled.push(root);
+        }
+
+        if removed.is_some() {
+            self.len -= 1;
+        }
+
+        removed
+    }
+
+    fn remove_at(&mut self, x: usize, key: &K) -> Option<V> {
+        if self.nodes[x].leaf {
+            let i = self.nodes[x].keys.binary_search(key).ok()?;
+
+            self.nodes[x].keys.remove(i);
+
+            return Some(self.nodes[x].values.remove(i));
+        }
+
+        let i = child_index(&self.nodes[x].keys, key);
+        let removed = self.remove_at(self.nodes[x].children[i], key);
+
+        if self.nodes[self.nodes[x].children[i]].keys.len() < self.t - 1 {
+            self.fix_underflow(x, i);
+        }
+
+        removed
+    }
+
+    fn fix_underflow(&mut self, x: usize, i: usize) {
+        let t = self.t;
+        let c = self.nodes[x].children[i];
+        let num_children = self.nodes[x].children.len();
+
+        let left = (i > 0).then(|| self.nodes[x].children[i - 1]);
+        let right = (i + 1 < num_children).then(|| self.nodes[x].children[i + 1]);
+
+        if let Some(s) = left.filter(|&s| self.nodes[s].keys.len() >= t) {
+            self.borrow_from_left(x, i, s, c);
+        } else if let Some(s) = right.filter(|&s| self.nodes[s].keys.len() >= t) {
+            self.borrow_from_right(x, i, c, s);
+        } else if right.is_some() {
+            self.merge_children(x, i);
+        } else {
+            self.merge_children(x, i - 1);
+        }
+    }
+
+    fn borrow_from_left(&mut self, x: usize, i: usize, s: usize, c: usize) {
+        let sk = self.nodes[s]
+            .keys
+            .pop()
+            .expect("sibling should not be empty");
+
+        if self.nodes[c].leaf {
+            let sv = self.nodes[s]
+                .values
+                .pop()
+                .expect("sibling should not be empty");
+
+            self.nodes[x].keys[i - 1] = sk.clone();
+            self.nodes[c].keys.insert(0, sk);
+            self.nodes[c].values.insert(0, sv);
+        } else {
+            let sc = self.nodes[s]
+                .children
+                .pop()
+                .expect("sibling should not be empty");
+            let sep = mem::replace(&mut self.nodes[x].keys[i - 1], sk);
+
+            self.nodes[c].keys.insert(0, sep);
+            self.nodes[c].children.insert(0, sc);
+        }
+    }
+
+    fn borrow_from_right(&mut self, x: usize, i: usize, c: usize, s: usize) {
+        let sk = self.nodes[s].keys.remove(0);
+
+        if self.nodes[c].leaf {
+            let sv = self.nodes[s].values.remove(0);
+
+            self.nodes[x].keys[i] = self.nodes[s].keys[0].clone();
+            self.nodes[c].keys.push(sk);
+            self.nodes[c].values.push(sv);
+        } else {
+            let sc = self.nodes[s].children.remove(0);
+            let sep = mem::replace(&mut self.nodes[x].keys[i], sk);
+
+            self.nodes[c].keys.push(sep);
+            self.nodes[c].children.push(sc);
+        }
+    }
+
+    /// Merge the `i + 1`-th child of `x` into the `i`-th one, dropping the separator between them.
+    fn merge_children(&mut self, x: usize, i: usize) {
+        let y = self.nodes[x].children[i];
+        let z = self.nodes[x].children.remove(i + 1);
+        let sep = self.nodes[x].keys.remove(i);
+
+        let mut z_keys = mem::take(&mut self.nodes[z].keys);
+        let mut z_values = mem::take(&mut self.nodes[z].values);
+        let mut z_children = mem::take(&mut self.nodes[z].children);
+
+        if self.nodes[y].leaf {
+            self.nodes[y].next = self.nodes[z].next;
+        } else {
+            self.nodes[y].keys.push(sep);
+        }
+
+        self.nodes[y].keys.append(&mut z_keys);
+        self.nodes[y].values.append(&mut z_values);
+        self.nodes[y].children.append(&mut z_children);
+
+        self.recycled.push(z);
+    }
+
+    /// Iterate over the key-value pairs whose keys lie within `range`, binary searching for the
+    /// first one and then walking the leaf chain.
+    pub fn range<'a, R: RangeBounds<K> + 'a>(
+        &'a self,
+        range: R,
+    ) -> impl Iterator<Item = (&'a K, &'a V)> + 'a {
+        let leaf = self.find_leaf(range.start_bound());
+        let pos = self.nodes[leaf]
+            .keys
+            .partition_point(|k| !after_start(k, range.start_bound()));
+
+        Iter {
+            tree: self,
+            leaf,
+            pos,
+        }
+        .take_while(move |(k, _)| before_end(*k, range.end_bound()))
+    }
+
+    /// Iterate over all key-value pairs in ascending order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            tree: self,
+            leaf: self.find_leaf(Bound::Unbounded),
+            pos: 0,
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.leaf == NIL {
+                return None;
+            }
+
+            let node = &self.tree.nodes[self.leaf];
+
+            if self.pos < node.keys.len() {
+                let item = (&node.keys[self.pos], &node.values[self.pos]);
+                self.pos += 1;
+                return Some(item);
+            }
+
+            self.leaf = node.next;
+            self.pos = 0;
+        }
+    }
+}
+
+fn child_index<K: Ord>(keys: &[K], key: &K) -> usize {
+    match keys.binary_search(key) {
+        Ok(i) => i + 1,
+        Err(i) => i,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::rand::Wyhash64RNG;
+
+    const NUM_KEYS: u64 = if cfg!(miri) { 200 } else { 2000 };
+
+    fn check_invariants<K: Ord + Clone, V>(tree: &BPlusTree<K, V>) {
+        let mut leaves = vec![];
+
+        check_node(tree, tree.root, 0, &mut None, &mut leaves);
+
+        let count: usize = leaves.iter().map(|&l| tree.nodes[l].keys.len()).sum();
+        assert_eq!(count, tree.len());
+
+        for w in leaves.windows(2) {
+            assert_eq!(tree.nodes[w[0]].next, w[1]);
+        }
+
+        if let Some(&last) = leaves.last() {
+            assert_eq!(tree.nodes[last].next, NIL);
+        }
+
+        let keys: Vec<_> = tree.iter().map(|(k, _)| k).collect();
+        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    fn check_node<K: Ord + Clone, V>(
+        tree: &BPlusTree<K, V>,
+        x: usize,
+        depth: usize,
+        leaf_depth: &mut Option<usize>,
+        leaves: &mut Vec<usize>,
+    ) {
+        let node = &tree.nodes[x];
+        let n = node.keys.len();
+
+        assert!(n < 2 * tree.t);
+
+        if x != tree.root {
+            assert!(n >= tree.t - 1);
+        }
+
+        if node.leaf {
+            assert_eq!(n, node.values.len());
+            assert_eq!(*leaf_depth.get_or_insert(depth), depth);
+            leaves.push(x);
+            return;
+        }
+
+        assert_eq!(node.children.len(), n + 1);
+
+        for (i, &c) in node.children.iter().enumerate() {
+            let child_keys = subtree_keys(tree, c);
+
+            if i > 0 {
+                assert!(child_keys.iter().all(|k| *k >= &node.keys[i - 1]));
+            }
+
+            if let Some(sep) = node.keys.get(i) {
+                assert!(child_keys.iter().all(|k| *k < sep));
+            }
+
+            check_node(tree, c, depth + 1, leaf_depth, leaves);
+        }
+    }
+
+    fn subtree_keys<K, V>(tree: &BPlusTree<K, V>, x: usize) -> Vec<&K> {
+        let node = &tree.nodes[x];
+
+        if node.leaf {
+            return node.keys.iter().collect();
+        }
+
+        node.children
+            .iter()
+            .flat_map(|&c| subtree_keys(tree, c))
+            .collect()
+    }
+
+    #[test]
+    fn simple() {
+        let mut tree = BPlusTree::new(2);
+
+        assert!(tree.is_empty());
+        assert_eq!(None, tree.insert(3, "Three"));
+        assert_eq!(None, tree.insert(5, "Five"));
+        assert_eq!(None, tree.insert(7, "Seven"));
+        assert_eq!(None, tree.insert(9, "Nine"));
+        assert_eq!(Some("Five"), tree.insert(5, "V"));
+
+        assert_eq!(4, tree.len());
+        assert_eq!(Some(&"Three"), tree.get(&3));
+        assert_eq!(Some(&"V"), tree.get(&5));
+        assert_eq!(None, tree.get(&4));
+
+        assert_eq!(Some("V"), tree.remove(&5));
+        assert_eq!(None, tree.remove(&5));
+        assert!(!tree.contains_key(&5));
+        assert_eq!(3, tree.len());
+    }
+
+    #[test]
+    fn random() {
+        for t in 2..6 {
+            let mut rng = Wyhash64RNG::from_seed(123);
+            let mut tree = BPlusTree::new(t);
+            let mut reference = BTreeMap::new();
+
+            for i in 0..NUM_KEYS {
+                let key = rng.gen_in_range(0..NUM_KEYS / 2);
+
+                assert_eq!(reference.insert(key, i), tree.insert(key, i));
+            }
+
+            check_invariants(&tree);
+
+            for _ in 0..NUM_KEYS {
+                let key = rng.gen_in_range(0..NUM_KEYS / 2);
+
+                assert_eq!(reference.remove(&key), tree.remove(&key));
+            }
+
+            check_invariants(&tree);
+
+            assert!(reference.iter().eq(tree.iter()));
+        }
+    }
+
+    #[test]
+    fn remove_all() {
+        let mut tree = BPlusTree::new(2);
+
+        for i in 0..100 {
+            tree.insert(i, i);
+        }
+
+        for i in 0..100 {
+            assert_eq!(Some(i), tree.remove(&i));
+            check_invariants(&tree);
+        }
+
+        assert!(tree.is_empty());
+        assert_eq!(None, tree.iter().next());
+    }
+
+    #[test]
+    fn range() {
+        let mut tree = BPlusTree::new(3);
+        let mut reference = BTreeMap::new();
+
+        for i in (0..200).step_by(3) {
+            tree.insert(i, i * 10);
+            reference.insert(i, i * 10);
+        }
+
+        let ranges = [(10, 50), (0, 0), (199, 300), (51, 52), (7, 8)];
+
+        for (l, h) in ranges {
+            let expected: Vec<_> = reference.range(l..h).collect();
+            assert_eq!(expected, tree.range(l..h).collect::<Vec<_>>());
+
+            let expected: Vec<_> = reference.range(l..=h).collect();
+            assert_eq!(expected, tree.range(l..=h).collect::<Vec<_>>());
+
+            let expected: Vec<_> = reference.range(l..).collect();
+            assert_eq!(expected, tree.range(l..).collect::<Vec<_>>());
+
+            let expected: Vec<_> = reference.range(..h).collect();
+            assert_eq!(expected, tree.range(..h).collect::<Vec<_>>());
+        }
+    }
+}
@@ -0,0 +1,625 @@
+//! B-tree of configurable minimum degree (CLRS chapter 18)
+
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+
+pub struct BTree<K, V> {
+    t: usize,
+    len: usize,
+    root: usize,
+    nodes: Vec<Node<K, V>>,
+    recycled: Vec<usize>,
+}
+
+struct Node<K, V> {
+    keys: Vec<K>,
+    values: Vec<V>,
+    children: Vec<usize>,
+}
+
+impl<K, V> Node<K, V> {
+    fn new() -> Self {
+        Self {
+            keys: Vec::new(),
+            values: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+}
+
+impl<K: Ord, V> BTree<K, V> {
+    /// Construct an empty B-tree in which every node except the root holds between `t - 1` and
+    /// `2t - 1` keys.
+    pub fn new(t: usize) -> Self {
+        assert!(t >= 2);
+
+        Self {
+            t,
+            len: 0,
+            root: 0,
+            nodes: vec![Node::new()],
+            recycled: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let mut x = self.root;
+
+        loop {
+            let node = &self.nodes[x];
+
+            match node.keys.binary_search(key) {
+                Ok(i) => return Some(&node.values[i]),
+                Err(_) if node.is_leaf() => return None,
+                Err(i) => x = node.children[i],
+            }
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Insert a key-value pair, returning the previous value if the key was already present.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if self.is_full(self.root) {
+            let s = self.allocate();
+
+            self.nodes[s].children.push(self.root);
+            self.root = s;
+
+            self.split_child(s, 0);
+        }
+
+        let old = self.insert_nonfull(self.root, key, value);
+
+        if old.is_none() {
+            self.len += 1;
+        }
+
+        old
+    }
+
+    fn allocate(&mut self) -> usize {
+        if let Some(idx) = self.recycled.pop() {
+            self.nodes[idx] = Node::new();
+            return idx;
+        }
+
+        self.nodes.push(Node::new());
+        self.nodes.len() - 1
+    }
+
+    fn is_full(&self, x: usize) -> bool {
+        self.nodes[x].keys.len() == 2 * self.t - 1
+    }
+
+    fn split_child(&mut self, x: usize, i: usize) {
+        let t = self.t;
+        let y = self.nodes[x].children[i];
+        let z = self.allocate();
+
+        let keys = self.nodes[y].keys.split_off(t);
+        let values = self.nodes[y].values.split_off(t);
+        let children = if self.nodes[y].is_leaf() {
+            Vec::new()
+        } else {
+            self.nodes[y].children.split_off(t)
+        };
+
+        self.nodes[z].keys = keys;
+        self.nodes[z].values = values;
+        self.nodes[z].children = children;
+
+        let mid_key = self.nodes[y]
+            .keys
+            .pop()
+            .expect("full node should have a median");
+        let mid_value = self.nodes[y]
+            .values
+            .pop()
+            .expect("full node should have a median");
+
+        self.nodes[x].keys.insert(i, mid_key);
+        self.nodes[x].values.insert(i, mid_value);
+        self.nodes[x].children.insert(i + 1, z);
+    }
+
+    fn insert_nonfull(&mut self, mut x: usize, key: K, value: V) -> Option<V> {
+        loop {
+            let mut i = match self.nodes[x].keys.binary_search(&key) {
+                Ok(i) => return Some(mem::replace(&mut self.nodes[x].values[i], value)),
+                Err(i) => i,
+            };
+
+            if self.nodes[x].is_leaf() {
+                self.nodes[x].keys.insert(i, key);
+                self.nodes[x].values.insert(i, value);
+                return None;
+            }
+
+            if self.is_full(self.nodes[x].children[i]) {
+                self.split_child(x, i);
+
+                if key == self.nodes[x].keys[i] {
+                    return Some(mem::replace(&mut self.nodes[x].values[i], value));
+                }
+
+                if key > self.nodes[x].keys[i] {
+                    i += 1;
+                }
+            }
+
+            x = self.nodes[x].children[i];
+        }
+    }
+
+    /// Remove a key, returning its value if it was present.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let removed = self.remove_at(self.root, key);
+
+        let root = self.root;
+
+        if self.nodes[root].keys.is_empty() && !self.nodes[root].is_leaf() {
+            self.root = self.nodes[root].children[0];
+            self.recycled.push(root);
+        }
+
+        if removed.is_some() {
+            self.len -= 1;
+        }
+
+        removed
+    }
+
+    // Every node visited, except possibly the root, has at least `t` keys so that a key can be
+    // removed from it without a second pass back up the tree.
+    fn remove_at(&mut self, x: usize, key: &K) -> Option<V> {
+        let t = self.t;
+        let search = self.nodes[x].keys.binary_search(key);
+
+        if self.nodes[x].is_leaf() {
+            let i = search.ok()?;
+
+            self.nodes[x].keys.remove(i);
+
+            return Some(self.nodes[x].values.remove(i));
+        }
+
+        match search {
+            Ok(i) => {
+                let y = self.nodes[x].children[i];
+                let z = self.nodes[x].children[i + 1];
+
+                if self.nodes[y].keys.len() >= t {
+                    // case 2a: replace with predecessor
+                    let (k, v) = self.remove_max(y);
+
+                    self.nodes[x].keys[i] = k;
+
+                    Some(mem::replace(&mut self.nodes[x].values[i], v))
+                } else if self.nodes[z].keys.len() >= t {
+                    // case 2b: replace with successor
+                    let (k, v) = self.remove_min(z);
+
+                    self.nodes[x].keys[i] = k;
+
+                    Some(mem::replace(&mut self.nodes[x].values[i], v))
+                } else {
+                    // case 2c: push the key down into the merged child
+                    self.merge_children(x, i);
+                    self.remove_at(y, key)
+                }
+            }
+            Err(i) => {
+                // case 3: make sure the child has enough keys before descending
+                let c = self.ensure_child_has_t_keys(x, i);
+                self.remove_at(c, key)
+            }
+        }
+    }
+
+    fn remove_max(&mut self, mut x: usize) -> (K, V) {
+        while !self.nodes[x].is_leaf() {
+            let last = self.nodes[x].children.len() - 1;
+            x = self.ensure_child_has_t_keys(x, last);
+        }
+
+        let k = self.nodes[x].keys.pop().expect("node should not be empty");
+        let v = self.nodes[x]
+            .values
+            .pop()
+            .expect("node should not be empty");
+
+        (k, v)
+    }
+
+    fn remove_min(&mut self, mut x: usize) -> (K, V) {
+        while !self.nodes[x].is_leaf() {
+            x = self.ensure_child_has_t_keys(x, 0);
+        }
+
+        let k = self.nodes[x].keys.remove(0);
+        let v = self.nodes[x].values.remove(0);
+
+        (k, v)
+    }
+
+    /// Returns the node to descend into, which may differ from the `i`-th child after merging.
+    fn ensure_child_has_t_keys(&mut self, x: usize, i: usize) -> usize {
+        let t = self.t;
+        let c = self.nodes[x].children[i];
+
+        if self.nodes[c].keys.len() >= t {
+            return c;
+        }
+
+        let num_children = self.nodes[x].children.len();
+
+        if i > 0 && self.nodes[self.nodes[x].children[i - 1]].keys.len() >= t {
+            // case 3a: rotate a key from the left sibling through the parent
+            let s = self.nodes[x].children[i - 1];
+
+            let sk = self.nodes[s]
+                .keys
+                .pop()
+                .expect("sibling should not be empty");
+            let sv = self.nodes[s]
+                .values
+                .pop()
+                .expect("sibling should not be empty");
+            let sc = self.nodes[s].children.pop();
+
+            let pk = mem::replace(&mut self.nodes[x].keys[i - 1], sk);
+            let pv = mem::replace(&mut self.nodes[x].values[i - 1], sv);
+
+            self.nodes[c].keys.insert(0, pk);
+            self.nodes[c].values.insert(0, pv);
+
+            if let Some(sc) = sc {
+                self.nodes[c].children.insert(0, sc);
+            }
+
+            c
+        } else if i + 1 < num_children && self.nodes[self.nodes[x].children[i + 1]].keys.len() >= t
+        {
+            // case 3a: rotate a key from the right sibling through the parent
+            let s = self.nodes[x].children[i + 1];
+
+            let sk = self.nodes[s].keys.remove(0);
+            let sv = self.nodes[s].values.remove(0);
+            let sc = if self.nodes[s].is_leaf() {
+                None
+            } else {
+                Some(self.nodes[s].children.remove(0))
+            };
+
+            let pk = mem::replace(&mut self.nodes[x].keys[i], sk);
+            let pv = mem::replace(&mut self.nodes[x].values[i], sv);
+
+            self.nodes[c].keys.push(pk);
+            self.nodes[c].values.push(pv);
+
+            if let Some(sc) = sc {
+                self.nodes[c].children.push(sc);
+            }
+
+            c
+        } else if i + 1 < num_children {
+            // case 3b: merge with the right sibling
+            self.merge_children(x, i);
+            c
+        } else {
+            // case 3b: merge with the left sibling
+            self.merge_children(x, i - 1);
+            self.nodes[x].children[i - 1]
+        }
+    }
+
+    /// Merge the `i + 1`-th child and the `i`-th key of `x` into the `i`-th child.
+    fn merge_children(&mut self, x: usize, i: usize) {
+        let y = self.nodes[x].children[i];
+        let z = self.nodes[x].children.remove(i + 1);
+
+        let k = self.nodes[x].keys.remove(i);
+        let v = self.nodes[x].values.remove(i);
+
+        let mut z_keys = mem::take(&mut self.nodes[z].keys);
+        let mut z_values = mem::take(&mut self.nodes[z].values);
+        let mut z_children = mem::take(&mut self.nodes[z].children);
+
+        self.nodes[y].keys.push(k);
+        self.nodes[y].values.push(v);
+
+        self.nodes[y].keys.append(&mut z_keys);
+        self.nodes[y].values.append(&mut z_values);
+        self.nodes[y].children.append(&mut z_children);
+
+        self.recycled.push(z);
+    }
+
+    /// Iterate over the key-value pairs whose keys lie within `range`, in ascending order.
+    pub fn range<'a, R: RangeBounds<K> + 'a>(
+        &'a self,
+        range: R,
+    ) -> impl Iterator<Item = (&'a K, &'a V)> + 'a {
+        let mut stack = vec![];
+        let mut x = self.root;
+
+        // down to the first key in the range, remembering where to resume on each level
+        loop {
+            let node = &self.nodes[x];
+            let i = node
+                .keys
+                .partition_point(|k| !after_start(k, range.start_bound()));
+
+            stack.push((x, i));
+
+            if node.is_leaf() {
+                break;
+            }
+
+            x = node.children[i];
+        }
+
+        Iter { tree: self, stack }.take_while(move |(k, _)| before_end(*k, range.end_bound()))
+    }
+
+    /// Iterate over all key-value pairs in ascending order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut iter = Iter {
+            tree: self,
+            stack: vec![],
+        };
+
+        iter.descend(self.root);
+
+        iter
+    }
+}
+
+/// In-order iterator, keeping for each node on the path from the root the index of the next key
+/// to yield.
+pub struct Iter<'a, K, V> {
+    tree: &'a BTree<K, V>,
+    stack: Vec<(usize, usize)>,
+}
+
+impl<K, V> Iter<'_, K, V> {
+    fn descend(&mut self, mut x: usize) {
+        loop {
+            self.stack.push((x, 0));
+
+            match self.tree.nodes[x].children.first() {
+                Some(&c) => x = c,
+                None => break,
+            }
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let &mut (x, ref mut i) = self.stack.last_mut()?;
+            let node = &self.tree.nodes[x];
+
+            if *i < node.keys.len() {
+                let k = *i;
+
+                *i += 1;
+
+                if !node.is_leaf() {
+                    self.descend(node.children[k + 1]);
+                }
+
+                return Some((&node.keys[k], &node.values[k]));
+            }
+
+            self.stack.pop();
+        }
+    }
+}
+
+pub(crate) fn after_start<K: Ord>(key: &K, start: Bound<&K>) -> bool {
+    match start {
+        Bound::Included(s) => key >= s,
+        Bound::Excluded(s) => key > s,
+        Bound::Unbounded => true,
+    }
+}
+
+pub(crate) fn before_end<K: Ord>(key: &K, end: Bound<&K>) -> bool {
+    match end {
+        Bound::Included(e) => key <= e,
+        Bound::Excluded(e) => key < e,
+        Bound::Unbounded => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::rand::Wyhash64RNG;
+
+    const NUM_KEYS: u64 = if cfg!(miri) { 200 } else { 2000 };
+
+    fn check_invariants<K: Ord, V>(tree: &BTree<K, V>) {
+        let mut leaf_depth = None;
+        let count = check_node(tree, tree.root, 0, &mut leaf_depth, None, None);
+
+        assert_eq!(count, tree.len());
+    }
+
+    fn check_node<K: Ord, V>(
+        tree: &BTree<K, V>,
+        x: usize,
+        depth: usize,
+        leaf_depth: &mut Option<usize>,
+        low: Option<&K>,
+        high: Option<&K>,
+    ) -> usize {
+        let node = &tree.nodes[x];
+        let n = node.keys.len();
+
+        assert!(n < 2 * tree.t);
+        assert_eq!(n, node.values.len());
+
+        if x != tree.root {
+            assert!(n >= tree.t - 1);
+        }
+
+        assert!(node.keys.windows(2).all(|w| w[0] < w[1]));
+        assert!(low.is_none_or(|l| node.keys.iter().all(|k| k > l)));
+        assert!(high.is_none_or(|h| node.keys.iter().all(|k| k < h)));
+
+        if node.is_leaf() {
+            assert_eq!(*leaf_depth.get_or_insert(depth), depth);
+            return n;
+        }
+
+        assert_eq!(node.children.len(), n + 1);
+
+        let mut count = n;
+
+        for (i, &c) in node.children.iter().enumerate() {
+            let l = if i == 0 { low } else { Some(&node.keys[i - 1]) };
+            let h = node.keys.get(i).or(high);
+
+            count += check_node(tree, c, depth + 1, leaf_depth, l, h);
+        }
+
+        count
+    }
+
+    #[test]
+    fn simple() {
+        let mut tree = BTree::new(2);
+
+        assert!(tree.is_empty());
+        assert_eq!(None, tree.insert(3, "Three"));
+        assert_eq!(None, tree.insert(5, "Five"));
+        assert_eq!(None, tree.insert(7, "Seven"));
+        assert_eq!(Some("Five"), tree.insert(5, "V"));
+
+        assert_eq!(3, tree.len());
+        assert_eq!(Some(&"Three"), tree.get(&3));
+        assert_eq!(Some(&"V"), tree.get(&5));
+        assert_eq!(None, tree.get(&4));
+
+        assert_eq!(Some("V"), tree.remove(&5));
+        assert_eq!(None, tree.remove(&5));
+        assert!(!tree.contains_key(&5));
+        assert_eq!(2, tree.len());
+    }
+
+    // Figure 18.8 from CLRS, exercising every deletion case
+    #[test]
+    fn clrs_deletion() {
+        let mut tree = BTree::new(3);
+
+        for c in "ACDEFGJKLMNOPQRSTUVXYZ".chars() {
+            tree.insert(c, ());
+            check_invariants(&tree);
+        }
+
+        for c in "FMGDBCPV".chars() {
+            tree.remove(&c);
+            check_invariants(&tree);
+            assert!(!tree.contains_key(&c));
+        }
+
+        let keys: String = tree.iter().map(|(k, ())| *k).collect();
+
+        assert_eq!(keys, "AEJKLNOQRSTUXYZ");
+    }
+
+    #[test]
+    fn random() {
+        for t in 2..6 {
+            let mut rng = Wyhash64RNG::from_seed(123);
+            let mut tree = BTree::new(t);
+            let mut reference = BTreeMap::new();
+
+            for i in 0..NUM_KEYS {
+                let key = rng.gen_in_range(0..NUM_KEYS / 2);
+
+                assert_eq!(reference.insert(key, i), tree.insert(key, i));
+            }
+
+            check_invariants(&tree);
+
+            for _ in 0..NUM_KEYS {
+                let key = rng.gen_in_range(0..NUM_KEYS / 2);
+
+                assert_eq!(reference.remove(&key), tree.remove(&key));
+            }
+
+            check_invariants(&tree);
+
+            let expected: Vec<_> = reference.iter().collect();
+
+            assert_eq!(expected, tree.iter().collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn remove_all() {
+        let mut tree = BTree::new(2);
+
+        for i in 0..100 {
+            tree.insert(i, i);
+        }
+
+        for i in (0..100).rev() {
+            assert_eq!(Some(i), tree.remove(&i));
+            check_invariants(&tree);
+        }
+
+        assert!(tree.is_empty());
+        assert!(tree.iter().next().is_none());
+    }
+
+    #[test]
+    fn range() {
+        let mut tree = BTree::new(3);
+        let mut reference = BTreeMap::new();
+
+        for i in (0..200).step_by(3) {
+            tree.insert(i, i * 10);
+            reference.insert(i, i * 10);
+        }
+
+        let ranges = [(10, 50), (0, 0), (199, 300), (51, 52), (7, 8)];
+
+        for (l, h) in ranges {
+            let expected: Vec<_> = reference.range(l..h).collect();
+            assert_eq!(expected, tree.range(l..h).collect::<Vec<_>>());
+
+            let expected: Vec<_> = reference.range(l..=h).collect();
+            assert_eq!(expected, tree.range(l..=h).collect::<Vec<_>>());
+
+            let expected: Vec<_> = reference.range(l..).collect();
+            assert_eq!(expected, tree.range(l..).collect::<Vec<_>>());
+
+            let expected: Vec<_> = reference.range(..h).collect();
+            assert_eq!(expected, tree.range(..h).collect::<Vec<_>>());
+        }
+    }
+}
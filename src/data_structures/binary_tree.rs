@@ -94,6 +94,29 @@ impl<K: Ord + Copy, V> BinaryTree<K, V> {
             }
         }
     }
+
+    /// Number of edges from the root to the node holding `key`.
+    pub fn depth(&self, key: K) -> Option<usize> {
+        let mut idx = self.root;
+        let mut depth = 0;
+
+        while idx != NIL {
+            let current = &self.nodes[idx];
+
+            if key == current.key {
+                return Some(depth);
+            }
+
+            idx = if key < current.key {
+                current.left
+            } else {
+                current.right
+            };
+            depth += 1;
+        }
+
+        None
+    }
 }
 
 #[test]
@@ -0,0 +1,207 @@
+//! Fenwick tree (binary indexed tree): prefix sums with point updates, both in O(log n)
+
+use std::ops::{Add, AddAssign, Range, Sub};
+
+pub struct FenwickTree<T> {
+    // tree[i] is the sum of the elements in (i - lowbit(i), i], 1-based
+    tree: Vec<T>,
+}
+
+fn lowbit(i: usize) -> usize {
+    i & i.wrapping_neg()
+}
+
+impl<T> FenwickTree<T>
+where
+    T: Copy + Default + Add<Output = T> + AddAssign + Sub<Output = T>,
+{
+    /// A tree of `n` zeros.
+    pub fn new(n: usize) -> Self {
+        Self {
+            tree: vec![T::default(); n + 1],
+        }
+    }
+
+    /// Build in O(n) by pushing each partial sum to its parent.
+    pub fn from_slice(a: &[T]) -> Self {
+        let mut tree = Vec::with_capacity(a.len() + 1);
+
+        tree.push(T::default());
+        tree.extend_from_slice(a);
+
+        for i in 1..tree.len() {
+            let parent = i + lowbit(i);
+
+            if parent < tree.len() {
+                let x = tree[i];
+                tree[parent] += x;
+            }
+        }
+
+        Self { tree }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn add(&mut self, i: usize, delta: T) {
+        assert!(i < self.len(), "index out of bounds");
+
+        let mut i = i + 1;
+
+        while i < self.tree.len() {
+            self.tree[i] += delta;
+            i += lowbit(i);
+        }
+    }
+
+    /// Sum of the first `end` elements.
+    pub fn prefix_sum(&self, end: usize) -> T {
+        assert!(end <= self.len(), "index out of bounds");
+
+        let mut sum = T::default();
+        let mut i = end;
+
+        while i > 0 {
+            sum += self.tree[i];
+            i -= lowbit(i);
+        }
+
+        sum
+    }
+
+    pub fn range_sum(&self, range: Range<usize>) -> T {
+        if range.start >= range.end {
+            return T::default();
+        }
+
+        self.prefix_sum(range.end) - self.prefix_sum(range.start)
+    }
+
+    pub fn get(&self, i: usize) -> T {
+        self.range_sum(i..i + 1)
+    }
+
+    pub fn set(&mut self, i: usize, value: T) {
+        let old = self.get(i);
+
+        self.add(i, value - old);
+    }
+}
+
+impl<T> FenwickTree<T>
+where
+    T: Copy + Default + Add<Output = T> + AddAssign + Sub<Output = T> + PartialOrd,
+{
+    /// The smallest `end` such that `prefix_sum(end) >= target`, or `len() + 1` if there is none.
+    /// Only meaningful when no element is negative, so that the prefix sums are sorted.
+    pub fn lower_bound(&self, target: T) -> usize {
+        if target <= T::default() {
+            return 0;
+        }
+
+        let mut pos = 0;
+        let mut sum = T::default();
+        let mut step = (self.tree.len() - 1)
+            .checked_next_power_of_two()
+            .unwrap_or(0);
+
+        // descend from the largest power of two, keeping prefix_sum(pos) < target
+        while step > 0 {
+            let next = pos + step;
+
+            if next < self.tree.len() && sum + self.tree[next] < target {
+                pos = next;
+                sum += self.tree[next];
+            }
+
+            step /= 2;
+        }
+
+        pos + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rand::Wyhash64RNG;
+
+    #[test]
+    fn simple() {
+        let mut tree = FenwickTree::from_slice(&[3, 2, -1, 6, 5, 4, -3, 3, 7, 2, 3]);
+
+        assert_eq!(11, tree.len());
+        assert_eq!(0, tree.prefix_sum(0));
+        assert_eq!(10, tree.prefix_sum(4));
+        assert_eq!(31, tree.prefix_sum(11));
+        assert_eq!(15, tree.range_sum(3..6));
+        assert_eq!(0, tree.range_sum(5..5));
+        assert_eq!(-3, tree.get(6));
+
+        tree.add(2, 5);
+        tree.set(10, 0);
+
+        assert_eq!(4, tree.get(2));
+        assert_eq!(33, tree.prefix_sum(11));
+    }
+
+    #[test]
+    fn random() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+        let n = 200;
+        let mut naive: Vec<i64> = (0..n).map(|_| rng.gen_in_range_i64(-50..50)).collect();
+        let mut tree = FenwickTree::from_slice(&naive);
+        let mut empty = FenwickTree::new(n);
+
+        for (i, &x) in naive.iter().enumerate() {
+            empty.add(i, x);
+        }
+
+        for _ in 0..1000 {
+            let i = rng.gen_in_range(0..n as u64) as usize;
+            let j = rng.gen_in_range(0..n as u64 + 1) as usize;
+
+            if rng.gen() % 2 == 0 {
+                let delta = rng.gen_in_range_i64(-50..50);
+
+                naive[i] += delta;
+                tree.add(i, delta);
+                empty.add(i, delta);
+            } else {
+                let value = rng.gen_in_range_i64(-50..50);
+
+                naive[i] = value;
+                tree.set(i, value);
+                empty.set(i, value);
+            }
+
+            let expected: i64 = naive[i.min(j)..i.max(j)].iter().sum();
+
+            assert_eq!(expected, tree.range_sum(i.min(j)..i.max(j)));
+            assert_eq!(expected, empty.range_sum(i.min(j)..i.max(j)));
+            assert_eq!(naive[..j].iter().sum::<i64>(), tree.prefix_sum(j));
+        }
+    }
+
+    #[test]
+    fn lower_bound() {
+        let counts = [0, 2, 0, 0, 3, 1, 0, 4];
+        let tree = FenwickTree::from_slice(&counts);
+
+        for target in 0..12 {
+            let expected = (0..=counts.len())
+                .find(|&end| tree.prefix_sum(end) >= target)
+                .unwrap_or(counts.len() + 1);
+
+            assert_eq!(expected, tree.lower_bound(target), "target {target}");
+        }
+
+        assert_eq!(1, FenwickTree::<u32>::new(0).lower_bound(1));
+    }
+}
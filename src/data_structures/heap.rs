@@ -1,17 +1,26 @@
 //! Binary heap that borrows contents of underlying storage as a mutable slice
 
-pub struct BorrowingHeap<'d, T: PartialOrd> {
+pub struct BorrowingHeap<'d, T, F = fn(&T, &T) -> bool> {
     len: usize,
     data: &'d mut [T],
+    // the root is the greatest element according to this ordering
+    is_less: F,
 }
 
-impl<'d, T: PartialOrd + Copy> BorrowingHeap<'d, T> {
+impl<'d, T: PartialOrd> BorrowingHeap<'d, T> {
     pub fn from_slice<'s: 'd>(slice: &'s mut [T]) -> Self {
+        Self::from_slice_by(slice, T::lt)
+    }
+}
+
+impl<'d, T, F: FnMut(&T, &T) -> bool> BorrowingHeap<'d, T, F> {
+    pub fn from_slice_by<'s: 'd>(slice: &'s mut [T], is_less: F) -> Self {
         let slice_len = slice.len();
 
         let mut heap = Self {
             len: slice_len,
             data: slice,
+            is_less,
         };
 
         for i in (0..=(slice_len / 2)).rev() {
@@ -21,8 +30,8 @@ impl<'d, T: PartialOrd + Copy> BorrowingHeap<'d, T> {
         heap
     }
 
-    fn has_more_priority(&self, x: usize, y: usize) -> bool {
-        self.data[x] > self.data[y]
+    fn has_more_priority(&mut self, x: usize, y: usize) -> bool {
+        (self.is_less)(&self.data[y], &self.data[x])
     }
 
     #[allow(clippy::useless_let_if_seq)]
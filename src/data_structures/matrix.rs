@@ -0,0 +1,548 @@
+//! Dense matrices stored in an [Array2D], with borrowed views of rows, columns and submatrices
+
+use std::fmt;
+use std::ops::{Add, Index, IndexMut, Mul, Range, Sub};
+
+use crate::data_structures::array_2d::{Array2D, Array2DView};
+
+// Below this size Strassen's extra additions cost more than the multiplication they save
+const STRASSEN_THRESHOLD: usize = 64;
+
+/// Numbers that matrices can be made of.
+pub trait Scalar:
+    Copy + PartialEq + fmt::Debug + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+    /// Whether Strassen's algorithm may be used, which needs differences of products even when
+    /// the result only has sums of them: not for unsigned types, whose subtraction overflows.
+    const STRASSEN: bool;
+}
+
+macro_rules! impl_scalar {
+    ($($t:ty: $zero:expr, $one:expr, $strassen:expr);*) => {$(
+        impl Scalar for $t {
+            const ZERO: Self = $zero;
+            const ONE: Self = $one;
+            const STRASSEN: bool = $strassen;
+        }
+    )*};
+}
+
+impl_scalar!(
+    i32: 0, 1, true;
+    i64: 0, 1, true;
+    isize: 0, 1, true;
+    u32: 0, 1, false;
+    u64: 0, 1, false;
+    usize: 0, 1, false;
+    f32: 0.0, 1.0, true;
+    f64: 0.0, 1.0, true
+);
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Matrix<T> {
+    data: Array2D<T>,
+}
+
+/// A rectangular part of a matrix, borrowed rather than copied.
+#[derive(Clone, Copy)]
+pub struct MatrixView<'a, T> {
+    view: Array2DView<'a, T>,
+}
+
+impl<T: Scalar> Matrix<T> {
+    /// A `rows` x `cols` matrix of zeros.
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Self {
+            data: Array2D::new(T::ZERO, cols, rows),
+        }
+    }
+
+    /// Build from the elements in row-major order.
+    pub fn from_slice(rows: usize, cols: usize, data: &[T]) -> Self {
+        assert_eq!(rows * cols, data.len(), "wrong number of elements");
+
+        Self {
+            data: Array2D::from_slice(cols, rows, data),
+        }
+    }
+
+    pub fn identity(n: usize) -> Self {
+        let mut m = Self::new(n, n);
+
+        for i in 0..n {
+            m[(i, i)] = T::ONE;
+        }
+
+        m
+    }
+
+    pub fn rows(&self) -> usize {
+        self.data.height()
+    }
+
+    pub fn cols(&self) -> usize {
+        self.data.width()
+    }
+
+    pub fn is_square(&self) -> bool {
+        self.rows() == self.cols()
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> Option<&T> {
+        self.data.get(i, j)
+    }
+
+    pub fn get_mut(&mut self, i: usize, j: usize) -> Option<&mut T> {
+        self.data.get_mut(i, j)
+    }
+
+    pub fn as_array(&self) -> &Array2D<T> {
+        &self.data
+    }
+
+    pub fn into_array(self) -> Array2D<T> {
+        self.data
+    }
+
+    pub fn view(&self) -> MatrixView<'_, T> {
+        MatrixView {
+            view: self.data.view(0..self.rows(), 0..self.cols()),
+        }
+    }
+
+    pub fn row(&self, i: usize) -> MatrixView<'_, T> {
+        self.view().row(i)
+    }
+
+    pub fn column(&self, j: usize) -> MatrixView<'_, T> {
+        self.view().column(j)
+    }
+
+    pub fn submatrix(&self, rows: Range<usize>, cols: Range<usize>) -> MatrixView<'_, T> {
+        self.view().submatrix(rows, cols)
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut t = Self::new(self.cols(), self.rows());
+
+        for i in 0..self.rows() {
+            for j in 0..self.cols() {
+                t[(j, i)] = self[(i, j)];
+            }
+        }
+
+        t
+    }
+
+    pub fn scale(&self, k: T) -> Self {
+        self.view().map(|x| x * k)
+    }
+
+    /// Multiply with Strassen's algorithm, padding odd sizes with a row and column of zeros.
+    /// Both matrices must be square and of the same size, and `T::STRASSEN` must hold.
+    pub fn strassen(&self, rhs: &Self) -> Self {
+        assert!(
+            T::STRASSEN,
+            "scalar type should support Strassen's algorithm"
+        );
+
+        strassen(self.view(), rhs.view(), STRASSEN_THRESHOLD)
+    }
+}
+
+impl<'a, T: Scalar> MatrixView<'a, T> {
+    pub fn rows(&self) -> usize {
+        self.view.height()
+    }
+
+    pub fn cols(&self) -> usize {
+        self.view.width()
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> Option<&'a T> {
+        self.view.get(i, j)
+    }
+
+    /// Row `i` of the view as a slice.
+    pub fn row_slice(&self, i: usize) -> &'a [T] {
+        self.view.row(i)
+    }
+
+    pub fn row(&self, i: usize) -> Self {
+        self.submatrix(i..i + 1, 0..self.cols())
+    }
+
+    pub fn column(&self, j: usize) -> Self {
+        self.submatrix(0..self.rows(), j..j + 1)
+    }
+
+    /// The part of this view at `rows` and `cols`, relative to the view.
+    pub fn submatrix(&self, rows: Range<usize>, cols: Range<usize>) -> Self {
+        Self {
+            view: self.view.view(rows, cols),
+        }
+    }
+
+    pub fn as_array_view(&self) -> Array2DView<'a, T> {
+        self.view
+    }
+
+    /// The elements in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = &'a T> + use<'a, T> {
+        self.view.rows().flatten()
+    }
+
+    pub fn to_matrix(&self) -> Matrix<T> {
+        Matrix {
+            data: self.view.to_array(),
+        }
+    }
+
+    fn map(&self, mut f: impl FnMut(T) -> T) -> Matrix<T> {
+        let data: Vec<T> = self.iter().map(|&x| f(x)).collect();
+
+        Matrix::from_slice(self.rows(), self.cols(), &data)
+    }
+
+    fn zip_with(&self, rhs: &Self, f: impl Fn(T, T) -> T) -> Matrix<T> {
+        assert!(
+            self.rows() == rhs.rows() && self.cols() == rhs.cols(),
+            "matrices should have the same dimensions"
+        );
+
+        let data: Vec<T> = self
+            .iter()
+            .zip(rhs.iter())
+            .map(|(&x, &y)| f(x, y))
+            .collect();
+
+        Matrix::from_slice(self.rows(), self.cols(), &data)
+    }
+
+    fn naive_mul(&self, rhs: &Self) -> Matrix<T> {
+        assert_eq!(
+            self.cols(),
+            rhs.rows(),
+            "columns of the left matrix should match rows of the right one"
+        );
+
+        let mut c = Matrix::new(self.rows(), rhs.cols());
+
+        // i-k-j order walks both `rhs` and `c` along rows
+        for i in 0..self.rows() {
+            let a = self.row_slice(i);
+
+            for (k, &x) in a.iter().enumerate() {
+                let b = rhs.row_slice(k);
+
+                for (j, &y) in b.iter().enumerate() {
+                    c[(i, j)] = c[(i, j)] + x * y;
+                }
+            }
+        }
+
+        c
+    }
+}
+
+fn strassen<T: Scalar>(a: MatrixView<T>, b: MatrixView<T>, threshold: usize) -> Matrix<T> {
+    let n = a.rows();
+
+    assert!(
+        a.cols() == n && b.rows() == n && b.cols() == n,
+        "matrices should be square and of the same size"
+    );
+
+    if n <= threshold.max(1) {
+        return a.naive_mul(&b);
+    }
+
+    if n % 2 == 1 {
+        let pad = |m: MatrixView<T>| {
+            let mut p = Matrix::new(n + 1, n + 1);
+
+            for i in 0..n {
+                p.data[i][..n].copy_from_slice(m.row_slice(i));
+            }
+
+            p
+        };
+
+        let c = strassen(pad(a).view(), pad(b).view(), threshold);
+
+        return c.submatrix(0..n, 0..n).to_matrix();
+    }
+
+    let h = n / 2;
+    let [a11, a12, a21, a22] = quadrants(a, h);
+    let [b11, b12, b21, b22] = quadrants(b, h);
+    let rec = |x: &Matrix<T>, y: &Matrix<T>| strassen(x.view(), y.view(), threshold);
+
+    let m1 = rec(&(a11 + a22), &(b11 + b22));
+    let m2 = rec(&(a21 + a22), &b11.to_matrix());
+    let m3 = rec(&a11.to_matrix(), &(b12 - b22));
+    let m4 = rec(&a22.to_matrix(), &(b21 - b11));
+    let m5 = rec(&(a11 + a12), &b22.to_matrix());
+    let m6 = rec(&(a21 - a11), &(b11 + b12));
+    let m7 = rec(&(a12 - a22), &(b21 + b22));
+
+    let c11 = &(&(&m1 + &m4) - &m5) + &m7;
+    let c12 = &m3 + &m5;
+    let c21 = &m2 + &m4;
+    let c22 = &(&(&m1 - &m2) + &m3) + &m6;
+
+    let mut c = Matrix::new(n, n);
+
+    for i in 0..h {
+        c.data[i][..h].copy_from_slice(&c11.data[i]);
+        c.data[i][h..].copy_from_slice(&c12.data[i]);
+        c.data[h + i][..h].copy_from_slice(&c21.data[i]);
+        c.data[h + i][h..].copy_from_slice(&c22.data[i]);
+    }
+
+    c
+}
+
+fn quadrants<T: Scalar>(m: MatrixView<'_, T>, h: usize) -> [MatrixView<'_, T>; 4] {
+    let n = m.rows();
+
+    [
+        m.submatrix(0..h, 0..h),
+        m.submatrix(0..h, h..n),
+        m.submatrix(h..n, 0..h),
+        m.submatrix(h..n, h..n),
+    ]
+}
+
+impl<T> Index<(usize, usize)> for Matrix<T> {
+    type Output = T;
+
+    fn index(&self, (i, j): (usize, usize)) -> &T {
+        &self.data[i][j]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Matrix<T> {
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut T {
+        &mut self.data[i][j]
+    }
+}
+
+impl<T: Scalar> Index<(usize, usize)> for MatrixView<'_, T> {
+    type Output = T;
+
+    fn index(&self, (i, j): (usize, usize)) -> &T {
+        self.get(i, j).expect("index out of bounds")
+    }
+}
+
+impl<T: Scalar> Add for MatrixView<'_, T> {
+    type Output = Matrix<T>;
+
+    fn add(self, rhs: Self) -> Matrix<T> {
+        self.zip_with(&rhs, |x, y| x + y)
+    }
+}
+
+impl<T: Scalar> Sub for MatrixView<'_, T> {
+    type Output = Matrix<T>;
+
+    fn sub(self, rhs: Self) -> Matrix<T> {
+        self.zip_with(&rhs, |x, y| x - y)
+    }
+}
+
+impl<T: Scalar> Mul for MatrixView<'_, T> {
+    type Output = Matrix<T>;
+
+    fn mul(self, rhs: Self) -> Matrix<T> {
+        self.naive_mul(&rhs)
+    }
+}
+
+impl<T: Scalar> Add for &Matrix<T> {
+    type Output = Matrix<T>;
+
+    fn add(self, rhs: Self) -> Matrix<T> {
+        self.view() + rhs.view()
+    }
+}
+
+impl<T: Scalar> Sub for &Matrix<T> {
+    type Output = Matrix<T>;
+
+    fn sub(self, rhs: Self) -> Matrix<T> {
+        self.view() - rhs.view()
+    }
+}
+
+/// Large square matrices of signed or floating point numbers are multiplied with Strassen's
+/// algorithm, everything else directly.
+impl<T: Scalar> Mul for &Matrix<T> {
+    type Output = Matrix<T>;
+
+    fn mul(self, rhs: Self) -> Matrix<T> {
+        let n = self.rows();
+
+        if T::STRASSEN
+            && n > STRASSEN_THRESHOLD
+            && self.is_square()
+            && rhs.rows() == n
+            && rhs.cols() == n
+        {
+            self.strassen(rhs)
+        } else {
+            self.view() * rhs.view()
+        }
+    }
+}
+
+impl<T: Scalar> Mul<T> for &Matrix<T> {
+    type Output = Matrix<T>;
+
+    fn mul(self, k: T) -> Matrix<T> {
+        self.scale(k)
+    }
+}
+
+impl<T: Scalar> Add for Matrix<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        &self + &rhs
+    }
+}
+
+impl<T: Scalar> Sub for Matrix<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        &self - &rhs
+    }
+}
+
+impl<T: Scalar> Mul for Matrix<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        &self * &rhs
+    }
+}
+
+impl<T: Scalar> Mul<T> for Matrix<T> {
+    type Output = Self;
+
+    fn mul(self, k: T) -> Self {
+        self.scale(k)
+    }
+}
+
+impl<T: Scalar> fmt::Debug for Matrix<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.data.rows()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rand::Wyhash64RNG;
+
+    fn random_matrix(rng: &mut Wyhash64RNG, rows: usize, cols: usize) -> Matrix<i64> {
+        let data: Vec<i64> = (0..rows * cols)
+            .map(|_| rng.gen_in_range_i64(-10..10))
+            .collect();
+
+        Matrix::from_slice(rows, cols, &data)
+    }
+
+    #[test]
+    fn arithmetic() {
+        let a = Matrix::from_slice(2, 3, &[1, 2, 3, 4, 5, 6]);
+        let b = Matrix::from_slice(3, 2, &[7, 8, 9, 10, 11, 12]);
+
+        assert_eq!(Matrix::from_slice(2, 2, &[58, 64, 139, 154]), &a * &b);
+        assert_eq!(Matrix::from_slice(2, 3, &[2, 4, 6, 8, 10, 12]), &a + &a);
+        assert_eq!(Matrix::new(2, 3), &a - &a);
+        assert_eq!(&a + &a, &a * 2);
+        assert_eq!(b, a.transpose().scale(1) + (&b - &a.transpose()));
+        assert_eq!(a, &Matrix::identity(2) * &a);
+        assert_eq!(a, &a * &Matrix::identity(3));
+        assert_eq!(a, a.transpose().transpose());
+        assert_eq!(3, a.transpose().rows());
+    }
+
+    #[test]
+    #[should_panic(expected = "columns of the left matrix")]
+    fn dimension_mismatch() {
+        let a = Matrix::<i32>::new(2, 3);
+
+        let _ = &a * &a;
+    }
+
+    #[test]
+    fn views() {
+        let a = Matrix::from_slice(3, 4, &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]);
+
+        let row = a.row(1);
+        assert_eq!((1, 4), (row.rows(), row.cols()));
+        assert_eq!(&[5, 6, 7, 8], row.row_slice(0));
+
+        let col = a.column(2);
+        assert_eq!(vec![3, 7, 11], col.iter().copied().collect::<Vec<_>>());
+
+        let sub = a.submatrix(1..3, 1..3);
+        assert_eq!(Matrix::from_slice(2, 2, &[6, 7, 10, 11]), sub.to_matrix());
+        assert_eq!(11, sub[(1, 1)]);
+        assert_eq!(None, sub.get(2, 0));
+        assert_eq!(Some(&10), sub.column(0).get(1, 0));
+
+        // views combine without copying their operands first
+        let t = a.transpose();
+
+        assert_eq!(Matrix::from_slice(1, 1, &[278]), row * t.column(2));
+        assert_eq!(
+            Matrix::from_slice(2, 2, &[8, 10, 16, 18]),
+            a.submatrix(0..2, 0..2) + a.submatrix(1..3, 2..4)
+        );
+    }
+
+    #[test]
+    fn strassen_small_threshold() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+
+        for n in [1, 2, 3, 5, 8, 13, 16] {
+            let a = random_matrix(&mut rng, n, n);
+            let b = random_matrix(&mut rng, n, n);
+
+            assert_eq!(a.view() * b.view(), strassen(a.view(), b.view(), 1));
+            assert_eq!(a.view() * b.view(), strassen(a.view(), b.view(), 2));
+        }
+    }
+
+    #[test]
+    fn strassen_large() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+        let n = if cfg!(miri) { 10 } else { 150 };
+        let a = random_matrix(&mut rng, n, n);
+        let b = random_matrix(&mut rng, n, n);
+
+        assert_eq!(a.view() * b.view(), &a * &b);
+        assert_eq!(a.view() * b.view(), a.strassen(&b));
+
+        // unsigned products are multiplied directly, as Strassen's differences would underflow
+        let data: Vec<u64> = (0..2 * n * n).map(|_| rng.gen_in_range(0..10)).collect();
+        let c = Matrix::from_slice(n, n, &data[..n * n]);
+        let d = Matrix::from_slice(n, n, &data[n * n..]);
+
+        assert_eq!(c.view() * d.view(), &c * &d);
+
+        let x = Matrix::from_slice(2, 2, &[0.5, 1.0, -2.0, 0.25]);
+        assert_eq!(
+            Matrix::from_slice(2, 2, &[-1.75, 0.75, -1.5, -1.9375]),
+            &x * &x
+        );
+    }
+}
@@ -1,9 +1,12 @@
 pub mod array_2d;
 pub mod avl_tree;
+pub mod b_plus_tree;
+pub mod b_tree;
 pub mod binary_tree;
 pub mod d_ary_heap;
 pub mod diet;
 pub mod doubly_linked_list;
+pub mod fenwick_tree;
 pub mod fib_heap;
 pub mod graphs;
 pub mod hash_table;
@@ -11,9 +14,18 @@ pub mod heap;
 pub mod interval_tree;
 pub mod linked_list;
 pub mod linked_list_unsafe;
+pub mod matrix;
 pub mod os_tree;
 pub mod perfect_hashing;
+pub mod persistent_map;
 pub mod priority_queue;
+pub mod radix_tree;
 pub mod rb_tree;
+pub mod segment_tree;
+pub mod skip_list;
+pub mod splay_tree;
+pub mod suffix_array;
+pub mod treap;
+pub mod trie;
 pub mod veb_tree;
 pub mod y_fast_trie;
@@ -0,0 +1,644 @@
+//! Persistent AVL map: every update returns a new version sharing unchanged subtrees with the
+//! old one through path copying.
+
+use std::cmp::Ordering;
+use std::ptr;
+use std::rc::Rc;
+
+type Link<K, V> = Option<Rc<Node<K, V>>>;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    height: i32,
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+pub struct PersistentMap<K, V> {
+    root: Link<K, V>,
+    len: usize,
+}
+
+/// Difference between two versions of a map, ordered by key.
+#[derive(Debug, PartialEq)]
+pub enum Change<'a, K, V> {
+    Added(&'a K, &'a V),
+    Removed(&'a K, &'a V),
+    Modified(&'a K, &'a V, &'a V),
+}
+
+/// Linear undo/redo history of map versions.
+pub struct History<K, V> {
+    versions: Vec<PersistentMap<K, V>>,
+    current: usize,
+}
+
+fn height<K, V>(link: &Link<K, V>) -> i32 {
+    link.as_ref().map_or(0, |n| n.height)
+}
+
+fn create<K, V>(left: Link<K, V>, key: K, value: V, right: Link<K, V>) -> Rc<Node<K, V>> {
+    let height = 1 + i32::max(height(&left), height(&right));
+
+    Rc::new(Node {
+        key,
+        value,
+        height,
+        left,
+        right,
+    })
+}
+
+// Rebuild a node whose subtrees differ in height by at most two, rotating if needed
+fn balance<K: Clone, V: Clone>(l: Link<K, V>, key: K, value: V, r: Link<K, V>) -> Rc<Node<K, V>> {
+    let hl = height(&l);
+    let hr = height(&r);
+
+    if hl > hr + 1 {
+        let l = l.expect("taller subtree should exist");
+
+        if height(&l.left) >= height(&l.right) {
+            let right = create(l.right.clone(), key, value, r);
+            create(l.left.clone(), l.key.clone(), l.value.clone(), Some(right))
+        } else {
+            let lr = l.right.as_ref().expect("taller subtree should exist");
+            let left = create(
+                l.left.clone(),
+                l.key.clone(),
+                l.value.clone(),
+                lr.left.clone(),
+            );
+            let right = create(lr.right.clone(), key, value, r);
+            create(Some(left), lr.key.clone(), lr.value.clone(), Some(right))
+        }
+    } else if hr > hl + 1 {
+        let r = r.expect("taller subtree should exist");
+
+        if height(&r.right) >= height(&r.left) {
+            let left = create(l, key, value, r.left.clone());
+            create(Some(left), r.key.clone(), r.value.clone(), r.right.clone())
+        } else {
+            let rl = r.left.as_ref().expect("taller subtree should exist");
+            let left = create(l, key, value, rl.left.clone());
+            let right = create(
+                rl.right.clone(),
+                r.key.clone(),
+                r.value.clone(),
+                r.right.clone(),
+            );
+            create(Some(left), rl.key.clone(), rl.value.clone(), Some(right))
+        }
+    } else {
+        create(l, key, value, r)
+    }
+}
+
+fn insert_at<K: Ord + Clone, V: Clone>(
+    link: &Link<K, V>,
+    key: K,
+    value: V,
+    added: &mut bool,
+) -> Rc<Node<K, V>> {
+    let Some(n) = link else {
+        *added = true;
+        return create(None, key, value, None);
+    };
+
+    match key.cmp(&n.key) {
+        Ordering::Less => {
+            let left = insert_at(&n.left, key, value, added);
+            balance(Some(left), n.key.clone(), n.value.clone(), n.right.clone())
+        }
+        Ordering::Greater => {
+            let right = insert_at(&n.right, key, value, added);
+            balance(n.left.clone(), n.key.clone(), n.value.clone(), Some(right))
+        }
+        Ordering::Equal => create(n.left.clone(), key, value, n.right.clone()),
+    }
+}
+
+fn remove_at<K: Ord + Clone, V: Clone>(link: &Link<K, V>, key: &K) -> Option<Link<K, V>> {
+    let n = link.as_ref()?;
+
+    let new = match key.cmp(&n.key) {
+        Ordering::Less => {
+            let left = remove_at(&n.left, key)?;
+            Some(balance(
+                left,
+                n.key.clone(),
+                n.value.clone(),
+                n.right.clone(),
+            ))
+        }
+        Ordering::Greater => {
+            let right = remove_at(&n.right, key)?;
+            Some(balance(
+                n.left.clone(),
+                n.key.clone(),
+                n.value.clone(),
+                right,
+            ))
+        }
+        Ordering::Equal => match (&n.left, &n.right) {
+            (None, r) => r.clone(),
+            (l, None) => l.clone(),
+            (l, r) => {
+                let (k, v, rest) = remove_min(r);
+                Some(balance(l.clone(), k, v, rest))
+            }
+        },
+    };
+
+    Some(new)
+}
+
+fn remove_min<K: Clone, V: Clone>(link: &Link<K, V>) -> (K, V, Link<K, V>) {
+    let n = link.as_ref().expect("subtree should not be empty");
+
+    if n.left.is_none() {
+        return (n.key.clone(), n.value.clone(), n.right.clone());
+    }
+
+    let (k, v, left) = remove_min(&n.left);
+    let node = balance(left, n.key.clone(), n.value.clone(), n.right.clone());
+
+    (k, v, Some(node))
+}
+
+impl<K, V> PersistentMap<K, V> {
+    pub fn new() -> Self {
+        Self { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Entries in ascending key order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            cursor: Cursor::new(&self.root),
+        }
+    }
+
+    /// Whether both versions are the same tree, without comparing any keys.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        match (&self.root, &other.root) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<K: Ord, V> PersistentMap<K, V> {
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let mut cur = &self.root;
+
+        while let Some(n) = cur {
+            match key.cmp(&n.key) {
+                Ordering::Less => cur = &n.left,
+                Ordering::Equal => return Some(&n.value),
+                Ordering::Greater => cur = &n.right,
+            }
+        }
+
+        None
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> PersistentMap<K, V> {
+    /// Returns a new version with `key` mapped to `value`. Only the nodes on the search path are
+    /// copied.
+    #[must_use]
+    pub fn insert(&self, key: K, value: V) -> Self {
+        let mut added = false;
+        let root = insert_at(&self.root, key, value, &mut added);
+
+        Self {
+            root: Some(root),
+            len: self.len + usize::from(added),
+        }
+    }
+
+    /// Returns a new version without `key`, or a cheap copy of `self` if `key` is absent.
+    #[must_use]
+    pub fn remove(&self, key: &K) -> Self {
+        match remove_at(&self.root, key) {
+            Some(root) => Self {
+                root,
+                len: self.len - 1,
+            },
+            None => self.clone(),
+        }
+    }
+}
+
+impl<K: Ord, V: PartialEq> PersistentMap<K, V> {
+    /// Changes needed to turn `self` into `other`. Subtrees shared by both versions are skipped
+    /// without being visited, so diffing two nearby versions is proportional to the number of
+    /// copied nodes rather than to the size of the map.
+    pub fn diff<'a>(&'a self, other: &'a Self) -> Vec<Change<'a, K, V>> {
+        let mut out = vec![];
+        let mut a = Cursor::new(&self.root);
+        let mut b = Cursor::new(&other.root);
+
+        loop {
+            match (a.peek(), b.peek()) {
+                (None, None) => break,
+                (Some(Item::Tree(x)), Some(Item::Tree(y))) if ptr::eq(x, y) => {
+                    a.pop();
+                    b.pop();
+                }
+                (Some(Item::Tree(x)), Some(Item::Tree(y))) => {
+                    if x.height >= y.height {
+                        a.expand_front();
+                    } else {
+                        b.expand_front();
+                    }
+                }
+                (Some(Item::Tree(_)), _) => a.expand_front(),
+                (_, Some(Item::Tree(_))) => b.expand_front(),
+                (Some(Item::Entry(x)), None) => {
+                    out.push(Change::Removed(&x.key, &x.value));
+                    a.pop();
+                }
+                (None, Some(Item::Entry(y))) => {
+                    out.push(Change::Added(&y.key, &y.value));
+                    b.pop();
+                }
+                (Some(Item::Entry(x)), Some(Item::Entry(y))) => match x.key.cmp(&y.key) {
+                    Ordering::Less => {
+                        out.push(Change::Removed(&x.key, &x.value));
+                        a.pop();
+                    }
+                    Ordering::Greater => {
+                        out.push(Change::Added(&y.key, &y.value));
+                        b.pop();
+                    }
+                    Ordering::Equal => {
+                        if x.value != y.value {
+                            out.push(Change::Modified(&x.key, &x.value, &y.value));
+                        }
+
+                        a.pop();
+                        b.pop();
+                    }
+                },
+            }
+        }
+
+        out
+    }
+}
+
+impl<K, V> Clone for PersistentMap<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<K, V> Default for PersistentMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+enum Item<'a, K, V> {
+    Tree(&'a Node<K, V>),
+    Entry(&'a Node<K, V>),
+}
+
+impl<K, V> Clone for Item<'_, K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V> Copy for Item<'_, K, V> {}
+
+// In-order traversal that can hand out whole unvisited subtrees. The front is the top of the
+// stack.
+struct Cursor<'a, K, V> {
+    stack: Vec<Item<'a, K, V>>,
+}
+
+impl<'a, K, V> Cursor<'a, K, V> {
+    fn new(root: &'a Link<K, V>) -> Self {
+        Self {
+            stack: root.as_deref().map(Item::Tree).into_iter().collect(),
+        }
+    }
+
+    fn peek(&self) -> Option<Item<'a, K, V>> {
+        self.stack.last().copied()
+    }
+
+    fn pop(&mut self) -> Option<Item<'a, K, V>> {
+        self.stack.pop()
+    }
+
+    fn expand(&mut self, n: &'a Node<K, V>) {
+        if let Some(r) = n.right.as_deref() {
+            self.stack.push(Item::Tree(r));
+        }
+
+        self.stack.push(Item::Entry(n));
+
+        if let Some(l) = n.left.as_deref() {
+            self.stack.push(Item::Tree(l));
+        }
+    }
+
+    fn expand_front(&mut self) {
+        if let Some(Item::Tree(n)) = self.pop() {
+            self.expand(n);
+        }
+    }
+}
+
+/// In-order iterator over the entries of a version, holding the subtrees still to visit.
+pub struct Iter<'a, K, V> {
+    cursor: Cursor<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(item) = self.cursor.pop() {
+            match item {
+                Item::Tree(n) => self.cursor.expand(n),
+                Item::Entry(n) => return Some((&n.key, &n.value)),
+            }
+        }
+
+        None
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> History<K, V> {
+    pub fn new() -> Self {
+        Self {
+            versions: vec![PersistentMap::new()],
+            current: 0,
+        }
+    }
+
+    pub fn current(&self) -> &PersistentMap<K, V> {
+        &self.versions[self.current]
+    }
+
+    pub fn current_version(&self) -> usize {
+        self.current
+    }
+
+    pub fn num_versions(&self) -> usize {
+        self.versions.len()
+    }
+
+    pub fn version(&self, v: usize) -> Option<&PersistentMap<K, V>> {
+        self.versions.get(v)
+    }
+
+    /// Make `map` the newest version, discarding any versions that could have been redone.
+    pub fn commit(&mut self, map: PersistentMap<K, V>) {
+        self.versions.truncate(self.current + 1);
+        self.versions.push(map);
+        self.current += 1;
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        let map = self.current().insert(key, value);
+        self.commit(map);
+    }
+
+    pub fn remove(&mut self, key: &K) {
+        let map = self.current().remove(key);
+        self.commit(map);
+    }
+
+    /// Returns `false` if already at the oldest version.
+    pub fn undo(&mut self) -> bool {
+        if self.current == 0 {
+            return false;
+        }
+
+        self.current -= 1;
+
+        true
+    }
+
+    /// Returns `false` if already at the newest version.
+    pub fn redo(&mut self) -> bool {
+        if self.current + 1 == self.versions.len() {
+            return false;
+        }
+
+        self.current += 1;
+
+        true
+    }
+}
+
+impl<K: Ord, V: PartialEq> History<K, V> {
+    pub fn diff(&self, from: usize, to: usize) -> Option<Vec<Change<'_, K, V>>> {
+        let a = self.versions.get(from)?;
+        let b = self.versions.get(to)?;
+
+        Some(a.diff(b))
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> Default for History<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashSet};
+
+    use super::*;
+    use crate::rand::Wyhash64RNG;
+
+    const NUM_KEYS: u64 = if cfg!(miri) { 100 } else { 1000 };
+
+    fn check_balanced<K: Ord, V>(link: &Link<K, V>) -> i32 {
+        let Some(n) = link else {
+            return 0;
+        };
+
+        assert!(n.left.as_ref().is_none_or(|l| l.key < n.key));
+        assert!(n.right.as_ref().is_none_or(|r| r.key > n.key));
+
+        let hl = check_balanced(&n.left);
+        let hr = check_balanced(&n.right);
+
+        assert!((hl - hr).abs() <= 1);
+        assert_eq!(n.height, 1 + hl.max(hr));
+
+        n.height
+    }
+
+    fn collect_nodes<K, V>(link: &Link<K, V>, out: &mut HashSet<*const Node<K, V>>) {
+        if let Some(n) = link {
+            out.insert(Rc::as_ptr(n));
+            collect_nodes(&n.left, out);
+            collect_nodes(&n.right, out);
+        }
+    }
+
+    #[test]
+    fn simple() {
+        let v0 = PersistentMap::new();
+        let v1 = v0.insert(1, "one");
+        let v2 = v1.insert(2, "two");
+        let v3 = v2.insert(1, "uno");
+        let v4 = v3.remove(&2);
+
+        assert!(v0.is_empty());
+        assert_eq!(vec![(&1, &"one")], v1.iter().collect::<Vec<_>>());
+        assert_eq!(
+            vec![(&1, &"one"), (&2, &"two")],
+            v2.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![(&1, &"uno"), (&2, &"two")],
+            v3.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(vec![(&1, &"uno")], v4.iter().collect::<Vec<_>>());
+
+        assert_eq!(Some(&"one"), v2.get(&1));
+        assert_eq!(None, v4.get(&2));
+        assert_eq!(2, v3.len());
+        assert_eq!(1, v4.len());
+
+        assert!(v4.remove(&5).ptr_eq(&v4));
+    }
+
+    #[test]
+    fn random() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+        let mut map = PersistentMap::new();
+        let mut reference = BTreeMap::new();
+        let mut snapshots = vec![];
+
+        for i in 0..NUM_KEYS {
+            let key = rng.gen_in_range(0..NUM_KEYS / 2);
+
+            if i % 3 == 0 {
+                map = map.remove(&key);
+                reference.remove(&key);
+            } else {
+                map = map.insert(key, i);
+                reference.insert(key, i);
+            }
+
+            if i % 50 == 0 {
+                snapshots.push((map.clone(), reference.clone()));
+            }
+        }
+
+        check_balanced(&map.root);
+
+        for (map, reference) in snapshots {
+            check_balanced(&map.root);
+
+            assert_eq!(reference.len(), map.len());
+            assert!(reference.iter().eq(map.iter()));
+        }
+    }
+
+    #[test]
+    fn structural_sharing() {
+        let mut map = PersistentMap::new();
+
+        for i in 0..1024 {
+            map = map.insert(i, i);
+        }
+
+        let updated = map.insert(500, 0);
+
+        let mut old_nodes = HashSet::new();
+        let mut new_nodes = HashSet::new();
+
+        collect_nodes(&map.root, &mut old_nodes);
+        collect_nodes(&updated.root, &mut new_nodes);
+
+        let copied = new_nodes.difference(&old_nodes).count();
+
+        assert!(copied <= map.root.as_ref().unwrap().height as usize);
+    }
+
+    #[test]
+    fn diff() {
+        let mut a = PersistentMap::new();
+
+        for i in 0..100 {
+            a = a.insert(i, i);
+        }
+
+        let b = a.insert(10, 0).remove(&20).insert(200, 200).insert(30, 30);
+
+        let expected = vec![
+            Change::Modified(&10, &10, &0),
+            Change::Removed(&20, &20),
+            Change::Added(&200, &200),
+        ];
+
+        assert_eq!(expected, a.diff(&b));
+        assert!(a.diff(&a).is_empty());
+        assert_eq!(100, PersistentMap::new().diff(&a).len());
+    }
+
+    #[test]
+    fn history() {
+        let mut h = History::new();
+
+        h.insert("a", 1);
+        h.insert("b", 2);
+        h.remove(&"a");
+
+        assert_eq!(4, h.num_versions());
+        assert_eq!(vec![(&"b", &2)], h.current().iter().collect::<Vec<_>>());
+
+        assert!(h.undo());
+        assert_eq!(Some(&1), h.current().get(&"a"));
+
+        assert!(h.undo());
+        assert!(h.undo());
+        assert!(!h.undo());
+        assert!(h.current().is_empty());
+
+        assert!(h.redo());
+        assert_eq!(1, h.current().len());
+
+        h.insert("c", 3);
+
+        assert!(!h.redo());
+        assert_eq!(3, h.num_versions());
+        assert_eq!(2, h.current_version());
+
+        let changes = h.diff(0, 2).unwrap();
+
+        assert_eq!(
+            vec![Change::Added(&"a", &1), Change::Added(&"c", &3)],
+            changes
+        );
+        assert_eq!(None, h.diff(0, 5));
+    }
+}
@@ -0,0 +1,390 @@
+//! Radix tree (compressed trie, Patricia tree): chains of single-child nodes are merged into one
+//! edge labelled with a byte string.
+
+pub struct RadixTree<V> {
+    len: usize,
+    nodes: Vec<Node<V>>,
+    recycled: Vec<usize>,
+}
+
+struct Node<V> {
+    label: Vec<u8>,
+    value: Option<V>,
+    // sorted by the first byte of their labels, which are distinct
+    children: Vec<usize>,
+}
+
+const ROOT: usize = 0;
+
+impl<V> Node<V> {
+    fn new(label: Vec<u8>, value: Option<V>) -> Self {
+        Self {
+            label,
+            value,
+            children: Vec::new(),
+        }
+    }
+}
+
+fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+impl<V> RadixTree<V> {
+    pub fn new() -> Self {
+        Self {
+            len: 0,
+            nodes: vec![Node::new(Vec::new(), None)],
+            recycled: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn allocate(&mut self, label: Vec<u8>, value: Option<V>) -> usize {
+        if let Some(idx) = self.recycled.pop() {
+            self.nodes[idx] = Node::new(label, value);
+            return idx;
+        }
+
+        self.nodes.push(Node::new(label, value));
+        self.nodes.len() - 1
+    }
+
+    fn child_pos(&self, x: usize, b: u8) -> Result<usize, usize> {
+        self.nodes[x]
+            .children
+            .binary_search_by_key(&b, |&c| self.nodes[c].label[0])
+    }
+
+    fn child(&self, x: usize, b: u8) -> Option<usize> {
+        let i = self.child_pos(x, b).ok()?;
+
+        Some(self.nodes[x].children[i])
+    }
+
+    /// Insert a value, returning the previous one if the key was already present.
+    pub fn insert(&mut self, key: &[u8], value: V) -> Option<V> {
+        let mut x = ROOT;
+        let mut rest = key;
+
+        while !rest.is_empty() {
+            let i = match self.child_pos(x, rest[0]) {
+                Ok(i) => i,
+                Err(i) => {
+                    let n = self.allocate(rest.to_vec(), Some(value));
+
+                    self.nodes[x].children.insert(i, n);
+                    self.len += 1;
+
+                    return None;
+                }
+            };
+
+            let c = self.nodes[x].children[i];
+            let common = common_prefix_len(&self.nodes[c].label, rest);
+
+            if common < self.nodes[c].label.len() {
+                // split the edge, the new middle node takes over the common part of the label
+                let tail = self.nodes[c].label.split_off(common);
+                let head = std::mem::replace(&mut self.nodes[c].label, tail);
+                let m = self.allocate(head, None);
+
+                self.nodes[m].children.push(c);
+                self.nodes[x].children[i] = m;
+
+                x = m;
+            } else {
+                x = c;
+            }
+
+            rest = &rest[common..];
+        }
+
+        let old = self.nodes[x].value.replace(value);
+
+        if old.is_none() {
+            self.len += 1;
+        }
+
+        old
+    }
+
+    fn find(&self, key: &[u8]) -> Option<usize> {
+        let mut x = ROOT;
+        let mut rest = key;
+
+        while !rest.is_empty() {
+            let c = self.child(x, rest[0])?;
+
+            rest = rest.strip_prefix(self.nodes[c].label.as_slice())?;
+            x = c;
+        }
+
+        Some(x)
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<&V> {
+        self.nodes[self.find(key)?].value.as_ref()
+    }
+
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Remove a key, re-compressing the path around the emptied node.
+    pub fn remove(&mut self, key: &[u8]) -> Option<V> {
+        let mut parent = ROOT;
+        let mut x = ROOT;
+        let mut rest = key;
+
+        while !rest.is_empty() {
+            let c = self.child(x, rest[0])?;
+
+            rest = rest.strip_prefix(self.nodes[c].label.as_slice())?;
+            parent = x;
+            x = c;
+        }
+
+        let old = self.nodes[x].value.take()?;
+
+        self.len -= 1;
+
+        if x == ROOT {
+            return Some(old);
+        }
+
+        match self.nodes[x].children.len() {
+            0 => {
+                let b = self.nodes[x].label[0];
+                let i = self.child_pos(parent, b).expect("node should be linked");
+
+                self.nodes[parent].children.remove(i);
+                self.recycled.push(x);
+
+                if parent != ROOT
+                    && self.nodes[parent].value.is_none()
+                    && self.nodes[parent].children.len() == 1
+                {
+                    self.merge_with_child(parent);
+                }
+            }
+            1 => self.merge_with_child(x),
+            _ => (),
+        }
+
+        Some(old)
+    }
+
+    fn merge_with_child(&mut self, x: usize) {
+        let c = self.nodes[x].children[0];
+
+        let label = std::mem::take(&mut self.nodes[c].label);
+        let value = self.nodes[c].value.take();
+        let children = std::mem::take(&mut self.nodes[c].children);
+
+        self.nodes[x].label.extend(label);
+        self.nodes[x].value = value;
+        self.nodes[x].children = children;
+
+        self.recycled.push(c);
+    }
+
+    /// All keys starting with `prefix` together with their values, in lexicographic order.
+    pub fn with_prefix(&self, prefix: &[u8]) -> Vec<(Vec<u8>, &V)> {
+        let mut out = vec![];
+        let mut key = Vec::with_capacity(prefix.len());
+        let mut x = ROOT;
+        let mut rest = prefix;
+
+        while !rest.is_empty() {
+            let Some(c) = self.child(x, rest[0]) else {
+                return out;
+            };
+
+            let label = &self.nodes[c].label;
+
+            if label.starts_with(rest) {
+                rest = &[];
+            } else if let Some(r) = rest.strip_prefix(label.as_slice()) {
+                rest = r;
+            } else {
+                return out;
+            }
+
+            key.extend_from_slice(label);
+            x = c;
+        }
+
+        self.collect(x, &mut key, &mut out);
+
+        out
+    }
+
+    fn collect<'a>(&'a self, x: usize, key: &mut Vec<u8>, out: &mut Vec<(Vec<u8>, &'a V)>) {
+        if let Some(v) = &self.nodes[x].value {
+            out.push((key.clone(), v));
+        }
+
+        for &c in &self.nodes[x].children {
+            let len = key.len();
+
+            key.extend_from_slice(&self.nodes[c].label);
+            self.collect(c, key, out);
+            key.truncate(len);
+        }
+    }
+
+    /// The value of the longest key that is a prefix of `s`, with the length of that key.
+    pub fn longest_prefix_of(&self, s: &[u8]) -> Option<(usize, &V)> {
+        let mut x = ROOT;
+        let mut consumed = 0;
+        let mut best = self.nodes[ROOT].value.as_ref().map(|v| (0, v));
+
+        while consumed < s.len() {
+            let Some(c) = self.child(x, s[consumed]) else {
+                break;
+            };
+
+            if !s[consumed..].starts_with(&self.nodes[c].label) {
+                break;
+            }
+
+            consumed += self.nodes[c].label.len();
+            x = c;
+
+            if let Some(v) = &self.nodes[x].value {
+                best = Some((consumed, v));
+            }
+        }
+
+        best
+    }
+}
+
+impl<V> Default for RadixTree<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::trie::Trie;
+    use crate::rand::Wyhash64RNG;
+
+    const WORDS: [&str; 9] = [
+        "romane",
+        "romanus",
+        "romulus",
+        "rubens",
+        "ruber",
+        "rubicon",
+        "rubicundus",
+        "rom",
+        "",
+    ];
+
+    fn build() -> RadixTree<usize> {
+        let mut tree = RadixTree::new();
+
+        for (i, w) in WORDS.iter().enumerate() {
+            assert_eq!(None, tree.insert(w.as_bytes(), i));
+        }
+
+        tree
+    }
+
+    // No node other than the root may be an empty pass-through
+    fn check_compressed<V>(tree: &RadixTree<V>, x: usize) -> usize {
+        let node = &tree.nodes[x];
+
+        if x != ROOT {
+            assert!(!node.label.is_empty());
+            assert!(node.value.is_some() || node.children.len() >= 2);
+        }
+
+        let mut count = usize::from(node.value.is_some());
+
+        for &c in &node.children {
+            count += check_compressed(tree, c);
+        }
+
+        count
+    }
+
+    #[test]
+    fn simple() {
+        let tree = build();
+
+        assert_eq!(WORDS.len(), tree.len());
+        assert_eq!(WORDS.len(), check_compressed(&tree, ROOT));
+
+        for (i, w) in WORDS.iter().enumerate() {
+            assert_eq!(Some(&i), tree.get(w.as_bytes()));
+        }
+
+        assert_eq!(None, tree.get(b"roman"));
+        assert_eq!(None, tree.get(b"r"));
+        assert_eq!(None, tree.get(b"rubiconx"));
+    }
+
+    #[test]
+    fn prefix() {
+        let tree = build();
+
+        let keys: Vec<_> = tree.with_prefix(b"rub").into_iter().map(|p| p.0).collect();
+        let expected = ["rubens", "ruber", "rubicon", "rubicundus"].map(|w| w.as_bytes().to_vec());
+
+        assert_eq!(expected.to_vec(), keys);
+        assert_eq!(2, tree.with_prefix(b"roma").len());
+        assert_eq!(WORDS.len(), tree.with_prefix(b"").len());
+        assert!(tree.with_prefix(b"rox").is_empty());
+
+        assert_eq!(Some((7, &5)), tree.longest_prefix_of(b"rubicons"));
+        assert_eq!(Some((3, &7)), tree.longest_prefix_of(b"roman"));
+        assert_eq!(Some((0, &8)), tree.longest_prefix_of(b"zzz"));
+    }
+
+    #[test]
+    fn random_against_trie() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+        let mut tree = RadixTree::new();
+        let mut trie = Trie::new();
+        let mut keys = vec![];
+
+        for i in 0..500 {
+            let len = rng.gen_in_range(0..8) as usize;
+            let key: Vec<u8> = (0..len)
+                .map(|_| b'a' + rng.gen_in_range(0..3) as u8)
+                .collect();
+
+            assert_eq!(trie.insert(&key, i), tree.insert(&key, i));
+
+            keys.push(key);
+        }
+
+        assert_eq!(trie.len(), check_compressed(&tree, ROOT));
+
+        for key in keys.iter().step_by(2) {
+            assert_eq!(trie.remove(key), tree.remove(key));
+            assert_eq!(trie.len(), check_compressed(&tree, ROOT));
+        }
+
+        for prefix in [&b""[..], b"a", b"ab", b"cca"] {
+            assert_eq!(trie.with_prefix(prefix), tree.with_prefix(prefix));
+        }
+
+        for key in &keys {
+            assert_eq!(trie.get(key), tree.get(key));
+            assert_eq!(trie.longest_prefix_of(key), tree.longest_prefix_of(key));
+        }
+    }
+}
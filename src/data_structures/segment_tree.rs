@@ -0,0 +1,580 @@
+//! Segment trees over a monoid: range queries with point updates, and a lazy variant that also
+//! applies updates to whole ranges, all in O(log n)
+
+use std::ops::Range;
+
+/// An associative operation with an identity element. It need not be commutative: ranges are
+/// always combined from left to right.
+pub trait Monoid: Clone {
+    fn identity() -> Self;
+    fn combine(&self, rhs: &Self) -> Self;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sum<T>(pub T);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Min<T>(pub T);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Max<T>(pub T);
+
+/// An update applied to every element of a range, for `LazySegmentTree`.
+pub trait Action<M>: Clone {
+    /// The summary of `len` elements summarized by `m`, after applying `self` to each of them.
+    fn apply(&self, m: &M, len: usize) -> M;
+
+    /// A single action equivalent to `earlier` followed by `self`.
+    fn compose(&self, earlier: &Self) -> Self;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Update<T> {
+    Add(T),
+    Assign(T),
+}
+
+macro_rules! impl_numeric_monoids {
+    ($($t:ty: $lowest:expr, $highest:expr);*) => {$(
+        impl Monoid for Sum<$t> {
+            fn identity() -> Self {
+                Self(0 as $t)
+            }
+
+            fn combine(&self, rhs: &Self) -> Self {
+                Self(self.0 + rhs.0)
+            }
+        }
+
+        impl Monoid for Min<$t> {
+            fn identity() -> Self {
+                Self($highest)
+            }
+
+            fn combine(&self, rhs: &Self) -> Self {
+                if rhs.0 < self.0 { *rhs } else { *self }
+            }
+        }
+
+        impl Monoid for Max<$t> {
+            fn identity() -> Self {
+                Self($lowest)
+            }
+
+            fn combine(&self, rhs: &Self) -> Self {
+                if rhs.0 > self.0 { *rhs } else { *self }
+            }
+        }
+
+        impl Action<Sum<$t>> for Update<$t> {
+            fn apply(&self, m: &Sum<$t>, len: usize) -> Sum<$t> {
+                match *self {
+                    Update::Add(d) => Sum(m.0 + d * len as $t),
+                    Update::Assign(v) => Sum(v * len as $t),
+                }
+            }
+
+            fn compose(&self, earlier: &Self) -> Self {
+                self.after(earlier)
+            }
+        }
+
+        impl Action<Min<$t>> for Update<$t> {
+            fn apply(&self, m: &Min<$t>, _len: usize) -> Min<$t> {
+                match *self {
+                    Update::Add(d) => Min(m.0 + d),
+                    Update::Assign(v) => Min(v),
+                }
+            }
+
+            fn compose(&self, earlier: &Self) -> Self {
+                self.after(earlier)
+            }
+        }
+
+        impl Action<Max<$t>> for Update<$t> {
+            fn apply(&self, m: &Max<$t>, _len: usize) -> Max<$t> {
+                match *self {
+                    Update::Add(d) => Max(m.0 + d),
+                    Update::Assign(v) => Max(v),
+                }
+            }
+
+            fn compose(&self, earlier: &Self) -> Self {
+                self.after(earlier)
+            }
+        }
+    )*};
+}
+
+impl_numeric_monoids!(
+    i32: i32::MIN, i32::MAX;
+    i64: i64::MIN, i64::MAX;
+    isize: isize::MIN, isize::MAX;
+    u32: u32::MIN, u32::MAX;
+    u64: u64::MIN, u64::MAX;
+    usize: usize::MIN, usize::MAX;
+    f32: f32::NEG_INFINITY, f32::INFINITY;
+    f64: f64::NEG_INFINITY, f64::INFINITY
+);
+
+impl<T: Copy + std::ops::Add<Output = T>> Update<T> {
+    fn after(&self, earlier: &Self) -> Self {
+        match (*self, *earlier) {
+            (Update::Add(a), Update::Add(b)) => Update::Add(b + a),
+            (Update::Add(a), Update::Assign(v)) => Update::Assign(v + a),
+            (Update::Assign(v), _) => Update::Assign(v),
+        }
+    }
+}
+
+/// Summary of a range for finding its maximum sum subarray (which is never empty): the total sum
+/// and the best sums of a subarray, a prefix and a suffix. The identity uses `i64::MIN` for the
+/// latter three and sums saturate, so that the empty range never wins.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MaxSubarray {
+    pub sum: i64,
+    pub best: i64,
+    pub prefix: i64,
+    pub suffix: i64,
+}
+
+impl MaxSubarray {
+    pub fn new(x: i64) -> Self {
+        Self {
+            sum: x,
+            best: x,
+            prefix: x,
+            suffix: x,
+        }
+    }
+}
+
+impl Monoid for MaxSubarray {
+    fn identity() -> Self {
+        Self {
+            sum: 0,
+            best: i64::MIN,
+            prefix: i64::MIN,
+            suffix: i64::MIN,
+        }
+    }
+
+    fn combine(&self, rhs: &Self) -> Self {
+        let across = self.suffix.saturating_add(rhs.prefix);
+
+        Self {
+            sum: self.sum.saturating_add(rhs.sum),
+            best: self.best.max(rhs.best).max(across),
+            prefix: self.prefix.max(self.sum.saturating_add(rhs.prefix)),
+            suffix: rhs.suffix.max(rhs.sum.saturating_add(self.suffix)),
+        }
+    }
+}
+
+/// Bottom-up segment tree: the leaves are `tree[n..2n]`, and `tree[i]` combines its children
+/// `tree[2i]` and `tree[2i + 1]`.
+pub struct SegmentTree<M> {
+    n: usize,
+    tree: Vec<M>,
+}
+
+impl<M: Monoid> SegmentTree<M> {
+    /// A tree of `n` identity elements.
+    pub fn new(n: usize) -> Self {
+        Self {
+            n,
+            tree: vec![M::identity(); 2 * n],
+        }
+    }
+
+    pub fn from_slice(a: &[M]) -> Self {
+        let n = a.len();
+        let mut tree = vec![M::identity(); n];
+
+        tree.extend_from_slice(a);
+
+        for i in (1..n).rev() {
+            tree[i] = tree[2 * i].combine(&tree[2 * i + 1]);
+        }
+
+        Self { n, tree }
+    }
+
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    pub fn get(&self, i: usize) -> &M {
+        assert!(i < self.n, "index out of bounds");
+
+        &self.tree[self.n + i]
+    }
+
+    pub fn set(&mut self, i: usize, value: M) {
+        assert!(i < self.n, "index out of bounds");
+
+        let mut i = self.n + i;
+        self.tree[i] = value;
+
+        while i > 1 {
+            i /= 2;
+            self.tree[i] = self.tree[2 * i].combine(&self.tree[2 * i + 1]);
+        }
+    }
+
+    /// The elements of `range` combined from left to right.
+    pub fn query(&self, range: Range<usize>) -> M {
+        assert!(range.end <= self.n, "range out of bounds");
+
+        let mut left = M::identity();
+        let mut right = M::identity();
+        let mut l = range.start + self.n;
+        let mut r = range.end + self.n;
+
+        // the two accumulators grow inwards so that the order of the elements is kept
+        while l < r {
+            if l % 2 == 1 {
+                left = left.combine(&self.tree[l]);
+                l += 1;
+            }
+
+            if r % 2 == 1 {
+                r -= 1;
+                right = self.tree[r].combine(&right);
+            }
+
+            l /= 2;
+            r /= 2;
+        }
+
+        left.combine(&right)
+    }
+}
+
+/// Segment tree with lazy propagation: an update to a range is stored at the O(log n) nodes that
+/// cover it, and pushed down to their children only when a later operation goes below them.
+pub struct LazySegmentTree<M, A> {
+    n: usize,
+    tree: Vec<M>,
+    lazy: Vec<Option<A>>,
+}
+
+impl<M: Monoid, A: Action<M>> LazySegmentTree<M, A> {
+    pub fn from_slice(a: &[M]) -> Self {
+        let n = a.len();
+        let size = 4 * n.max(1);
+        let mut s = Self {
+            n,
+            tree: vec![M::identity(); size],
+            lazy: vec![None; size],
+        };
+
+        if n > 0 {
+            s.build(1, 0, n, a);
+        }
+
+        s
+    }
+
+    fn build(&mut self, x: usize, lo: usize, hi: usize, a: &[M]) {
+        if hi - lo == 1 {
+            self.tree[x] = a[lo].clone();
+            return;
+        }
+
+        let mid = lo + (hi - lo) / 2;
+
+        self.build(2 * x, lo, mid, a);
+        self.build(2 * x + 1, mid, hi, a);
+        self.pull(x);
+    }
+
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    fn pull(&mut self, x: usize) {
+        self.tree[x] = self.tree[2 * x].combine(&self.tree[2 * x + 1]);
+    }
+
+    fn apply_at(&mut self, x: usize, len: usize, action: &A) {
+        self.tree[x] = action.apply(&self.tree[x], len);
+        self.lazy[x] = Some(match &self.lazy[x] {
+            Some(earlier) => action.compose(earlier),
+            None => action.clone(),
+        });
+    }
+
+    fn push(&mut self, x: usize, lo: usize, mid: usize, hi: usize) {
+        if let Some(action) = self.lazy[x].take() {
+            self.apply_at(2 * x, mid - lo, &action);
+            self.apply_at(2 * x + 1, hi - mid, &action);
+        }
+    }
+
+    /// The elements of `range` combined from left to right.
+    pub fn query(&mut self, range: Range<usize>) -> M {
+        assert!(range.end <= self.n, "range out of bounds");
+
+        if range.start >= range.end {
+            return M::identity();
+        }
+
+        self.query_at(1, 0, self.n, &range)
+    }
+
+    fn query_at(&mut self, x: usize, lo: usize, hi: usize, range: &Range<usize>) -> M {
+        if range.end <= lo || hi <= range.start {
+            return M::identity();
+        }
+
+        if range.start <= lo && hi <= range.end {
+            return self.tree[x].clone();
+        }
+
+        let mid = lo + (hi - lo) / 2;
+
+        self.push(x, lo, mid, hi);
+
+        let left = self.query_at(2 * x, lo, mid, range);
+        let right = self.query_at(2 * x + 1, mid, hi, range);
+
+        left.combine(&right)
+    }
+
+    /// Apply `action` to every element of `range`.
+    pub fn update(&mut self, range: Range<usize>, action: A) {
+        assert!(range.end <= self.n, "range out of bounds");
+
+        if range.start < range.end {
+            self.update_at(1, 0, self.n, &range, &action);
+        }
+    }
+
+    fn update_at(&mut self, x: usize, lo: usize, hi: usize, range: &Range<usize>, action: &A) {
+        if range.end <= lo || hi <= range.start {
+            return;
+        }
+
+        if range.start <= lo && hi <= range.end {
+            self.apply_at(x, hi - lo, action);
+            return;
+        }
+
+        let mid = lo + (hi - lo) / 2;
+
+        self.push(x, lo, mid, hi);
+        self.update_at(2 * x, lo, mid, range, action);
+        self.update_at(2 * x + 1, mid, hi, range, action);
+        self.pull(x);
+    }
+
+    pub fn get(&mut self, i: usize) -> M {
+        self.query(i..i + 1)
+    }
+
+    pub fn set(&mut self, i: usize, value: M) {
+        assert!(i < self.n, "index out of bounds");
+
+        self.set_at(1, 0, self.n, i, value);
+    }
+
+    fn set_at(&mut self, x: usize, lo: usize, hi: usize, i: usize, value: M) {
+        if hi - lo == 1 {
+            self.tree[x] = value;
+            self.lazy[x] = None;
+            return;
+        }
+
+        let mid = lo + (hi - lo) / 2;
+
+        self.push(x, lo, mid, hi);
+
+        if i < mid {
+            self.set_at(2 * x, lo, mid, i, value);
+        } else {
+            self.set_at(2 * x + 1, mid, hi, i, value);
+        }
+
+        self.pull(x);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rand::Wyhash64RNG;
+
+    // Not commutative, to check that ranges are combined in order
+    #[derive(Clone, Debug, PartialEq)]
+    struct Concat(String);
+
+    impl Monoid for Concat {
+        fn identity() -> Self {
+            Self(String::new())
+        }
+
+        fn combine(&self, rhs: &Self) -> Self {
+            Self(self.0.clone() + &rhs.0)
+        }
+    }
+
+    fn random_range(rng: &mut Wyhash64RNG, n: usize) -> Range<usize> {
+        let i = rng.gen_in_range(0..n as u64 + 1) as usize;
+        let j = rng.gen_in_range(0..n as u64 + 1) as usize;
+
+        i.min(j)..i.max(j)
+    }
+
+    fn naive_max_subarray(a: &[i64]) -> i64 {
+        let mut best = i64::MIN;
+
+        for i in 0..a.len() {
+            for j in i + 1..=a.len() {
+                best = best.max(a[i..j].iter().sum());
+            }
+        }
+
+        best
+    }
+
+    #[test]
+    fn simple() {
+        let mut sums = SegmentTree::from_slice(&[1, 2, 3, 4, 5].map(Sum));
+
+        assert_eq!(Sum(9), sums.query(1..4));
+        assert_eq!(Sum(0), sums.query(2..2));
+
+        sums.set(2, Sum(10));
+
+        assert_eq!(Sum(22), sums.query(0..5));
+        assert_eq!(&Sum(10), sums.get(2));
+
+        let mut mins = SegmentTree::<Min<u32>>::new(4);
+
+        assert_eq!(Min(u32::MAX), mins.query(0..4));
+
+        mins.set(3, Min(7));
+        mins.set(1, Min(9));
+
+        assert_eq!(Min(7), mins.query(0..4));
+        assert_eq!(Min(9), mins.query(0..3));
+
+        let words =
+            SegmentTree::from_slice(&["a", "b", "c", "d", "e", "f", "g"].map(|s| Concat(s.into())));
+
+        for start in 0..7 {
+            for end in start..=7 {
+                assert_eq!("abcdefg"[start..end], words.query(start..end).0);
+            }
+        }
+    }
+
+    #[test]
+    fn max_subarray() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+        let n = 60;
+        let mut a: Vec<i64> = (0..n).map(|_| rng.gen_in_range_i64(-100..100)).collect();
+        let mut tree =
+            SegmentTree::from_slice(&a.iter().map(|&x| MaxSubarray::new(x)).collect::<Vec<_>>());
+
+        let negative = SegmentTree::from_slice(&[-5, -3, -4].map(MaxSubarray::new));
+
+        assert_eq!(-3, negative.query(0..3).best);
+        assert_eq!(MaxSubarray::identity(), negative.query(1..1));
+
+        for _ in 0..200 {
+            let i = rng.gen_in_range(0..n as u64) as usize;
+            let x = rng.gen_in_range_i64(-100..100);
+
+            a[i] = x;
+            tree.set(i, MaxSubarray::new(x));
+
+            let range = random_range(&mut rng, n);
+
+            if !range.is_empty() {
+                let summary = tree.query(range.clone());
+
+                assert_eq!(naive_max_subarray(&a[range.clone()]), summary.best);
+                assert_eq!(a[range].iter().sum::<i64>(), summary.sum);
+            }
+        }
+    }
+
+    #[test]
+    fn lazy_random() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+        let n = if cfg!(miri) { 20 } else { 100 };
+        let mut a: Vec<i64> = (0..n).map(|_| rng.gen_in_range_i64(-100..100)).collect();
+        let mut sums = LazySegmentTree::from_slice(&a.iter().map(|&x| Sum(x)).collect::<Vec<_>>());
+        let mut mins = LazySegmentTree::from_slice(&a.iter().map(|&x| Min(x)).collect::<Vec<_>>());
+        let mut maxs = LazySegmentTree::from_slice(&a.iter().map(|&x| Max(x)).collect::<Vec<_>>());
+
+        for _ in 0..1000 {
+            let range = random_range(&mut rng, n);
+            let x = rng.gen_in_range_i64(-100..100);
+
+            match rng.gen_in_range(0..4) {
+                0 => {
+                    a[range.clone()].iter_mut().for_each(|v| *v += x);
+                    sums.update(range.clone(), Update::Add(x));
+                    mins.update(range.clone(), Update::Add(x));
+                    maxs.update(range.clone(), Update::Add(x));
+                }
+                1 => {
+                    a[range.clone()].fill(x);
+                    sums.update(range.clone(), Update::Assign(x));
+                    mins.update(range.clone(), Update::Assign(x));
+                    maxs.update(range.clone(), Update::Assign(x));
+                }
+                2 if !range.is_empty() => {
+                    a[range.start] = x;
+                    sums.set(range.start, Sum(x));
+                    mins.set(range.start, Min(x));
+                    maxs.set(range.start, Max(x));
+                }
+                _ => (),
+            }
+
+            let range = random_range(&mut rng, n);
+            let slice = &a[range.clone()];
+
+            assert_eq!(slice.iter().sum::<i64>(), sums.query(range.clone()).0);
+            assert_eq!(
+                slice.iter().copied().min().unwrap_or(i64::MAX),
+                mins.query(range.clone()).0
+            );
+            assert_eq!(
+                slice.iter().copied().max().unwrap_or(i64::MIN),
+                maxs.query(range).0
+            );
+        }
+
+        for (i, &x) in a.iter().enumerate() {
+            assert_eq!(Sum(x), sums.get(i));
+        }
+    }
+
+    #[test]
+    fn compose() {
+        let mut tree = LazySegmentTree::from_slice(&[0.5, 1.5, 2.0, 4.0].map(Sum));
+
+        tree.update(0..4, Update::Add(1.0));
+        tree.update(1..3, Update::Assign(2.0));
+        tree.update(0..2, Update::Add(0.25));
+
+        assert_eq!(Sum(1.75), tree.get(0));
+        assert_eq!(Sum(2.25), tree.get(1));
+        assert_eq!(Sum(11.0), tree.query(0..4));
+        assert!(LazySegmentTree::<Sum<f64>, Update<f64>>::from_slice(&[]).is_empty());
+    }
+}
@@ -0,0 +1,293 @@
+//! Skip list: a randomized ordered set built from a hierarchy of linked lists
+
+use crate::rand::Wyhash64RNG;
+
+const MAX_LEVEL: usize = 32;
+const NIL: usize = usize::MAX;
+
+pub struct SkipList<T> {
+    head: [usize; MAX_LEVEL],
+    level: usize,
+    len: usize,
+    nodes: Vec<Node<T>>,
+    recycled: Vec<usize>,
+    rng: Wyhash64RNG,
+}
+
+struct Node<T> {
+    key: Option<T>,
+    next: Vec<usize>,
+}
+
+pub struct Iter<'a, T> {
+    list: &'a SkipList<T>,
+    cur: usize,
+}
+
+impl<T: Ord> SkipList<T> {
+    pub fn new() -> Self {
+        Self::with_rng(Wyhash64RNG::new())
+    }
+
+    pub fn from_seed(seed: u64) -> Self {
+        Self::with_rng(Wyhash64RNG::from_seed(seed))
+    }
+
+    fn with_rng(rng: Wyhash64RNG) -> Self {
+        Self {
+            head: [NIL; MAX_LEVEL],
+            level: 1,
+            len: 0,
+            nodes: Vec::new(),
+            recycled: Vec::new(),
+            rng,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn key(&self, x: usize) -> &T {
+        self.nodes[x]
+            .key
+            .as_ref()
+            .expect("live node should have a key")
+    }
+
+    fn next(&self, x: usize, lvl: usize) -> usize {
+        if x == NIL {
+            self.head[lvl]
+        } else {
+            self.nodes[x].next[lvl]
+        }
+    }
+
+    fn set_next(&mut self, x: usize, lvl: usize, to: usize) {
+        if x == NIL {
+            self.head[lvl] = to;
+        } else {
+            self.nodes[x].next[lvl] = to;
+        }
+    }
+
+    // Each level is kept with probability 1/2, so the number of levels is geometric
+    fn random_level(&mut self) -> usize {
+        let bits = self.rng.gen().trailing_ones() as usize;
+
+        (bits + 1).min(MAX_LEVEL)
+    }
+
+    /// For each level, the last node (or `NIL` for the head) whose key is less than `key`.
+    fn predecessors(&self, key: &T) -> [usize; MAX_LEVEL] {
+        let mut update = [NIL; MAX_LEVEL];
+        let mut x = NIL;
+
+        for lvl in (0..self.level).rev() {
+            loop {
+                let n = self.next(x, lvl);
+
+                if n == NIL || self.key(n) >= key {
+                    break;
+                }
+
+                x = n;
+            }
+
+            update[lvl] = x;
+        }
+
+        update
+    }
+
+    pub fn contains(&self, key: &T) -> bool {
+        let update = self.predecessors(key);
+        let n = self.next(update[0], 0);
+
+        n != NIL && self.key(n) == key
+    }
+
+    /// Returns `false` if the key was already present.
+    pub fn insert(&mut self, key: T) -> bool {
+        let update = self.predecessors(&key);
+        let n = self.next(update[0], 0);
+
+        if n != NIL && *self.key(n) == key {
+            return false;
+        }
+
+        let lvl = self.random_level();
+
+        self.level = self.level.max(lvl);
+
+        let node = Node {
+            key: Some(key),
+            next: vec![NIL; lvl],
+        };
+
+        let x = if let Some(idx) = self.recycled.pop() {
+            self.nodes[idx] = node;
+            idx
+        } else {
+            self.nodes.push(node);
+            self.nodes.len() - 1
+        };
+
+        for (l, &prev) in update.iter().enumerate().take(lvl) {
+            self.nodes[x].next[l] = self.next(prev, l);
+            self.set_next(prev, l, x);
+        }
+
+        self.len += 1;
+
+        true
+    }
+
+    /// Returns `false` if the key was not present.
+    pub fn remove(&mut self, key: &T) -> bool {
+        let update = self.predecessors(key);
+        let x = self.next(update[0], 0);
+
+        if x == NIL || self.key(x) != key {
+            return false;
+        }
+
+        for (l, &prev) in update.iter().enumerate().take(self.nodes[x].next.len()) {
+            self.set_next(prev, l, self.nodes[x].next[l]);
+        }
+
+        while self.level > 1 && self.head[self.level - 1] == NIL {
+            self.level -= 1;
+        }
+
+        self.nodes[x].key = None;
+        self.nodes[x].next.clear();
+        self.recycled.push(x);
+        self.len -= 1;
+
+        true
+    }
+
+    pub fn minimum(&self) -> Option<&T> {
+        let x = self.head[0];
+
+        (x != NIL).then(|| self.key(x))
+    }
+
+    /// Iterate over the keys in ascending order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            list: self,
+            cur: self.head[0],
+        }
+    }
+}
+
+impl<'a, T: Ord> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.cur == NIL {
+            return None;
+        }
+
+        let key = self.list.key(self.cur);
+
+        self.cur = self.list.nodes[self.cur].next[0];
+
+        Some(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+
+    const NUM_KEYS: u64 = if cfg!(miri) { 100 } else { 1000 };
+
+    fn check_levels<T: Ord>(list: &SkipList<T>) {
+        assert_eq!(list.iter().count(), list.len());
+
+        for lvl in 0..list.level {
+            let mut x = list.head[lvl];
+            let mut prev: Option<&T> = None;
+
+            while x != NIL {
+                assert!(list.nodes[x].next.len() > lvl);
+                assert!(prev.is_none_or(|p| p < list.key(x)));
+
+                prev = Some(list.key(x));
+                x = list.nodes[x].next[lvl];
+            }
+        }
+    }
+
+    #[test]
+    fn simple() {
+        let mut list = SkipList::from_seed(123);
+
+        assert_eq!(None, list.minimum());
+        assert!(list.insert(5));
+        assert!(list.insert(3));
+        assert!(list.insert(7));
+        assert!(!list.insert(3));
+
+        assert_eq!(3, list.len());
+        assert_eq!(Some(&3), list.minimum());
+        assert!(list.contains(&5));
+        assert!(!list.contains(&4));
+
+        assert!(list.remove(&3));
+        assert!(!list.remove(&3));
+
+        assert_eq!(vec![&5, &7], list.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn random() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+        let mut list = SkipList::from_seed(123);
+        let mut reference = BTreeSet::new();
+
+        for _ in 0..NUM_KEYS {
+            let key = rng.gen_in_range(0..NUM_KEYS);
+
+            assert_eq!(reference.insert(key), list.insert(key));
+        }
+
+        check_levels(&list);
+
+        for _ in 0..NUM_KEYS {
+            let key = rng.gen_in_range(0..NUM_KEYS);
+
+            assert_eq!(reference.contains(&key), list.contains(&key));
+            assert_eq!(reference.remove(&key), list.remove(&key));
+        }
+
+        check_levels(&list);
+
+        assert!(reference.iter().eq(list.iter()));
+    }
+
+    #[test]
+    fn reproducible() {
+        let mut a = SkipList::from_seed(7);
+        let mut b = SkipList::from_seed(7);
+
+        for i in 0..100 {
+            a.insert(i);
+            b.insert(i);
+        }
+
+        let levels_a: Vec<_> = a.nodes.iter().map(|n| n.next.len()).collect();
+        let levels_b: Vec<_> = b.nodes.iter().map(|n| n.next.len()).collect();
+
+        assert_eq!(levels_a, levels_b);
+    }
+}
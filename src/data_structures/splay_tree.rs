@@ -0,0 +1,392 @@
+//! Splay tree: a self-adjusting binary search tree with amortized O(log n) operations
+
+use std::cmp::Ordering;
+
+pub struct SplayTree<T> {
+    root: usize,
+    len: usize,
+    nodes: Vec<Node<T>>,
+    recycled: Vec<usize>,
+}
+
+struct Node<T> {
+    key: Option<T>,
+    left: usize,
+    right: usize,
+    parent: usize,
+}
+
+const NIL: usize = usize::MAX;
+
+impl<T> Node<T> {
+    fn new(key: T, parent: usize) -> Self {
+        Self {
+            key: Some(key),
+            left: NIL,
+            right: NIL,
+            parent,
+        }
+    }
+}
+
+impl<T: Ord> SplayTree<T> {
+    pub fn new() -> Self {
+        Self {
+            root: NIL,
+            len: 0,
+            nodes: Vec::new(),
+            recycled: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn key(&self, x: usize) -> &T {
+        self.nodes[x]
+            .key
+            .as_ref()
+            .expect("live node should have a key")
+    }
+
+    fn allocate(&mut self, key: T, parent: usize) -> usize {
+        if let Some(idx) = self.recycled.pop() {
+            self.nodes[idx] = Node::new(key, parent);
+            return idx;
+        }
+
+        self.nodes.push(Node::new(key, parent));
+        self.nodes.len() - 1
+    }
+
+    /// Returns `false` if the key was already present. The inserted key becomes the root.
+    pub fn insert(&mut self, key: T) -> bool {
+        let mut par = NIL;
+        let mut cur = self.root;
+        let mut ord = Ordering::Equal;
+
+        while cur != NIL {
+            par = cur;
+            ord = key.cmp(self.key(cur));
+
+            cur = match ord {
+                Ordering::Less => self.nodes[cur].left,
+                Ordering::Greater => self.nodes[cur].right,
+                Ordering::Equal => {
+                    self.splay(cur);
+                    return false;
+                }
+            };
+        }
+
+        let x = self.allocate(key, par);
+
+        match ord {
+            _ if par == NIL => self.root = x,
+            Ordering::Less => self.nodes[par].left = x,
+            _ => self.nodes[par].right = x,
+        }
+
+        self.splay(x);
+        self.len += 1;
+
+        true
+    }
+
+    /// Search for a key, splaying the last visited node to the root.
+    pub fn contains(&mut self, key: &T) -> bool {
+        let x = self.find(key);
+
+        x != NIL && self.key(x) == key
+    }
+
+    // Returns the node holding `key` or the last node on the search path, splayed to the root
+    fn find(&mut self, key: &T) -> usize {
+        let mut last = NIL;
+        let mut cur = self.root;
+
+        while cur != NIL {
+            last = cur;
+
+            cur = match key.cmp(self.key(cur)) {
+                Ordering::Less => self.nodes[cur].left,
+                Ordering::Greater => self.nodes[cur].right,
+                Ordering::Equal => break,
+            };
+        }
+
+        if last != NIL {
+            self.splay(last);
+        }
+
+        last
+    }
+
+    /// Returns `false` if the key was not present.
+    pub fn remove(&mut self, key: &T) -> bool {
+        let x = self.find(key);
+
+        if x == NIL || self.key(x) != key {
+            return false;
+        }
+
+        let l = self.nodes[x].left;
+        let r = self.nodes[x].right;
+
+        if l != NIL {
+            self.nodes[l].parent = NIL;
+        }
+
+        if r != NIL {
+            self.nodes[r].parent = NIL;
+        }
+
+        if l == NIL {
+            self.root = r;
+        } else {
+            // the maximum of the left subtree has no right child once splayed
+            self.root = l;
+
+            let mut m = l;
+
+            while self.nodes[m].right != NIL {
+                m = self.nodes[m].right;
+            }
+
+            self.splay(m);
+
+            self.nodes[m].right = r;
+
+            if r != NIL {
+                self.nodes[r].parent = m;
+            }
+        }
+
+        self.nodes[x].key = None;
+        self.recycled.push(x);
+        self.len -= 1;
+
+        true
+    }
+
+    pub fn minimum(&mut self) -> Option<&T> {
+        self.extreme(|n| n.left)
+    }
+
+    pub fn maximum(&mut self) -> Option<&T> {
+        self.extreme(|n| n.right)
+    }
+
+    fn extreme(&mut self, next: impl Fn(&Node<T>) -> usize) -> Option<&T> {
+        let mut x = self.root;
+
+        if x == NIL {
+            return None;
+        }
+
+        while next(&self.nodes[x]) != NIL {
+            x = next(&self.nodes[x]);
+        }
+
+        self.splay(x);
+
+        Some(self.key(x))
+    }
+
+    /// Keys in ascending order. Does not restructure the tree.
+    pub fn keys(&self) -> Vec<&T> {
+        let mut out = Vec::with_capacity(self.len);
+        let mut stack = vec![];
+        let mut cur = self.root;
+
+        while cur != NIL || !stack.is_empty() {
+            while cur != NIL {
+                stack.push(cur);
+                cur = self.nodes[cur].left;
+            }
+
+            let x = stack.pop().expect("stack should not be empty");
+
+            out.push(self.key(x));
+
+            cur = self.nodes[x].right;
+        }
+
+        out
+    }
+
+    fn splay(&mut self, x: usize) {
+        loop {
+            let p = self.nodes[x].parent;
+
+            if p == NIL {
+                break;
+            }
+
+            let g = self.nodes[p].parent;
+
+            if g == NIL {
+                // zig
+                self.rotate(x);
+            } else if (self.nodes[g].left == p) == (self.nodes[p].left == x) {
+                // zig-zig
+                self.rotate(p);
+                self.rotate(x);
+            } else {
+                // zig-zag
+                self.rotate(x);
+                self.rotate(x);
+            }
+        }
+
+        self.root = x;
+    }
+
+    /// Rotate `x` above its parent.
+    fn rotate(&mut self, x: usize) {
+        let p = self.nodes[x].parent;
+        let g = self.nodes[p].parent;
+
+        if self.nodes[p].left == x {
+            let b = self.nodes[x].right;
+
+            self.nodes[p].left = b;
+
+            if b != NIL {
+                self.nodes[b].parent = p;
+            }
+
+            self.nodes[x].right = p;
+        } else {
+            let b = self.nodes[x].left;
+
+            self.nodes[p].right = b;
+
+            if b != NIL {
+                self.nodes[b].parent = p;
+            }
+
+            self.nodes[x].left = p;
+        }
+
+        self.nodes[p].parent = x;
+        self.nodes[x].parent = g;
+
+        if g != NIL {
+            if self.nodes[g].left == p {
+                self.nodes[g].left = x;
+            } else {
+                self.nodes[g].right = x;
+            }
+        }
+    }
+}
+
+impl<T: Ord> Default for SplayTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::rand::Wyhash64RNG;
+
+    const NUM_KEYS: u64 = if cfg!(miri) { 100 } else { 1000 };
+
+    fn check_links<T: Ord>(tree: &SplayTree<T>) {
+        if tree.root != NIL {
+            assert_eq!(NIL, tree.nodes[tree.root].parent);
+        }
+
+        let keys = tree.keys();
+
+        assert_eq!(keys.len(), tree.len());
+        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+
+        for (i, node) in tree.nodes.iter().enumerate() {
+            if node.key.is_none() {
+                continue;
+            }
+
+            for c in [node.left, node.right] {
+                if c != NIL {
+                    assert_eq!(i, tree.nodes[c].parent);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn simple() {
+        let mut tree = SplayTree::new();
+
+        assert!(tree.insert(5));
+        assert!(tree.insert(3));
+        assert!(tree.insert(7));
+        assert!(!tree.insert(3));
+
+        assert_eq!(3, tree.len());
+        assert!(tree.contains(&5));
+        assert!(!tree.contains(&4));
+
+        assert_eq!(Some(&3), tree.minimum());
+        assert_eq!(Some(&7), tree.maximum());
+
+        assert!(tree.remove(&5));
+        assert!(!tree.remove(&5));
+
+        assert_eq!(vec![&3, &7], tree.keys());
+    }
+
+    #[test]
+    fn access_splays_to_root() {
+        let mut tree = SplayTree::new();
+
+        for i in 0..100 {
+            tree.insert(i);
+        }
+
+        assert!(tree.contains(&42));
+        assert_eq!(42, *tree.key(tree.root));
+
+        tree.minimum();
+        assert_eq!(0, *tree.key(tree.root));
+
+        check_links(&tree);
+    }
+
+    #[test]
+    fn random() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+        let mut tree = SplayTree::default();
+        let mut reference = BTreeSet::new();
+
+        for _ in 0..NUM_KEYS {
+            let key = rng.gen_in_range(0..NUM_KEYS);
+
+            assert_eq!(reference.insert(key), tree.insert(key));
+        }
+
+        check_links(&tree);
+
+        for _ in 0..NUM_KEYS {
+            let key = rng.gen_in_range(0..NUM_KEYS);
+
+            assert_eq!(reference.contains(&key), tree.contains(&key));
+            assert_eq!(reference.remove(&key), tree.remove(&key));
+        }
+
+        check_links(&tree);
+
+        assert!(reference.iter().eq(tree.keys()));
+    }
+}
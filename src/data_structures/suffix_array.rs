@@ -0,0 +1,209 @@
+//! Suffix array built by prefix doubling, with the LCP array computed by Kasai's algorithm
+
+use std::cmp::Ordering;
+
+pub struct SuffixArray<'t> {
+    text: &'t [u8],
+    sa: Vec<usize>,
+    lcp: Vec<usize>,
+}
+
+impl<'t> SuffixArray<'t> {
+    pub fn new(text: &'t [u8]) -> Self {
+        let sa = build_suffix_array(text);
+        let lcp = build_lcp_array(text, &sa);
+
+        Self { text, sa, lcp }
+    }
+
+    /// Starting positions of the suffixes of the text, in lexicographic order.
+    pub fn suffixes(&self) -> &[usize] {
+        &self.sa
+    }
+
+    /// `lcp()[i]` is the length of the longest common prefix of suffixes `sa[i - 1]` and `sa[i]`;
+    /// `lcp()[0]` is zero.
+    pub fn lcp(&self) -> &[usize] {
+        &self.lcp
+    }
+
+    /// Every position at which `pattern` occurs in the text, in ascending order.
+    pub fn find_all(&self, pattern: &[u8]) -> Vec<usize> {
+        let (lo, hi) = self.matching_range(pattern);
+        let mut out = self.sa[lo..hi].to_vec();
+
+        out.sort_unstable();
+
+        out
+    }
+
+    pub fn contains(&self, pattern: &[u8]) -> bool {
+        let (lo, hi) = self.matching_range(pattern);
+
+        lo < hi
+    }
+
+    // Suffixes starting with `pattern` form a contiguous block of the suffix array
+    fn matching_range(&self, pattern: &[u8]) -> (usize, usize) {
+        let cmp_prefix = |&s: &usize| {
+            let suffix = &self.text[s..];
+            let n = suffix.len().min(pattern.len());
+
+            suffix[..n].cmp(&pattern[..n]).then(if n < pattern.len() {
+                Ordering::Less
+            } else {
+                Ordering::Equal
+            })
+        };
+
+        let lo = self.sa.partition_point(|s| cmp_prefix(s) == Ordering::Less);
+        let hi = lo + self.sa[lo..].partition_point(|s| cmp_prefix(s) == Ordering::Equal);
+
+        (lo, hi)
+    }
+
+    /// The longest substring occurring at least twice in the text.
+    pub fn longest_repeated_substring(&self) -> &'t [u8] {
+        let best = (1..self.lcp.len()).max_by_key(|&i| (self.lcp[i], usize::MAX - i));
+
+        match best {
+            Some(i) => &self.text[self.sa[i]..self.sa[i] + self.lcp[i]],
+            None => &self.text[..0],
+        }
+    }
+}
+
+// Sort suffixes by their first 2^k characters for increasing k, until all ranks are distinct
+fn build_suffix_array(text: &[u8]) -> Vec<usize> {
+    let n = text.len();
+    let mut sa: Vec<usize> = (0..n).collect();
+    let mut rank: Vec<usize> = text.iter().map(|&b| usize::from(b)).collect();
+    let mut tmp = vec![0; n];
+    let mut k = 1;
+
+    if n <= 1 {
+        return sa;
+    }
+
+    loop {
+        let key = |i: usize| (rank[i], rank.get(i + k).map_or(0, |r| r + 1));
+
+        sa.sort_unstable_by_key(|&i| key(i));
+
+        tmp[sa[0]] = 0;
+
+        for w in 1..n {
+            let diff = usize::from(key(sa[w - 1]) != key(sa[w]));
+            tmp[sa[w]] = tmp[sa[w - 1]] + diff;
+        }
+
+        std::mem::swap(&mut rank, &mut tmp);
+
+        if rank[sa[n - 1]] == n - 1 {
+            break;
+        }
+
+        k *= 2;
+    }
+
+    sa
+}
+
+fn build_lcp_array(text: &[u8], sa: &[usize]) -> Vec<usize> {
+    let n = text.len();
+    let mut rank = vec![0; n];
+    let mut lcp = vec![0; n];
+    let mut h = 0;
+
+    for (i, &s) in sa.iter().enumerate() {
+        rank[s] = i;
+    }
+
+    for i in 0..n {
+        if rank[i] == 0 {
+            h = 0;
+            continue;
+        }
+
+        let j = sa[rank[i] - 1];
+
+        while i + h < n && j + h < n && text[i + h] == text[j + h] {
+            h += 1;
+        }
+
+        lcp[rank[i]] = h;
+
+        h = h.saturating_sub(1);
+    }
+
+    lcp
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rand::Wyhash64RNG;
+
+    fn naive_suffix_array(text: &[u8]) -> Vec<usize> {
+        let mut sa: Vec<usize> = (0..text.len()).collect();
+        sa.sort_by_key(|&i| &text[i..]);
+        sa
+    }
+
+    fn naive_find_all(text: &[u8], pattern: &[u8]) -> Vec<usize> {
+        (0..text.len())
+            .filter(|&i| text[i..].starts_with(pattern))
+            .collect()
+    }
+
+    #[test]
+    fn banana() {
+        let sa = SuffixArray::new(b"banana");
+
+        assert_eq!(&[5, 3, 1, 0, 4, 2], sa.suffixes());
+        assert_eq!(&[0, 1, 3, 0, 0, 2], sa.lcp());
+
+        assert_eq!(vec![1, 3], sa.find_all(b"ana"));
+        assert_eq!(vec![0], sa.find_all(b"banana"));
+        assert!(sa.find_all(b"bananas").is_empty());
+        assert!(sa.contains(b"nan"));
+        assert!(!sa.contains(b"nab"));
+
+        assert_eq!(b"ana", sa.longest_repeated_substring());
+    }
+
+    #[test]
+    fn empty() {
+        let sa = SuffixArray::new(b"");
+
+        assert!(sa.suffixes().is_empty());
+        assert!(sa.find_all(b"a").is_empty());
+        assert!(sa.longest_repeated_substring().is_empty());
+    }
+
+    #[test]
+    fn random() {
+        let mut rng = Wyhash64RNG::from_seed(123);
+
+        for len in [1, 2, 10, 100, 300] {
+            let text: Vec<u8> = (0..len)
+                .map(|_| b'a' + rng.gen_in_range(0..3) as u8)
+                .collect();
+            let sa = SuffixArray::new(&text);
+
+            assert_eq!(naive_suffix_array(&text), sa.suffixes());
+
+            for i in 1..len {
+                let a = &text[sa.suffixes()[i - 1]..];
+                let b = &text[sa.suffixes()[i]..];
+                let l = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+
+                assert_eq!(l, sa.lcp()[i]);
+            }
+
+            for pattern in [&b"a"[..], b"ab", b"cab", b"bbb", b"abcabc"] {
+                assert_eq!(naive_find_all(&text, pattern), sa.find_all(pattern));
+            }
+        }
+    }
+}
@@ -0,0 +1,461 @@
+//! Treap: a randomized binary search tree that is a heap on random priorities.
+//! Nodes are boxed rather than kept in an arena so that `split` and `merge` between two treaps
+//! only relink O(log n) nodes.
+
+use std::cmp::Ordering;
+
+use crate::rand::Wyhash64RNG;
+
+type Link<T> = Option<Box<Node<T>>>;
+
+struct Node<T> {
+    value: T,
+    priority: u64,
+    size: usize,
+    left: Link<T>,
+    right: Link<T>,
+}
+
+/// Ordered set keyed by `T`.
+pub struct Treap<T> {
+    root: Link<T>,
+    rng: Wyhash64RNG,
+}
+
+/// Sequence indexed by position ("implicit keys"), supporting O(log n) insertion, removal,
+/// splitting and concatenation anywhere in the sequence.
+pub struct ImplicitTreap<T> {
+    root: Link<T>,
+    rng: Wyhash64RNG,
+}
+
+impl<T> Node<T> {
+    fn new(value: T, priority: u64) -> Box<Self> {
+        Box::new(Self {
+            value,
+            priority,
+            size: 1,
+            left: None,
+            right: None,
+        })
+    }
+
+    fn update(&mut self) {
+        self.size = 1 + size(&self.left) + size(&self.right);
+    }
+}
+
+fn size<T>(link: &Link<T>) -> usize {
+    link.as_ref().map_or(0, |n| n.size)
+}
+
+/// Concatenate two treaps, every element of `l` is placed before every element of `r`.
+fn merge<T>(l: Link<T>, r: Link<T>) -> Link<T> {
+    match (l, r) {
+        (None, r) => r,
+        (l, None) => l,
+        (Some(mut l), Some(mut r)) => {
+            if l.priority > r.priority {
+                l.right = merge(l.right.take(), Some(r));
+                l.update();
+                Some(l)
+            } else {
+                r.left = merge(Some(l), r.left.take());
+                r.update();
+                Some(r)
+            }
+        }
+    }
+}
+
+/// Split a treap into nodes for which `goes_left` holds and the rest. `goes_left` gets the node
+/// and the number of nodes preceding it in the subtree, and must be monotone in-order.
+fn split_by<T>(
+    link: Link<T>,
+    offset: usize,
+    goes_left: &impl Fn(&T, usize) -> bool,
+) -> (Link<T>, Link<T>) {
+    let Some(mut node) = link else {
+        return (None, None);
+    };
+
+    let pos = offset + size(&node.left);
+
+    if goes_left(&node.value, pos) {
+        let (l, r) = split_by(node.right.take(), pos + 1, goes_left);
+        node.right = l;
+        node.update();
+        (Some(node), r)
+    } else {
+        let (l, r) = split_by(node.left.take(), offset, goes_left);
+        node.left = r;
+        node.update();
+        (l, Some(node))
+    }
+}
+
+fn collect<'a, T>(link: &'a Link<T>, out: &mut Vec<&'a T>) {
+    if let Some(node) = link {
+        collect(&node.left, out);
+        out.push(&node.value);
+        collect(&node.right, out);
+    }
+}
+
+impl<T: Ord> Treap<T> {
+    pub fn new() -> Self {
+        Self::with_rng(Wyhash64RNG::new())
+    }
+
+    pub fn from_seed(seed: u64) -> Self {
+        Self::with_rng(Wyhash64RNG::from_seed(seed))
+    }
+
+    fn with_rng(rng: Wyhash64RNG) -> Self {
+        Self { root: None, rng }
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        let mut cur = &self.root;
+
+        while let Some(node) = cur {
+            match value.cmp(&node.value) {
+                Ordering::Less => cur = &node.left,
+                Ordering::Equal => return true,
+                Ordering::Greater => cur = &node.right,
+            }
+        }
+
+        false
+    }
+
+    /// Returns `false` if the value was already present.
+    pub fn insert(&mut self, value: T) -> bool {
+        if self.contains(&value) {
+            return false;
+        }
+
+        let (l, r) = split_by(self.root.take(), 0, &|v, _| *v < value);
+        let node = Node::new(value, self.rng.gen());
+
+        self.root = merge(merge(l, Some(node)), r);
+
+        true
+    }
+
+    /// Returns `false` if the value was not present.
+    pub fn remove(&mut self, value: &T) -> bool {
+        let (l, rest) = split_by(self.root.take(), 0, &|v, _| v < value);
+        let (mid, r) = split_by(rest, 0, &|v, _| v <= value);
+
+        self.root = merge(l, r);
+
+        mid.is_some()
+    }
+
+    /// Move all values greater or equal to `value` into a new treap.
+    pub fn split_off(&mut self, value: &T) -> Self {
+        let (l, r) = split_by(self.root.take(), 0, &|v, _| v < value);
+
+        self.root = l;
+
+        Self {
+            root: r,
+            rng: Wyhash64RNG::from_seed(self.rng.gen()),
+        }
+    }
+
+    /// Concatenate with a treap whose values are all greater than the values of `self`.
+    pub fn merge(&mut self, other: Self) {
+        assert!(match (self.maximum(), other.minimum()) {
+            (Some(l), Some(r)) => l < r,
+            _ => true,
+        });
+
+        self.root = merge(self.root.take(), other.root);
+    }
+
+    pub fn minimum(&self) -> Option<&T> {
+        let mut node = self.root.as_ref()?;
+
+        while let Some(left) = &node.left {
+            node = left;
+        }
+
+        Some(&node.value)
+    }
+
+    pub fn maximum(&self) -> Option<&T> {
+        let mut node = self.root.as_ref()?;
+
+        while let Some(right) = &node.right {
+            node = right;
+        }
+
+        Some(&node.value)
+    }
+
+    /// Values in ascending order.
+    pub fn values(&self) -> Vec<&T> {
+        let mut out = Vec::with_capacity(self.len());
+        collect(&self.root, &mut out);
+        out
+    }
+}
+
+impl<T> ImplicitTreap<T> {
+    pub fn new() -> Self {
+        Self::with_rng(Wyhash64RNG::new())
+    }
+
+    pub fn from_seed(seed: u64) -> Self {
+        Self::with_rng(Wyhash64RNG::from_seed(seed))
+    }
+
+    fn with_rng(rng: Wyhash64RNG) -> Self {
+        Self { root: None, rng }
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn get(&self, mut idx: usize) -> Option<&T> {
+        let mut cur = &self.root;
+
+        while let Some(node) = cur {
+            let ls = size(&node.left);
+
+            match idx.cmp(&ls) {
+                Ordering::Less => cur = &node.left,
+                Ordering::Equal => return Some(&node.value),
+                Ordering::Greater => {
+                    idx -= ls + 1;
+                    cur = &node.right;
+                }
+            }
+        }
+
+        None
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.insert(self.len(), value);
+    }
+
+    /// Insert `value` so that it ends up at position `idx`.
+    pub fn insert(&mut self, idx: usize, value: T) {
+        assert!(idx <= self.len());
+
+        let (l, r) = split_by(self.root.take(), 0, &|_, pos| pos < idx);
+        let node = Node::new(value, self.rng.gen());
+
+        self.root = merge(merge(l, Some(node)), r);
+    }
+
+    pub fn remove(&mut self, idx: usize) -> Option<T> {
+        if idx >= self.len() {
+            return None;
+        }
+
+        let (l, rest) = split_by(self.root.take(), 0, &|_, pos| pos < idx);
+        let (mid, r) = split_by(rest, 0, &|_, pos| pos < 1);
+
+        self.root = merge(l, r);
+
+        mid.map(|node| node.value)
+    }
+
+    /// Move the elements at positions `idx..` into a new sequence.
+    pub fn split_off(&mut self, idx: usize) -> Self {
+        let (l, r) = split_by(self.root.take(), 0, &|_, pos| pos < idx);
+
+        self.root = l;
+
+        Self {
+            root: r,
+            rng: Wyhash64RNG::from_seed(self.rng.gen()),
+        }
+    }
+
+    /// Append all elements of `other` to the end of the sequence.
+    pub fn append(&mut self, other: Self) {
+        self.root = merge(self.root.take(), other.root);
+    }
+
+    pub fn values(&self) -> Vec<&T> {
+        let mut out = Vec::with_capacity(self.len());
+        collect(&self.root, &mut out);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+
+    const SEED: u64 = 123;
+    const NUM_VALUES: u64 = if cfg!(miri) { 100 } else { 1000 };
+
+    fn check_heap<T: Ord>(link: &Link<T>, low: Option<&T>, high: Option<&T>) -> usize {
+        let Some(node) = link else {
+            return 0;
+        };
+
+        assert!(low.is_none_or(|l| node.value > *l));
+        assert!(high.is_none_or(|h| node.value < *h));
+
+        for child in [&node.left, &node.right].into_iter().flatten() {
+            assert!(child.priority <= node.priority);
+        }
+
+        let ls = check_heap(&node.left, low, Some(&node.value));
+        let rs = check_heap(&node.right, Some(&node.value), high);
+
+        assert_eq!(node.size, 1 + ls + rs);
+
+        node.size
+    }
+
+    #[test]
+    fn simple() {
+        let mut treap = Treap::from_seed(SEED);
+
+        assert!(treap.insert(5));
+        assert!(treap.insert(3));
+        assert!(treap.insert(7));
+        assert!(!treap.insert(5));
+
+        assert_eq!(3, treap.len());
+        assert!(treap.contains(&3));
+        assert!(!treap.contains(&4));
+        assert_eq!(Some(&3), treap.minimum());
+        assert_eq!(Some(&7), treap.maximum());
+
+        assert!(treap.remove(&3));
+        assert!(!treap.remove(&3));
+        assert_eq!(vec![&5, &7], treap.values());
+    }
+
+    #[test]
+    fn random() {
+        let mut rng = Wyhash64RNG::from_seed(SEED);
+        let mut treap = Treap::from_seed(SEED);
+        let mut reference = BTreeSet::new();
+
+        for _ in 0..NUM_VALUES {
+            let value = rng.gen_in_range(0..NUM_VALUES);
+
+            assert_eq!(reference.insert(value), treap.insert(value));
+        }
+
+        check_heap(&treap.root, None, None);
+
+        for _ in 0..NUM_VALUES {
+            let value = rng.gen_in_range(0..NUM_VALUES);
+
+            assert_eq!(reference.remove(&value), treap.remove(&value));
+        }
+
+        check_heap(&treap.root, None, None);
+
+        assert!(reference.iter().eq(treap.values()));
+    }
+
+    #[test]
+    fn split_merge() {
+        let mut treap = Treap::from_seed(SEED);
+
+        for i in 0..100 {
+            treap.insert(i);
+        }
+
+        let upper = treap.split_off(&40);
+
+        check_heap(&treap.root, None, None);
+        check_heap(&upper.root, None, None);
+
+        assert!((0..40).eq(treap.values().into_iter().copied()));
+        assert!((40..100).eq(upper.values().into_iter().copied()));
+
+        treap.merge(upper);
+
+        check_heap(&treap.root, None, None);
+        assert!((0..100).eq(treap.values().into_iter().copied()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn merge_overlapping() {
+        let mut a = Treap::from_seed(SEED);
+        let mut b = Treap::from_seed(SEED);
+
+        a.insert(5);
+        b.insert(3);
+
+        a.merge(b);
+    }
+
+    #[test]
+    fn implicit() {
+        let mut rng = Wyhash64RNG::from_seed(SEED);
+        let mut seq = ImplicitTreap::from_seed(SEED);
+        let mut reference = Vec::new();
+
+        for i in 0..NUM_VALUES {
+            let idx = rng.gen_in_range(0..i + 1) as usize;
+
+            seq.insert(idx, i);
+            reference.insert(idx, i);
+        }
+
+        for i in 0..NUM_VALUES / 2 {
+            let idx = rng.gen_in_range(0..NUM_VALUES - i) as usize;
+
+            assert_eq!(Some(reference.remove(idx)), seq.remove(idx));
+        }
+
+        assert_eq!(None, seq.remove(seq.len()));
+        assert_eq!(reference.len(), seq.len());
+
+        for (i, value) in reference.iter().enumerate() {
+            assert_eq!(Some(value), seq.get(i));
+        }
+
+        assert_eq!(None, seq.get(reference.len()));
+    }
+
+    #[test]
+    fn implicit_rotate() {
+        let mut seq = ImplicitTreap::from_seed(SEED);
+
+        for c in "abcdefgh".chars() {
+            seq.push(c);
+        }
+
+        // cut the middle out and paste it at the front
+        let mut tail = seq.split_off(2);
+        let rest = tail.split_off(3);
+
+        tail.append(seq);
+        tail.append(rest);
+
+        let text: String = tail.values().into_iter().collect();
+
+        assert_eq!("cdeabfgh", text);
+    }
+}
@@ -0,0 +1,272 @@
+//! Byte-level trie mapping byte strings to values
+
+pub struct Trie<V> {
+    len: usize,
+    nodes: Vec<Node<V>>,
+    recycled: Vec<usize>,
+}
+
+struct Node<V> {
+    value: Option<V>,
+    // sorted by byte
+    children: Vec<(u8, usize)>,
+}
+
+const ROOT: usize = 0;
+
+impl<V> Node<V> {
+    fn new() -> Self {
+        Self {
+            value: None,
+            children: Vec::new(),
+        }
+    }
+
+    fn child(&self, b: u8) -> Option<usize> {
+        let i = self.children.binary_search_by_key(&b, |c| c.0).ok()?;
+
+        Some(self.children[i].1)
+    }
+}
+
+impl<V> Trie<V> {
+    pub fn new() -> Self {
+        Self {
+            len: 0,
+            nodes: vec![Node::new()],
+            recycled: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn allocate(&mut self) -> usize {
+        if let Some(idx) = self.recycled.pop() {
+            self.nodes[idx] = Node::new();
+            return idx;
+        }
+
+        self.nodes.push(Node::new());
+        self.nodes.len() - 1
+    }
+
+    /// Insert a value, returning the previous one if the key was already present.
+    pub fn insert(&mut self, key: &[u8], value: V) -> Option<V> {
+        let mut x = ROOT;
+
+        for &b in key {
+            x = match self.nodes[x].children.binary_search_by_key(&b, |c| c.0) {
+                Ok(i) => self.nodes[x].children[i].1,
+                Err(i) => {
+                    let n = self.allocate();
+                    self.nodes[x].children.insert(i, (b, n));
+                    n
+                }
+            };
+        }
+
+        let old = self.nodes[x].value.replace(value);
+
+        if old.is_none() {
+            self.len += 1;
+        }
+
+        old
+    }
+
+    fn find(&self, key: &[u8]) -> Option<usize> {
+        let mut x = ROOT;
+
+        for &b in key {
+            x = self.nodes[x].child(b)?;
+        }
+
+        Some(x)
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<&V> {
+        self.nodes[self.find(key)?].value.as_ref()
+    }
+
+    pub fn get_mut(&mut self, key: &[u8]) -> Option<&mut V> {
+        let x = self.find(key)?;
+        self.nodes[x].value.as_mut()
+    }
+
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Whether any key starts with `prefix`.
+    pub fn starts_with(&self, prefix: &[u8]) -> bool {
+        self.find(prefix)
+            .is_some_and(|x| x != ROOT || !self.is_empty())
+    }
+
+    /// Remove a key and prune the nodes that no longer lead to any value.
+    pub fn remove(&mut self, key: &[u8]) -> Option<V> {
+        let mut path = Vec::with_capacity(key.len());
+        let mut x = ROOT;
+
+        for &b in key {
+            path.push(x);
+            x = self.nodes[x].child(b)?;
+        }
+
+        let old = self.nodes[x].value.take()?;
+
+        self.len -= 1;
+
+        for (&p, &b) in path.iter().zip(key).rev() {
+            if self.nodes[x].value.is_some() || !self.nodes[x].children.is_empty() {
+                break;
+            }
+
+            let i = self.nodes[p]
+                .children
+                .binary_search_by_key(&b, |c| c.0)
+                .expect("child should be linked to its parent");
+
+            self.nodes[p].children.remove(i);
+            self.recycled.push(x);
+
+            x = p;
+        }
+
+        Some(old)
+    }
+
+    /// All keys starting with `prefix` together with their values, in lexicographic order.
+    pub fn with_prefix(&self, prefix: &[u8]) -> Vec<(Vec<u8>, &V)> {
+        let mut out = vec![];
+
+        if let Some(x) = self.find(prefix) {
+            let mut key = prefix.to_vec();
+            self.collect(x, &mut key, &mut out);
+        }
+
+        out
+    }
+
+    fn collect<'a>(&'a self, x: usize, key: &mut Vec<u8>, out: &mut Vec<(Vec<u8>, &'a V)>) {
+        if let Some(v) = &self.nodes[x].value {
+            out.push((key.clone(), v));
+        }
+
+        for &(b, c) in &self.nodes[x].children {
+            key.push(b);
+            self.collect(c, key, out);
+            key.pop();
+        }
+    }
+
+    /// The value of the longest key that is a prefix of `s`, with the length of that key.
+    pub fn longest_prefix_of(&self, s: &[u8]) -> Option<(usize, &V)> {
+        let mut x = ROOT;
+        let mut best = self.nodes[ROOT].value.as_ref().map(|v| (0, v));
+
+        for (i, &b) in s.iter().enumerate() {
+            let Some(c) = self.nodes[x].child(b) else {
+                break;
+            };
+
+            x = c;
+
+            if let Some(v) = &self.nodes[x].value {
+                best = Some((i + 1, v));
+            }
+        }
+
+        best
+    }
+}
+
+impl<V> Default for Trie<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORDS: [&str; 8] = ["a", "to", "tea", "ted", "ten", "i", "in", "inn"];
+
+    fn build() -> Trie<usize> {
+        let mut trie = Trie::new();
+
+        for (i, w) in WORDS.iter().enumerate() {
+            assert_eq!(None, trie.insert(w.as_bytes(), i));
+        }
+
+        trie
+    }
+
+    #[test]
+    fn simple() {
+        let mut trie = build();
+
+        assert_eq!(WORDS.len(), trie.len());
+
+        for (i, w) in WORDS.iter().enumerate() {
+            assert_eq!(Some(&i), trie.get(w.as_bytes()));
+        }
+
+        assert_eq!(None, trie.get(b"te"));
+        assert_eq!(None, trie.get(b"tea!"));
+        assert!(trie.starts_with(b"te"));
+        assert!(!trie.starts_with(b"x"));
+
+        assert_eq!(Some(2), trie.insert(b"tea", 20));
+        *trie.get_mut(b"tea").unwrap() += 1;
+        assert_eq!(Some(&21), trie.get(b"tea"));
+    }
+
+    #[test]
+    fn prefix() {
+        let trie = build();
+
+        let keys: Vec<_> = trie.with_prefix(b"te").into_iter().map(|p| p.0).collect();
+        assert_eq!(
+            vec![b"tea".to_vec(), b"ted".to_vec(), b"ten".to_vec()],
+            keys
+        );
+
+        assert_eq!(WORDS.len(), trie.with_prefix(b"").len());
+        assert!(trie.with_prefix(b"q").is_empty());
+
+        assert_eq!(Some((2, &6)), trie.longest_prefix_of(b"into"));
+        assert_eq!(Some((3, &7)), trie.longest_prefix_of(b"inn"));
+        assert_eq!(None, trie.longest_prefix_of(b"xyz"));
+    }
+
+    #[test]
+    fn remove() {
+        let mut trie = build();
+
+        assert_eq!(Some(6), trie.remove(b"in"));
+        assert_eq!(None, trie.remove(b"in"));
+        assert_eq!(None, trie.remove(b"te"));
+        assert_eq!(Some(&7), trie.get(b"inn"));
+
+        assert_eq!(Some(7), trie.remove(b"inn"));
+        assert!(trie.starts_with(b"i"));
+        assert!(!trie.starts_with(b"in"));
+
+        for w in WORDS {
+            trie.remove(w.as_bytes());
+        }
+
+        assert!(trie.is_empty());
+        assert!(!trie.starts_with(b""));
+        assert!(trie.nodes[ROOT].children.is_empty());
+        assert_eq!(trie.nodes.len() - 1, trie.recycled.len());
+    }
+}
@@ -14,6 +14,60 @@ pub fn test_sort(f: impl Fn(&mut [u64])) {
     }
 }
 
+// Non-`Copy` element for testing the `_by` and `_by_key` sorts: `name` holds the original position
+#[derive(Debug)]
+pub struct Record {
+    pub key: u64,
+    pub name: String,
+}
+
+pub fn test_sort_records(f: impl Fn(&mut [Record])) {
+    test_sort_records_impl(f, false);
+}
+
+pub fn test_stable_sort_records(f: impl Fn(&mut [Record])) {
+    test_sort_records_impl(f, true);
+}
+
+fn test_sort_records_impl(f: impl Fn(&mut [Record]), stable: bool) {
+    let arrays = generate_test_arrays(TEST_ARRAY_LEN);
+
+    for (desc, arr) in arrays {
+        println!("Array: {}", desc);
+
+        let mut records: Vec<Record> = arr
+            .iter()
+            .enumerate()
+            .map(|(i, &key)| Record {
+                key,
+                name: i.to_string(),
+            })
+            .collect();
+
+        f(&mut records);
+
+        let keys: Vec<u64> = records.iter().map(|r| r.key).collect();
+        let mut names: Vec<usize> = records.iter().map(|r| r.name.parse().unwrap()).collect();
+
+        assert_sorted(&keys);
+
+        if stable {
+            for w in records.windows(2) {
+                if w[0].key == w[1].key {
+                    assert!(w[0].name.parse::<usize>().unwrap() < w[1].name.parse().unwrap());
+                }
+            }
+        }
+
+        for r in &records {
+            assert_eq!(arr[r.name.parse::<usize>().unwrap()], r.key);
+        }
+
+        names.sort_unstable();
+        assert!(names.iter().copied().eq(0..arr.len()));
+    }
+}
+
 pub fn generate_test_arrays(n: usize) -> Vec<(&'static str, Vec<u64>)> {
     vec![
         ("empty", vec![]),